@@ -1,5 +1,6 @@
 #pragma once
 
+#include "fs.h"
 #include "string.h"
 #include "util.h"
 #include "assert.h"
@@ -13,6 +14,10 @@ enum {
     PAWKIT_INPUT_MOUSEAXIS_DELTA_Y,
     PAWKIT_INPUT_MOUSEAXIS_WHEEL_X,
     PAWKIT_INPUT_MOUSEAXIS_WHEEL_Y,
+    PAWKIT_INPUT_MOUSEAXIS_X,
+    PAWKIT_INPUT_MOUSEAXIS_Y,
+    PAWKIT_INPUT_MOUSEAXIS_NORMALIZED_X,
+    PAWKIT_INPUT_MOUSEAXIS_NORMALIZED_Y,
 };
 typedef pawkit_u8 pawkit_input_mouseaxis_t;
 
@@ -23,9 +28,25 @@ enum {
     PAWKIT_INPUT_JOYAXIS_RIGHT_Y,
     PAWKIT_INPUT_JOYAXIS_LEFT_TRIGGER,
     PAWKIT_INPUT_JOYAXIS_RIGHT_TRIGGER,
+    PAWKIT_INPUT_JOYAXIS_GYRO_X,
+    PAWKIT_INPUT_JOYAXIS_GYRO_Y,
+    PAWKIT_INPUT_JOYAXIS_GYRO_Z,
+    PAWKIT_INPUT_JOYAXIS_ACCEL_X,
+    PAWKIT_INPUT_JOYAXIS_ACCEL_Y,
+    PAWKIT_INPUT_JOYAXIS_ACCEL_Z,
 };
 typedef pawkit_u8 pawkit_input_joyaxis_t;
 
+enum {
+    PAWKIT_INPUT_TOUCHAXIS_X,
+    PAWKIT_INPUT_TOUCHAXIS_Y,
+    PAWKIT_INPUT_TOUCHAXIS_PRESSURE,
+    PAWKIT_INPUT_TOUCHAXIS_PINCH,
+    PAWKIT_INPUT_TOUCHAXIS_PAN_X,
+    PAWKIT_INPUT_TOUCHAXIS_PAN_Y,
+};
+typedef pawkit_u8 pawkit_input_touchaxis_t;
+
 typedef pawkit_u8 pawkit_input_axis_t;
 
 enum {
@@ -117,21 +138,54 @@ enum {
 };
 typedef pawkit_u8 pawkit_input_joybutton_t;
 
+enum {
+    PAWKIT_INPUT_TOUCHBUTTON_TOUCH,
+    PAWKIT_INPUT_TOUCHBUTTON_MULTI_TOUCH,
+    PAWKIT_INPUT_TOUCHBUTTON_TAP,
+    PAWKIT_INPUT_TOUCHBUTTON_SWIPE_LEFT,
+    PAWKIT_INPUT_TOUCHBUTTON_SWIPE_RIGHT,
+    PAWKIT_INPUT_TOUCHBUTTON_SWIPE_UP,
+    PAWKIT_INPUT_TOUCHBUTTON_SWIPE_DOWN,
+};
+typedef pawkit_u8 pawkit_input_touchbutton_t;
+
 typedef pawkit_u8 pawkit_input_button_t;
 
 enum {
     PAWKIT_INPUT_FAMILY_KEY,
     PAWKIT_INPUT_FAMILY_MOUSE,
     PAWKIT_INPUT_FAMILY_JOY,
+    PAWKIT_INPUT_FAMILY_TOUCH,
+    /// Keyboard bindings to the key printed on a key, rather than its position.
+    PAWKIT_INPUT_FAMILY_KEY_LOGICAL,
+    /// Buttons and axes of a virtual device, by index.
+    PAWKIT_INPUT_FAMILY_VIRTUAL,
 };
 typedef pawkit_u8 pawkit_input_family_t;
 
+enum {
+    PAWKIT_INPUT_BINDING_TYPE_DIGITAL,
+    PAWKIT_INPUT_BINDING_TYPE_ANALOG,
+    PAWKIT_INPUT_BINDING_TYPE_VECTOR,
+};
+typedef pawkit_u8 pawkit_input_binding_type_t;
+
+enum {
+    PAWKIT_INPUT_CAPTURE_STATUS_PENDING,
+    PAWKIT_INPUT_CAPTURE_STATUS_CANCELLED,
+    PAWKIT_INPUT_CAPTURE_STATUS_CAPTURED,
+};
+typedef pawkit_u8 pawkit_input_capture_status_t;
+
 enum {
     PAWKIT_INPUT_BOUND_BUTTON_TYPE_DIGITAL,
     PAWKIT_INPUT_BOUND_BUTTON_TYPE_ANALOG,
+    PAWKIT_INPUT_BOUND_BUTTON_TYPE_CHORD,
 };
 typedef pawkit_u8 pawkit_input_bound_button_type_t;
 
+#define PAWKIT_INPUT_CHORD_MAX_MODIFIERS 4
+
 typedef struct pawkit_input_bound_button_t {
     pawkit_input_bound_button_type_t type;
     union {
@@ -140,6 +194,11 @@ typedef struct pawkit_input_bound_button_t {
             pawkit_input_axis_t axis;
             pawkit_f32 threshold;
         };
+        struct {
+            pawkit_input_button_t trigger;
+            pawkit_u8 modifier_count;
+            pawkit_input_button_t modifiers[PAWKIT_INPUT_CHORD_MAX_MODIFIERS];
+        };
     };
 } pawkit_input_bound_button_t;
 
@@ -162,6 +221,38 @@ typedef struct pawkit_input_bound_axis_t {
     };
 } pawkit_input_bound_axis_t;
 
+enum {
+    PAWKIT_INPUT_DEADZONE_SHAPE_AXIAL,
+    PAWKIT_INPUT_DEADZONE_SHAPE_RADIAL,
+    PAWKIT_INPUT_DEADZONE_SHAPE_SCALED_RADIAL,
+};
+typedef pawkit_u8 pawkit_input_deadzone_shape_t;
+
+enum {
+    PAWKIT_INPUT_RESPONSE_CURVE_TYPE_LINEAR,
+    PAWKIT_INPUT_RESPONSE_CURVE_TYPE_POWER,
+    PAWKIT_INPUT_RESPONSE_CURVE_TYPE_EXPONENTIAL,
+    PAWKIT_INPUT_RESPONSE_CURVE_TYPE_PIECEWISE,
+};
+typedef pawkit_u8 pawkit_input_response_curve_type_t;
+
+#define PAWKIT_INPUT_MAX_CURVE_POINTS 8
+
+typedef struct pawkit_input_curve_point_t {
+    pawkit_f32 x;
+    pawkit_f32 y;
+} pawkit_input_curve_point_t;
+
+typedef struct pawkit_input_response_curve_t {
+    pawkit_input_response_curve_type_t type;
+    /// The exponent for power curves, which must be above zero, or the strength for exponential curves.
+    pawkit_f32 parameter;
+    /// Only used by piecewise curves, which can have up to PAWKIT_INPUT_MAX_CURVE_POINTS points.
+    /// The points are copied, ownership is retained by the caller.
+    pawkit_input_curve_point_t const *points;
+    size_t point_count;
+} pawkit_input_response_curve_t;
+
 typedef struct pawkit_input_digital_binding_t {
     pawkit_input_family_t family;
     pawkit_input_bound_button_t binding;
@@ -172,6 +263,10 @@ typedef struct pawkit_input_analog_binding_t {
     pawkit_input_bound_axis_t binding;
     pawkit_f32 deadzone;
     pawkit_f32 scale;
+    pawkit_input_deadzone_shape_t deadzone_shape;
+    /// Values <= 0 disable the outer deadzone.
+    pawkit_f32 outer_deadzone;
+    pawkit_input_response_curve_t curve;
 } pawkit_input_analog_binding_t;
 
 typedef struct pawkit_input_vector_binding_t {
@@ -181,17 +276,37 @@ typedef struct pawkit_input_vector_binding_t {
     pawkit_f32 deadzone;
     pawkit_f32 scale_x;
     pawkit_f32 scale_y;
+    pawkit_input_deadzone_shape_t deadzone_shape;
+    /// Values <= 0 disable the outer deadzone.
+    pawkit_f32 outer_deadzone;
+    pawkit_input_response_curve_t curve;
+    /// When set, the binding only produces a value while the ratchet is held on the same device.
+    bool has_ratchet;
+    pawkit_input_digital_binding_t ratchet;
 } pawkit_input_vector_binding_t;
 
 typedef struct pawkit_input_binding_map *pawkit_input_binding_map_t;
 typedef struct pawkit_input_state *pawkit_input_state_t;
 typedef struct pawkit_input_manager *pawkit_input_manager_t;
+typedef struct pawkit_input_recording *pawkit_input_recording_t;
+typedef struct pawkit_input_replay *pawkit_input_replay_t;
+typedef struct pawkit_input_capture *pawkit_input_capture_t;
+typedef struct pawkit_input_mappings *pawkit_input_mappings_t;
+typedef struct pawkit_input_mapping const *pawkit_input_mapping_t;
+typedef struct pawkit_input_action_layout *pawkit_input_action_layout_t;
+typedef struct pawkit_input_remote_manager *pawkit_input_remote_manager_t;
 
 typedef union pawkit_input_frame_t {
     struct {
         bool value;
         bool just_pressed;
         bool just_released;
+        bool held;
+        bool long_pressed;
+        bool tapped;
+        bool double_tapped;
+        bool repeated;
+        bool buffered;
     } digital;
     
     struct {
@@ -207,6 +322,20 @@ typedef union pawkit_input_frame_t {
     } vector;
 } pawkit_input_frame_t;
 
+/// All durations are in seconds. Values <= 0 disable the interaction.
+typedef struct pawkit_input_digital_interactions_t {
+    pawkit_f32 hold;
+    pawkit_f32 tap;
+    pawkit_f32 double_tap;
+    pawkit_f32 repeat_delay;
+    /// Repeats per second.
+    pawkit_f32 repeat_rate;
+    /// Keeps a press buffered for this many frames after it happens.
+    /// Takes priority over buffer_milliseconds. 0 disables it.
+    pawkit_u32 buffer_frames;
+    pawkit_f32 buffer_milliseconds;
+} pawkit_input_digital_interactions_t;
+
 enum {
     PAWKIT_INPUT_ERROR_OK,
     PAWKIT_INPUT_ERROR_INVALID_STRING,
@@ -218,6 +347,99 @@ typedef struct pawkit_device_id {
     pawkit_u8 state[16];
 } pawkit_device_id_t;
 
+typedef struct pawkit_input_touch_contact_t {
+    /// Identifies the finger for as long as it's on the screen.
+    pawkit_u64 id;
+    /// Normalized to the screen, from 0 to 1, with y pointing down.
+    pawkit_f32 position[2];
+    pawkit_f32 pressure;
+} pawkit_input_touch_contact_t;
+
+/// Distances are normalized to the screen, like the contact positions.
+typedef struct pawkit_input_touch_settings_t {
+    pawkit_f32 tap_distance;
+    pawkit_f32 swipe_distance;
+    /// A finger held down for more frames than this isn't a tap, even if it didn't move.
+    pawkit_u32 max_tap_frames;
+} pawkit_input_touch_settings_t;
+
+typedef struct pawkit_input_motion_sample_t {
+    /// Microseconds. Only the difference between samples matters.
+    pawkit_u64 timestamp;
+    /// Radians per second.
+    pawkit_f32 gyro[3];
+    /// Meters per second squared.
+    pawkit_f32 accel[3];
+} pawkit_input_motion_sample_t;
+
+typedef struct pawkit_input_gyro_calibration_t {
+    pawkit_f32 offset[3];
+    /// Values <= 0 disable drift compensation.
+    pawkit_f32 rest_threshold;
+    pawkit_f32 drift_rate;
+} pawkit_input_gyro_calibration_t;
+
+/// Motor strengths, from 0 to 1.
+typedef struct pawkit_input_haptic_output_t {
+    pawkit_f32 low_frequency;
+    pawkit_f32 high_frequency;
+    pawkit_f32 left_trigger;
+    pawkit_f32 right_trigger;
+} pawkit_input_haptic_output_t;
+
+/// Fades in over attack, holds, then fades out over release. Times are in seconds.
+typedef struct pawkit_input_haptic_envelope_t {
+    pawkit_f32 attack;
+    pawkit_f32 hold;
+    pawkit_f32 release;
+} pawkit_input_haptic_envelope_t;
+
+enum {
+    PAWKIT_INPUT_CONTROLLER_STYLE_GENERIC,
+    PAWKIT_INPUT_CONTROLLER_STYLE_XBOX,
+    PAWKIT_INPUT_CONTROLLER_STYLE_PLAYSTATION,
+    PAWKIT_INPUT_CONTROLLER_STYLE_NINTENDO,
+};
+typedef pawkit_u8 pawkit_input_controller_style_t;
+
+typedef struct pawkit_input_glyph_t {
+    /// A stable identifier such as "xbox_a", for looking up an icon or a translated name.
+    /// Static, and not null terminated.
+    char const *id;
+    size_t id_len;
+    /// The English name. Static, and not null terminated.
+    char const *name;
+    size_t name_len;
+} pawkit_input_glyph_t;
+
+enum {
+    PAWKIT_INPUT_TEXT_EVENT_COMMIT,
+    PAWKIT_INPUT_TEXT_EVENT_COMPOSITION_START,
+    PAWKIT_INPUT_TEXT_EVENT_COMPOSITION_UPDATE,
+    PAWKIT_INPUT_TEXT_EVENT_COMPOSITION_END,
+};
+typedef pawkit_u8 pawkit_input_text_event_type_t;
+
+typedef struct pawkit_input_text_event_t {
+    pawkit_input_text_event_type_t type;
+    /// UTF-8, owned by the caller and freed with pawkit_free_string.
+    /// NULL for composition start and end events.
+    char const *text;
+    size_t len;
+    /// The cursor or selection of a composition update, as a byte range into text.
+    size_t cursor_start;
+    size_t cursor_end;
+} pawkit_input_text_event_t;
+
+typedef struct pawkit_input_device_change_t {
+    pawkit_device_id_t device;
+    pawkit_input_family_t family;
+    /// `previous` and `previous_family` are only set when there was a device before.
+    bool has_previous;
+    pawkit_device_id_t previous;
+    pawkit_input_family_t previous_family;
+} pawkit_input_device_change_t;
+
 pawkit_input_binding_map_t pawkit_input_binding_map_create();
 
 void pawkit_input_binding_map_destroy(pawkit_input_binding_map_t map);
@@ -269,6 +491,8 @@ pawkit_device_id_t pawkit_input_state_connect_mouse(pawkit_input_state_t state);
 
 pawkit_device_id_t pawkit_input_state_connect_gamepad(pawkit_input_state_t state);
 
+pawkit_device_id_t pawkit_input_state_connect_touch(pawkit_input_state_t state);
+
 void pawkit_input_state_disconnect_device(
     pawkit_input_state_t state,
     pawkit_device_id_t device
@@ -288,6 +512,244 @@ void pawkit_input_state_set_axis(
     pawkit_f32 value
 );
 
+/// Adds a finger to a touch device, or moves one that's already on it.
+bool pawkit_input_state_set_touch_contact(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_touch_contact_t contact
+);
+
+bool pawkit_input_state_remove_touch_contact(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_u64 id
+);
+
+bool pawkit_input_state_set_touch_settings(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_touch_settings_t settings
+);
+
+/// Gyro axes accumulate the rotation between samples until the next frame.
+bool pawkit_input_state_set_gamepad_motion(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_motion_sample_t sample
+);
+
+bool pawkit_input_state_get_gyro_calibration(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_gyro_calibration_t *calibration
+);
+
+bool pawkit_input_state_set_gyro_calibration(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_gyro_calibration_t calibration
+);
+
+/// The gamepad should be left resting until the calibration ends.
+bool pawkit_input_state_begin_gyro_calibration(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device
+);
+
+/// Returns false if no calibration was running, or no samples arrived during it.
+bool pawkit_input_state_end_gyro_calibration(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_gyro_calibration_t *calibration
+);
+
+/// The size of the window the cursor is in, which the normalized axes are relative to.
+bool pawkit_input_state_set_mouse_viewport(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_f32 width,
+    pawkit_f32 height
+);
+
+/// Only tracks whether the cursor should be locked, the platform layer has to actually lock it.
+bool pawkit_input_state_set_cursor_locked(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    bool locked
+);
+
+bool pawkit_input_state_is_cursor_locked(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device
+);
+
+/// Sets which labels a gamepad's buttons have, for showing glyphs.
+bool pawkit_input_state_set_controller_style(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_controller_style_t style
+);
+
+pawkit_input_controller_style_t pawkit_input_state_get_controller_style(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device
+);
+
+/// Replaces the layout of a keyboard, for logical bindings. `logical[i]` is the key printed on `physical[i]`,
+/// and keys that aren't listed print what they would on US QWERTY.
+bool pawkit_input_state_set_keyboard_layout(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_keybutton_t const *physical,
+    pawkit_input_keybutton_t const *logical,
+    size_t len
+);
+
+/// The key printed on a physical key.
+pawkit_input_keybutton_t pawkit_input_state_get_logical_key(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_keybutton_t physical
+);
+
+/// While text input is active, keyboard bindings only see Escape and Enter.
+bool pawkit_input_state_set_text_input_active(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    bool active
+);
+
+bool pawkit_input_state_is_text_input_active(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device
+);
+
+/// The text events are ignored while text input isn't active. All strings are UTF-8.
+bool pawkit_input_state_commit_text(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    char const *cstr,
+    size_t len
+);
+
+bool pawkit_input_state_start_composition(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device
+);
+
+/// The cursor is a byte range into the text, and has to land on character boundaries.
+bool pawkit_input_state_update_composition(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    char const *cstr,
+    size_t len,
+    size_t cursor_start,
+    size_t cursor_end
+);
+
+bool pawkit_input_state_end_composition(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device
+);
+
+/// Takes the oldest queued text event. Returns false once the queue is empty, or if `event` is null.
+bool pawkit_input_state_poll_text_event(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_input_text_event_t *event
+);
+
+bool pawkit_input_state_play_rumble(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_f32 low_frequency,
+    pawkit_f32 high_frequency,
+    pawkit_input_haptic_envelope_t envelope
+);
+
+bool pawkit_input_state_play_trigger_rumble(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_f32 left,
+    pawkit_f32 right,
+    pawkit_input_haptic_envelope_t envelope
+);
+
+/// Plays any effect, including patterns, described as JSON.
+bool pawkit_input_state_play_haptic_json(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    char const *cstr,
+    size_t len
+);
+
+bool pawkit_input_state_stop_haptics(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device
+);
+
+/// Called by the platform layer once per frame for each gamepad.
+/// Returns false if the motors should be left alone.
+bool pawkit_input_state_drain_haptics(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_f32 delta_time,
+    pawkit_input_haptic_output_t *output
+);
+
+/// Marks the end of a frame, for recording, mouse deltas and single frame gestures.
+/// Call it once per frame after updating every input manager.
+void pawkit_input_state_next_frame(pawkit_input_state_t state);
+
+void pawkit_input_state_start_recording(pawkit_input_state_t state);
+
+/// Returns NULL if the state wasn't recording.
+pawkit_input_recording_t pawkit_input_state_stop_recording(pawkit_input_state_t state);
+
+/// Returns NULL if the buffer isn't a valid recording.
+pawkit_input_recording_t pawkit_input_recording_load(pawkit_vfs_buffer_t buf);
+
+pawkit_vfs_buffer_t pawkit_input_recording_save(pawkit_input_recording_t recording);
+
+void pawkit_input_recording_destroy(pawkit_input_recording_t recording);
+
+/// Takes ownership of the recording.
+pawkit_input_replay_t pawkit_input_replay_create(pawkit_input_recording_t recording);
+
+void pawkit_input_replay_destroy(pawkit_input_replay_t replay);
+
+/// Applies the events of the current frame, and moves on to the next one.
+void pawkit_input_replay_step(pawkit_input_replay_t replay, pawkit_input_state_t state);
+
+bool pawkit_input_replay_is_finished(pawkit_input_replay_t replay);
+
+/// Waits for the next button press or axis movement on any device, for rebinding.
+/// Threshold and deadzone values <= 0 use the defaults.
+/// With `logical_keys`, keys are captured by what's printed on them instead of their position.
+pawkit_input_capture_t pawkit_input_capture_begin(
+    pawkit_input_state_t state,
+    pawkit_input_binding_type_t type,
+    pawkit_f32 threshold,
+    pawkit_f32 deadzone,
+    pawkit_input_digital_binding_t const *cancel,
+    size_t cancel_len,
+    bool logical_keys
+);
+
+void pawkit_input_capture_destroy(pawkit_input_capture_t capture);
+
+pawkit_input_capture_status_t pawkit_input_capture_poll(
+    pawkit_input_capture_t capture,
+    pawkit_input_state_t state
+);
+
+/// Adds the captured binding to the manager.
+bool pawkit_input_capture_apply(
+    pawkit_input_capture_t capture,
+    pawkit_input_manager_t manager,
+    pawkit_string_t name
+);
+
 pawkit_input_manager_t pawkit_input_manager_create(
     pawkit_input_binding_map_t map
 );
@@ -306,7 +768,90 @@ void pawkit_input_manager_disconnect_device(
 
 void pawkit_input_manager_update(
     pawkit_input_manager_t manager,
-    pawkit_input_state_t state
+    pawkit_input_state_t state,
+    pawkit_f32 delta_time
+);
+
+enum {
+    PAWKIT_INPUT_CONSUMPTION_PASSTHROUGH,
+    PAWKIT_INPUT_CONSUMPTION_BOUND,
+    PAWKIT_INPUT_CONSUMPTION_ALL,
+};
+typedef pawkit_u8 pawkit_input_consumption_t;
+
+/// Registers a named context with its own bindings, which sees input once it's pushed.
+/// Takes ownership of the binding map.
+bool pawkit_input_manager_add_context(
+    pawkit_input_manager_t manager,
+    pawkit_string_t name,
+    pawkit_input_binding_map_t map,
+    pawkit_i32 priority,
+    pawkit_input_consumption_t consumption
+);
+
+bool pawkit_input_manager_remove_context(
+    pawkit_input_manager_t manager,
+    pawkit_string_t name
+);
+
+/// Active contexts with a higher priority see input first, and can hide it from lower contexts.
+bool pawkit_input_manager_push_context(
+    pawkit_input_manager_t manager,
+    pawkit_string_t name
+);
+
+/// Deactivates the most recently pushed context.
+bool pawkit_input_manager_pop_context(pawkit_input_manager_t manager);
+
+bool pawkit_input_manager_deactivate_context(
+    pawkit_input_manager_t manager,
+    pawkit_string_t name
+);
+
+bool pawkit_input_manager_is_context_active(
+    pawkit_input_manager_t manager,
+    pawkit_string_t name
+);
+
+bool pawkit_input_manager_set_interactions(
+    pawkit_input_manager_t manager,
+    pawkit_string_t name,
+    pawkit_input_digital_interactions_t interactions
+);
+
+/// Uses up a buffered press, returning whether there was one.
+bool pawkit_input_manager_consume(
+    pawkit_input_manager_t manager,
+    pawkit_string_t name
+);
+
+/// Plays on the device that last drove the action, or the device the player last used.
+bool pawkit_input_manager_play_rumble(
+    pawkit_input_manager_t manager,
+    pawkit_input_state_t state,
+    pawkit_string_t name,
+    pawkit_f32 low_frequency,
+    pawkit_f32 high_frequency,
+    pawkit_input_haptic_envelope_t envelope
+);
+
+bool pawkit_input_manager_play_haptic_json(
+    pawkit_input_manager_t manager,
+    pawkit_input_state_t state,
+    pawkit_string_t name,
+    char const *cstr,
+    size_t len
+);
+
+/// Gets glyph `index` of binding `binding` of an action, for the device the player last used.
+/// Returns false once either index is past the end.
+bool pawkit_input_manager_get_glyph(
+    pawkit_input_manager_t manager,
+    pawkit_input_state_t state,
+    pawkit_string_t name,
+    size_t binding,
+    size_t index,
+    pawkit_input_glyph_t *glyph
 );
 
 bool pawkit_input_manager_get(
@@ -351,6 +896,241 @@ bool pawkit_input_manager_remove_vector_binding(
     pawkit_input_vector_binding_t binding
 );
 
+pawkit_input_mappings_t pawkit_input_mappings_create();
+
+void pawkit_input_mappings_destroy(pawkit_input_mappings_t mappings);
+
+/// Adds the mappings in the text of an SDL `gamecontrollerdb.txt` file for the current platform.
+/// Returns how many were added. Malformed lines are skipped.
+size_t pawkit_input_mappings_parse(pawkit_input_mappings_t mappings, char const *cstr, size_t len);
+
+bool pawkit_input_mappings_load(
+    pawkit_input_mappings_t mappings,
+    pawkit_vfs_t vfs,
+    char const *path,
+    size_t path_len
+);
+
+/// Returns a generic mapping if the device isn't in the database.
+/// The mapping is only valid until the database is changed or destroyed.
+pawkit_input_mapping_t pawkit_input_mappings_get(
+    pawkit_input_mappings_t mappings,
+    pawkit_u8 const (*guid)[16]
+);
+
+/// The string is owned by the mapping, and isn't null terminated.
+char const *pawkit_input_mapping_get_name(pawkit_input_mapping_t mapping, size_t *len);
+
+/// Updates a gamepad from a raw button index.
+void pawkit_input_mapping_set_button(
+    pawkit_input_mapping_t mapping,
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_u32 button,
+    bool value
+);
+
+/// Updates a gamepad from a raw hat, with a bit for each held direction: 1 up, 2 right, 4 down, 8 left.
+void pawkit_input_mapping_set_hat(
+    pawkit_input_mapping_t mapping,
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_u32 hat,
+    pawkit_u8 value
+);
+
+/// Updates a gamepad from a raw axis, between -1 and 1.
+void pawkit_input_mapping_set_axis(
+    pawkit_input_mapping_t mapping,
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_u32 axis,
+    pawkit_f32 value
+);
+
+/// Every action in the manager, sorted by name. Both sides of a connection need the same layout.
+pawkit_input_action_layout_t pawkit_input_action_layout_create(pawkit_input_manager_t manager);
+
+void pawkit_input_action_layout_destroy(pawkit_input_action_layout_t layout);
+
+/// Encodes the manager's current frames. Precision values <= 0 use the defaults.
+/// The snapshot is freed with `pawkit_free_array`.
+pawkit_u8 *pawkit_input_snapshot_encode(
+    pawkit_input_action_layout_t layout,
+    pawkit_input_manager_t manager,
+    pawkit_f32 analog_precision,
+    pawkit_f32 vector_precision,
+    size_t *len
+);
+
+/// The layout is copied. The precision values have to match the ones the snapshots were encoded with.
+pawkit_input_remote_manager_t pawkit_input_remote_manager_create(
+    pawkit_input_action_layout_t layout,
+    pawkit_f32 analog_precision,
+    pawkit_f32 vector_precision
+);
+
+void pawkit_input_remote_manager_destroy(pawkit_input_remote_manager_t manager);
+
+/// Returns false, leaving the frames alone, if the snapshot doesn't match the layout.
+bool pawkit_input_remote_manager_apply(
+    pawkit_input_remote_manager_t manager,
+    pawkit_u8 const *snapshot,
+    size_t len
+);
+
+bool pawkit_input_remote_manager_get(
+    pawkit_input_remote_manager_t manager,
+    pawkit_string_t name,
+    pawkit_input_frame_t *frame
+);
+
+/// Keeps the inputs of the last `length` ticks for rollback. A length of 0 turns the history off.
+void pawkit_input_manager_set_history_length(pawkit_input_manager_t manager, size_t length);
+
+/// How many ticks have been applied, which is also the number of the next tick.
+pawkit_u64 pawkit_input_manager_get_tick(pawkit_input_manager_t manager);
+
+/// The ticks still in the history, from `start` up to but not including `end`.
+void pawkit_input_manager_get_history_range(
+    pawkit_input_manager_t manager,
+    pawkit_u64 *start,
+    pawkit_u64 *end
+);
+
+/// Overwrites what an action read on a past tick, such as when a remote input arrives late.
+/// Rewind to the tick to replay it.
+bool pawkit_input_manager_set_tick_input_digital(
+    pawkit_input_manager_t manager,
+    pawkit_u64 tick,
+    pawkit_string_t name,
+    bool pressed
+);
+
+bool pawkit_input_manager_set_tick_input_analog(
+    pawkit_input_manager_t manager,
+    pawkit_u64 tick,
+    pawkit_string_t name,
+    pawkit_f32 value
+);
+
+bool pawkit_input_manager_set_tick_input_vector(
+    pawkit_input_manager_t manager,
+    pawkit_u64 tick,
+    pawkit_string_t name,
+    pawkit_f32 x,
+    pawkit_f32 y
+);
+
+/// Puts every frame back to how it was just before the given tick.
+bool pawkit_input_manager_rewind(pawkit_input_manager_t manager, pawkit_u64 tick);
+
+/// Applies the next tick after a rewind. Returns false once the manager has caught up.
+bool pawkit_input_manager_replay_tick(pawkit_input_manager_t manager);
+
+/// Adds an action with the bindings it has in `map`, which is only read from.
+/// `context` may be null for the bindings the manager was created with.
+/// Returns false if the action already exists, or isn't in the map.
+bool pawkit_input_manager_register_action(
+    pawkit_input_manager_t manager,
+    pawkit_string_t context,
+    pawkit_input_binding_map_t map,
+    pawkit_string_t name
+);
+
+/// Replaces every binding of an action with the ones it has in `map`.
+/// The action keeps its frame unless it changes kind.
+bool pawkit_input_manager_replace_action(
+    pawkit_input_manager_t manager,
+    pawkit_string_t context,
+    pawkit_input_binding_map_t map,
+    pawkit_string_t name
+);
+
+bool pawkit_input_manager_unregister_action(
+    pawkit_input_manager_t manager,
+    pawkit_string_t context,
+    pawkit_string_t name
+);
+
+/// Connects a device with any number of buttons and axes, driven from code.
+/// Its buttons and axes are set by index with `pawkit_input_state_set_button` and `pawkit_input_state_set_axis`.
+pawkit_device_id_t pawkit_input_state_connect_virtual(
+    pawkit_input_state_t state,
+    pawkit_u8 buttons,
+    pawkit_u8 axes
+);
+
+/// Drives an action directly from a virtual device, in place of whatever its bindings read.
+bool pawkit_input_state_set_virtual_action_digital(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_string_t name,
+    bool pressed
+);
+
+bool pawkit_input_state_set_virtual_action_analog(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_string_t name,
+    pawkit_f32 value
+);
+
+bool pawkit_input_state_set_virtual_action_vector(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_string_t name,
+    pawkit_f32 x,
+    pawkit_f32 y
+);
+
+/// Hands an action back to the device's bindings.
+bool pawkit_input_state_clear_virtual_action(
+    pawkit_input_state_t state,
+    pawkit_device_id_t device,
+    pawkit_string_t name
+);
+
+/// Gets the device that most recently drove an action, and its family, for switching prompts between keyboard and controller.
+/// Returns false if no device has been used yet.
+bool pawkit_input_manager_get_last_device(
+    pawkit_input_manager_t manager,
+    pawkit_device_id_t *device,
+    pawkit_input_family_t *family
+);
+
+/// How strongly, from 0 to 1, another device has to drive an action to take over as the last device.
+/// Defaults to 0.5, so a slightly drifting stick doesn't switch prompts away from the keyboard.
+void pawkit_input_manager_set_device_switch_threshold(
+    pawkit_input_manager_t manager,
+    pawkit_f32 threshold
+);
+
+/// Takes the oldest change of the last device. Returns false once there are none left.
+bool pawkit_input_manager_poll_device_change(
+    pawkit_input_manager_t manager,
+    pawkit_input_device_change_t *change
+);
+
+enum {
+    PAWKIT_INPUT_AGGREGATION_MAX_MAGNITUDE,
+    PAWKIT_INPUT_AGGREGATION_SUM_CLAMP,
+    PAWKIT_INPUT_AGGREGATION_MOST_RECENT,
+    PAWKIT_INPUT_AGGREGATION_DEVICE,
+};
+typedef pawkit_u8 pawkit_input_aggregation_t;
+
+/// Sets how an action combines the values of several devices. Actions default to the value furthest from zero.
+/// `device` is only read for PAWKIT_INPUT_AGGREGATION_DEVICE, where only that device drives the action.
+/// `context` may be null for the bindings the manager was created with.
+bool pawkit_input_manager_set_aggregation(
+    pawkit_input_manager_t manager,
+    pawkit_string_t context,
+    pawkit_string_t name,
+    pawkit_input_aggregation_t aggregation,
+    pawkit_device_id_t device
+);
+
 #ifdef __cplusplus
 }
 #endif
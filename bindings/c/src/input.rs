@@ -1,35 +1,141 @@
-use std::{ffi::c_char, fmt::Debug, mem::forget, ptr::null_mut};
+use std::{
+    ffi::c_char,
+    fmt::Debug,
+    mem::forget,
+    ptr::{null, null_mut},
+};
 
 use num_enum::TryFromPrimitive;
+use pawkit_fs::{Vfs, VfsBuffer};
 use pawkit_input::{
     DeviceId,
+    aggregation::DeviceAggregation,
     binding::{
-        AnalogBinding, AnalogBindingKind, BindingList, BoundAxis, BoundButton, DigitalBinding,
-        VectorBinding, VectorBindingKind,
-        axis::{GamepadAxis, MouseAxis},
-        button::{GamepadButton, KeyboardButton, MouseButton},
+        AnalogBinding, AnalogBindingKind, BindingKind, BindingList, BoundAxis, BoundButton,
+        ChordModifiers, DigitalBinding, VectorBinding, VectorBindingKind,
+        axis::{GamepadAxis, MouseAxis, VirtualAxis},
+        button::{GamepadButton, KeyboardButton, MouseButton, VirtualButton},
+        curve::ResponseCurve,
+        deadzone::DeadzoneShape,
         map::BindingMap,
     },
+    capture::{CaptureOptions, CaptureResult, CapturedBinding, InputCapture},
+    context::{ContextOptions, InputConsumption},
+    glyph::ControllerStyle,
+    haptics::{HapticEffect, HapticEnvelope, HapticOutput},
+    history::ActionValue,
+    interaction::{DigitalInteractions, InputBuffer, RepeatInteraction},
+    layout::KeyboardLayout,
     manager::{InputManager, RawInputFrame},
+    mapping::{GamepadGuid, GamepadMapping, GamepadMappings},
+    motion::{GyroCalibration, MotionSample},
+    recording::{InputRecording, InputReplay},
+    snapshot::{ActionLayout, RemoteInputManager, SnapshotOptions},
     state::{InputFamily, InputState},
+    text::{TextComposition, TextEvent},
+    touch::{TouchContact, TouchSettings},
+    virtual_device::VirtualLayout,
 };
 use pawkit_interner::InternString;
 use serde::Serialize;
 
 use crate::{
-    c_enum, cstr_to_str, drop_from_heap, move_to_heap, move_to_stack, ptr_to_ref, ptr_to_ref_mut,
-    ptr_to_slice, set_if_valid, str_to_cstr,
+    c_enum, cstr_to_str, disown_str_to_cstr, drop_from_heap, move_slice_to_heap, move_to_heap,
+    move_to_stack, ptr_to_ref, ptr_to_ref_mut, ptr_to_slice, set_if_valid, str_to_cstr,
 };
 
+c_enum!(CTextEventType: u8 {
+    TEXT_EVENT_COMMIT,
+    TEXT_EVENT_COMPOSITION_START,
+    TEXT_EVENT_COMPOSITION_UPDATE,
+    TEXT_EVENT_COMPOSITION_END,
+});
+
+#[repr(C)]
+struct CTextEvent {
+    kind: CTextEventType,
+    text: *const c_char,
+    len: usize,
+    cursor_start: usize,
+    cursor_end: usize,
+}
+
+/// Glyph strings are static, and aren't null terminated.
+#[repr(C)]
+struct CInputGlyph {
+    id: *const c_char,
+    id_len: usize,
+    name: *const c_char,
+    name_len: usize,
+}
+
+c_enum!(CInputConsumption: u8 {
+    INPUT_CONSUMPTION_PASSTHROUGH,
+    INPUT_CONSUMPTION_BOUND,
+    INPUT_CONSUMPTION_ALL,
+});
+
+c_enum!(CDeviceAggregation: u8 {
+    INPUT_AGGREGATION_MAX_MAGNITUDE,
+    INPUT_AGGREGATION_SUM_CLAMP,
+    INPUT_AGGREGATION_MOST_RECENT,
+    INPUT_AGGREGATION_DEVICE,
+});
+
 c_enum!(CInputFamily: u8 {
     INPUT_FAMILY_KEY,
     INPUT_FAMILY_MOUSE,
     INPUT_FAMILY_JOY,
+    INPUT_FAMILY_TOUCH,
+    INPUT_FAMILY_KEY_LOGICAL,
+    INPUT_FAMILY_VIRTUAL,
 });
 
+/// `previous` and `previous_family` are only meaningful when `has_previous` is set.
+#[repr(C)]
+struct CDeviceChange {
+    device: DeviceId,
+    family: CInputFamily,
+    has_previous: bool,
+    previous: DeviceId,
+    previous_family: CInputFamily,
+}
+
+fn c_family(family: InputFamily) -> CInputFamily {
+    return match family {
+        InputFamily::Keyboard => INPUT_FAMILY_KEY,
+        InputFamily::Mouse => INPUT_FAMILY_MOUSE,
+        InputFamily::Gamepad => INPUT_FAMILY_JOY,
+        InputFamily::Touch => INPUT_FAMILY_TOUCH,
+        InputFamily::Virtual => INPUT_FAMILY_VIRTUAL,
+    };
+}
+
 c_enum!(CBoundButtonKind: u8 {
     BOUND_BUTTON_TYPE_DIGITAL,
     BOUND_BUTTON_TYPE_ANALOG,
+    BOUND_BUTTON_TYPE_CHORD,
+});
+
+const CHORD_MAX_MODIFIERS: usize = 4;
+
+c_enum!(CResponseCurveKind: u8 {
+    RESPONSE_CURVE_TYPE_LINEAR,
+    RESPONSE_CURVE_TYPE_POWER,
+    RESPONSE_CURVE_TYPE_EXPONENTIAL,
+    RESPONSE_CURVE_TYPE_PIECEWISE,
+});
+
+c_enum!(CBindingKind: u8 {
+    BINDING_TYPE_DIGITAL,
+    BINDING_TYPE_ANALOG,
+    BINDING_TYPE_VECTOR,
+});
+
+c_enum!(CCaptureStatus: u8 {
+    CAPTURE_STATUS_PENDING,
+    CAPTURE_STATUS_CANCELLED,
+    CAPTURE_STATUS_CAPTURED,
 });
 
 c_enum!(CBoundAxisKind: u8 {
@@ -47,12 +153,14 @@ struct CBoundButton {
 
 type CButton = u8;
 type CAxis = u8;
+type CDeadzoneShape = u8;
 
 #[repr(C)]
 #[derive(Clone, Copy)]
 union CBoundButtonUnion {
     button: CButton,
     analog: CAnalogSpec,
+    chord: CChordSpec,
 }
 
 #[repr(C)]
@@ -62,6 +170,14 @@ struct CAnalogSpec {
     threshold: f32,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CChordSpec {
+    trigger: CButton,
+    modifier_count: u8,
+    modifiers: [CButton; CHORD_MAX_MODIFIERS],
+}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 struct CBoundAxis {
@@ -84,6 +200,22 @@ struct CMultiDigitalAxis {
     positive: CButton,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CCurvePoint {
+    x: f32,
+    y: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CResponseCurve {
+    kind: CResponseCurveKind,
+    parameter: f32,
+    points: *const CCurvePoint,
+    point_count: usize,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 struct CDigitalBinding {
@@ -98,6 +230,9 @@ struct CAnalogBinding {
     binding: CBoundAxis,
     deadzone: f32,
     scale: f32,
+    deadzone_shape: CDeadzoneShape,
+    outer_deadzone: f32,
+    curve: CResponseCurve,
 }
 
 #[repr(C)]
@@ -109,14 +244,25 @@ struct CVectorBinding {
     deadzone: f32,
     scale_x: f32,
     scale_y: f32,
+    deadzone_shape: CDeadzoneShape,
+    outer_deadzone: f32,
+    curve: CResponseCurve,
+    has_ratchet: bool,
+    ratchet: CDigitalBinding,
 }
 
 unsafe fn convert_bound_button<TButton, TAxis>(
     button: CBoundButton,
 ) -> Option<BoundButton<TButton, TAxis>>
 where
-    TButton:
-        Sized + Debug + Clone + Copy + PartialEq + Serialize + TryFromPrimitive<Primitive = u8>,
+    TButton: Sized
+        + Debug
+        + Clone
+        + Copy
+        + PartialEq
+        + Serialize
+        + TryFromPrimitive<Primitive = u8>
+        + Into<usize>,
     TAxis: Sized + Debug + Clone + Copy + PartialEq + Serialize + TryFromPrimitive<Primitive = u8>,
 {
     unsafe {
@@ -134,6 +280,22 @@ where
                 });
             }
 
+            BOUND_BUTTON_TYPE_CHORD => {
+                let chord = button.value.chord;
+
+                let modifiers = chord
+                    .modifiers
+                    .get(..chord.modifier_count as usize)?
+                    .iter()
+                    .map(|it| TButton::try_from_primitive(*it).ok())
+                    .collect::<Option<Vec<_>>>()?;
+
+                return Some(BoundButton::Chord {
+                    modifiers: ChordModifiers::new(&modifiers)?,
+                    button: TButton::try_from_primitive(chord.trigger).ok()?,
+                });
+            }
+
             _ => return None,
         }
     }
@@ -180,6 +342,12 @@ fn convert_digital_binding(binding: CDigitalBinding) -> Option<DigitalBinding> {
                 )?));
             }
 
+            INPUT_FAMILY_KEY_LOGICAL => {
+                return Some(DigitalBinding::LogicalKeyboard(convert_bound_button(
+                    binding.binding,
+                )?));
+            }
+
             INPUT_FAMILY_MOUSE => {
                 return Some(DigitalBinding::Mouse(convert_bound_button(
                     binding.binding,
@@ -192,19 +360,92 @@ fn convert_digital_binding(binding: CDigitalBinding) -> Option<DigitalBinding> {
                 )?));
             }
 
+            INPUT_FAMILY_TOUCH => {
+                return Some(DigitalBinding::Touch(convert_bound_button(
+                    binding.binding,
+                )?));
+            }
+
+            INPUT_FAMILY_VIRTUAL => {
+                return Some(DigitalBinding::Virtual(convert_bound_button(
+                    binding.binding,
+                )?));
+            }
+
+            _ => return None,
+        }
+    }
+}
+
+unsafe fn convert_response_curve(curve: CResponseCurve) -> Option<ResponseCurve> {
+    unsafe {
+        match curve.kind {
+            RESPONSE_CURVE_TYPE_LINEAR => return Some(ResponseCurve::Linear),
+
+            RESPONSE_CURVE_TYPE_POWER => {
+                if !(curve.parameter > 0f32 && curve.parameter.is_finite()) {
+                    return None;
+                }
+
+                return Some(ResponseCurve::Power {
+                    exponent: curve.parameter,
+                });
+            }
+
+            RESPONSE_CURVE_TYPE_EXPONENTIAL => {
+                return Some(ResponseCurve::Exponential {
+                    strength: curve.parameter,
+                });
+            }
+
+            RESPONSE_CURVE_TYPE_PIECEWISE => {
+                let points = ptr_to_slice(curve.points, curve.point_count)?;
+
+                return ResponseCurve::piecewise(
+                    &points.iter().map(|it| [it.x, it.y]).collect::<Vec<_>>(),
+                );
+            }
+
             _ => return None,
         }
     }
 }
 
+fn convert_outer_deadzone(outer_deadzone: f32) -> Option<f32> {
+    if outer_deadzone <= 0f32 {
+        return None;
+    }
+
+    return Some(outer_deadzone);
+}
+
 fn convert_analog_binding(binding: CAnalogBinding) -> Option<AnalogBinding> {
+    let deadzone_shape = DeadzoneShape::try_from_primitive(binding.deadzone_shape).ok()?;
+    let outer_deadzone = convert_outer_deadzone(binding.outer_deadzone);
+
     unsafe {
+        let curve = convert_response_curve(binding.curve)?;
+
         match binding.family {
             INPUT_FAMILY_KEY => {
                 return Some(AnalogBinding {
                     axis: AnalogBindingKind::Keyboard(convert_bound_axis(binding.binding)?),
                     deadzone: binding.deadzone,
                     scale: binding.scale,
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
+                });
+            }
+
+            INPUT_FAMILY_KEY_LOGICAL => {
+                return Some(AnalogBinding {
+                    axis: AnalogBindingKind::LogicalKeyboard(convert_bound_axis(binding.binding)?),
+                    deadzone: binding.deadzone,
+                    scale: binding.scale,
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
                 });
             }
 
@@ -213,6 +454,9 @@ fn convert_analog_binding(binding: CAnalogBinding) -> Option<AnalogBinding> {
                     axis: AnalogBindingKind::Mouse(convert_bound_axis(binding.binding)?),
                     deadzone: binding.deadzone,
                     scale: binding.scale,
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
                 });
             }
 
@@ -221,6 +465,31 @@ fn convert_analog_binding(binding: CAnalogBinding) -> Option<AnalogBinding> {
                     axis: AnalogBindingKind::Gamepad(convert_bound_axis(binding.binding)?),
                     deadzone: binding.deadzone,
                     scale: binding.scale,
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
+                });
+            }
+
+            INPUT_FAMILY_TOUCH => {
+                return Some(AnalogBinding {
+                    axis: AnalogBindingKind::Touch(convert_bound_axis(binding.binding)?),
+                    deadzone: binding.deadzone,
+                    scale: binding.scale,
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
+                });
+            }
+
+            INPUT_FAMILY_VIRTUAL => {
+                return Some(AnalogBinding {
+                    axis: AnalogBindingKind::Virtual(convert_bound_axis(binding.binding)?),
+                    deadzone: binding.deadzone,
+                    scale: binding.scale,
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
                 });
             }
 
@@ -230,7 +499,18 @@ fn convert_analog_binding(binding: CAnalogBinding) -> Option<AnalogBinding> {
 }
 
 fn convert_vector_binding(binding: CVectorBinding) -> Option<VectorBinding> {
+    let deadzone_shape = DeadzoneShape::try_from_primitive(binding.deadzone_shape).ok()?;
+    let outer_deadzone = convert_outer_deadzone(binding.outer_deadzone);
+
+    let ratchet = if binding.has_ratchet {
+        Some(convert_digital_binding(binding.ratchet)?)
+    } else {
+        None
+    };
+
     unsafe {
+        let curve = convert_response_curve(binding.curve)?;
+
         match binding.family {
             INPUT_FAMILY_KEY => {
                 return Some(VectorBinding {
@@ -240,6 +520,25 @@ fn convert_vector_binding(binding: CVectorBinding) -> Option<VectorBinding> {
                     },
                     deadzone: binding.deadzone,
                     scale: (binding.scale_x, binding.scale_y),
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
+                    ratchet,
+                });
+            }
+
+            INPUT_FAMILY_KEY_LOGICAL => {
+                return Some(VectorBinding {
+                    axes: VectorBindingKind::LogicalKeyboard {
+                        x: convert_bound_axis(binding.x)?,
+                        y: convert_bound_axis(binding.y)?,
+                    },
+                    deadzone: binding.deadzone,
+                    scale: (binding.scale_x, binding.scale_y),
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
+                    ratchet,
                 });
             }
 
@@ -251,6 +550,10 @@ fn convert_vector_binding(binding: CVectorBinding) -> Option<VectorBinding> {
                     },
                     deadzone: binding.deadzone,
                     scale: (binding.scale_x, binding.scale_y),
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
+                    ratchet,
                 });
             }
 
@@ -262,6 +565,40 @@ fn convert_vector_binding(binding: CVectorBinding) -> Option<VectorBinding> {
                     },
                     deadzone: binding.deadzone,
                     scale: (binding.scale_x, binding.scale_y),
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
+                    ratchet,
+                });
+            }
+
+            INPUT_FAMILY_TOUCH => {
+                return Some(VectorBinding {
+                    axes: VectorBindingKind::Touch {
+                        x: convert_bound_axis(binding.x)?,
+                        y: convert_bound_axis(binding.y)?,
+                    },
+                    deadzone: binding.deadzone,
+                    scale: (binding.scale_x, binding.scale_y),
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
+                    ratchet,
+                });
+            }
+
+            INPUT_FAMILY_VIRTUAL => {
+                return Some(VectorBinding {
+                    axes: VectorBindingKind::Virtual {
+                        x: convert_bound_axis(binding.x)?,
+                        y: convert_bound_axis(binding.y)?,
+                    },
+                    deadzone: binding.deadzone,
+                    scale: (binding.scale_x, binding.scale_y),
+                    deadzone_shape,
+                    outer_deadzone,
+                    curve,
+                    ratchet,
                 });
             }
 
@@ -270,9 +607,68 @@ fn convert_vector_binding(binding: CVectorBinding) -> Option<VectorBinding> {
     }
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CDigitalInteractions {
+    hold: f32,
+    tap: f32,
+    double_tap: f32,
+    repeat_delay: f32,
+    repeat_rate: f32,
+    buffer_frames: u32,
+    buffer_milliseconds: f32,
+}
+
+fn convert_interaction_time(time: f32) -> Option<f32> {
+    if time <= 0f32 {
+        return None;
+    }
+
+    return Some(time);
+}
+
+fn convert_interactions(interactions: CDigitalInteractions) -> DigitalInteractions {
+    let repeat = if interactions.repeat_rate > 0f32 {
+        Some(RepeatInteraction {
+            delay: interactions.repeat_delay.max(0f32),
+            rate: interactions.repeat_rate,
+        })
+    } else {
+        None
+    };
+
+    let buffer = if interactions.buffer_frames > 0 {
+        Some(InputBuffer::Frames(interactions.buffer_frames))
+    } else if interactions.buffer_milliseconds > 0f32 {
+        Some(InputBuffer::Milliseconds(interactions.buffer_milliseconds))
+    } else {
+        None
+    };
+
+    return DigitalInteractions {
+        hold: convert_interaction_time(interactions.hold),
+        tap: convert_interaction_time(interactions.tap),
+        double_tap: convert_interaction_time(interactions.double_tap),
+        repeat,
+        buffer,
+    };
+}
+
 type CBindingMap = *mut BindingMap;
 type CInputState = *mut InputState;
 type CInputManager = *mut InputManager;
+type CInputRecording = *mut InputRecording;
+type CInputReplay = *mut InputReplay;
+type CInputCapture = *mut CaptureState;
+type CGamepadMappings = *mut GamepadMappings;
+type CGamepadMapping = *const GamepadMapping;
+type CActionLayout = *mut ActionLayout;
+type CRemoteInputManager = *mut RemoteInputManager;
+
+struct CaptureState {
+    capture: InputCapture,
+    captured: Option<CapturedBinding>,
+}
 
 c_enum!(InputError {
     ERROR_OK,
@@ -519,6 +915,17 @@ unsafe extern "C" fn pawkit_input_state_connect_gamepad(state: CInputState) -> D
     }
 }
 
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_connect_touch(state: CInputState) -> DeviceId {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return DeviceId::null();
+        };
+
+        return state.connect_touch();
+    }
+}
+
 #[unsafe(no_mangle)]
 unsafe extern "C" fn pawkit_input_state_disconnect_device(state: CInputState, device: DeviceId) {
     unsafe {
@@ -570,6 +977,13 @@ unsafe extern "C" fn pawkit_input_state_set_button(
 
                 state.set_gamepad_button(&device, button, value);
             }
+
+            // Touch buttons come from contacts, and can't be set directly.
+            InputFamily::Touch => {}
+
+            InputFamily::Virtual => {
+                state.set_virtual_button(&device, VirtualButton(button), value);
+            }
         }
     }
 }
@@ -591,7 +1005,7 @@ unsafe extern "C" fn pawkit_input_state_set_axis(
         };
 
         match family {
-            InputFamily::Keyboard => {}
+            InputFamily::Keyboard | InputFamily::Touch => {}
 
             InputFamily::Mouse => {
                 let Ok(axis) = MouseAxis::try_from_primitive(button) else {
@@ -608,113 +1022,1586 @@ unsafe extern "C" fn pawkit_input_state_set_axis(
 
                 state.set_gamepad_axis(&device, axis, value);
             }
+
+            InputFamily::Virtual => {
+                state.set_virtual_axis(&device, VirtualAxis(button), value);
+            }
         }
     }
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_create(map: CBindingMap) -> CInputManager {
+unsafe extern "C" fn pawkit_input_state_set_touch_contact(
+    state: CInputState,
+    device: DeviceId,
+    contact: TouchContact,
+) -> bool {
     unsafe {
-        let Some(map) = move_to_stack(map) else {
-            return null_mut();
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
         };
 
-        return move_to_heap(InputManager::new(map));
+        return state.set_touch_contact(&device, contact);
     }
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_destroy(manager: CInputManager) {
+unsafe extern "C" fn pawkit_input_state_remove_touch_contact(
+    state: CInputState,
+    device: DeviceId,
+    id: u64,
+) -> bool {
     unsafe {
-        drop_from_heap(manager);
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        return state.remove_touch_contact(&device, id);
     }
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_connect_device(manager: CInputManager, device: DeviceId) {
+unsafe extern "C" fn pawkit_input_state_set_touch_settings(
+    state: CInputState,
+    device: DeviceId,
+    settings: TouchSettings,
+) -> bool {
     unsafe {
-        let Some(manager) = ptr_to_ref_mut(manager) else {
-            return;
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
         };
 
-        manager.connect_device(device);
+        return state.set_touch_settings(&device, settings);
     }
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_disconnect_device(
-    manager: CInputManager,
+unsafe extern "C" fn pawkit_input_state_set_gamepad_motion(
+    state: CInputState,
     device: DeviceId,
-) {
+    sample: MotionSample,
+) -> bool {
     unsafe {
-        let Some(manager) = ptr_to_ref_mut(manager) else {
-            return;
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
         };
 
-        manager.disconnect_device(device);
+        return state.set_gamepad_motion(&device, sample);
     }
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_update(manager: CInputManager, state: CInputState) {
+unsafe extern "C" fn pawkit_input_state_get_gyro_calibration(
+    state: CInputState,
+    device: DeviceId,
+    calibration: *mut GyroCalibration,
+) -> bool {
     unsafe {
-        let Some(manager) = ptr_to_ref_mut(manager) else {
-            return;
+        let Some(state) = ptr_to_ref(state) else {
+            return false;
         };
 
-        let Some(state) = ptr_to_ref(state) else {
-            return;
+        let Some(value) = state.gyro_calibration(&device) else {
+            return false;
         };
 
-        manager.update(state);
+        set_if_valid(calibration, value);
+
+        return true;
     }
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_get(
-    manager: CInputManager,
-    name: *const u8,
-    frame: *mut RawInputFrame,
+unsafe extern "C" fn pawkit_input_state_set_gyro_calibration(
+    state: CInputState,
+    device: DeviceId,
+    calibration: GyroCalibration,
 ) -> bool {
     unsafe {
-        let Some(manager) = ptr_to_ref(manager) else {
+        let Some(state) = ptr_to_ref_mut(state) else {
             return false;
         };
 
-        let Some(frame) = ptr_to_ref_mut(frame) else {
-            return false;
-        };
+        return state.set_gyro_calibration(&device, calibration);
+    }
+}
 
-        let Some(name) = InternString::from_raw(name) else {
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_begin_gyro_calibration(
+    state: CInputState,
+    device: DeviceId,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
             return false;
         };
 
-        let Some(value) = manager.get_binding_raw(&name) else {
-            forget(name);
+        return state.begin_gyro_calibration(&device);
+    }
+}
 
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_end_gyro_calibration(
+    state: CInputState,
+    device: DeviceId,
+    calibration: *mut GyroCalibration,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
             return false;
         };
 
-        *frame = value;
+        let Some(value) = state.end_gyro_calibration(&device) else {
+            return false;
+        };
 
-        forget(name);
+        set_if_valid(calibration, value);
 
         return true;
     }
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_add_digital_binding(
-    manager: CInputManager,
-    name: *const u8,
-    binding: CDigitalBinding,
+unsafe extern "C" fn pawkit_input_state_play_rumble(
+    state: CInputState,
+    device: DeviceId,
+    low_frequency: f32,
+    high_frequency: f32,
+    envelope: HapticEnvelope,
 ) -> bool {
     unsafe {
-        let Some(manager) = ptr_to_ref_mut(manager) else {
+        let Some(state) = ptr_to_ref_mut(state) else {
             return false;
         };
 
-        let Some(value) = convert_digital_binding(binding) else {
+        return state.play_haptic(
+            &device,
+            HapticEffect::Rumble {
+                low_frequency,
+                high_frequency,
+                envelope,
+            },
+        );
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_play_trigger_rumble(
+    state: CInputState,
+    device: DeviceId,
+    left: f32,
+    right: f32,
+    envelope: HapticEnvelope,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        return state.play_haptic(
+            &device,
+            HapticEffect::TriggerRumble {
+                left,
+                right,
+                envelope,
+            },
+        );
+    }
+}
+
+unsafe fn haptic_effect_from_json(cstr: *const c_char, len: usize) -> Option<HapticEffect> {
+    unsafe {
+        let str = cstr_to_str(cstr, len)?;
+
+        return HapticEffect::load(str).ok();
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_play_haptic_json(
+    state: CInputState,
+    device: DeviceId,
+    cstr: *const c_char,
+    len: usize,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        let Some(effect) = haptic_effect_from_json(cstr, len) else {
+            return false;
+        };
+
+        return state.play_haptic(&device, effect);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_stop_haptics(state: CInputState, device: DeviceId) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        return state.stop_haptics(&device);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_drain_haptics(
+    state: CInputState,
+    device: DeviceId,
+    delta_time: f32,
+    output: *mut HapticOutput,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        let Some(value) = state.drain_haptics(&device, delta_time) else {
+            return false;
+        };
+
+        set_if_valid(output, value);
+
+        return true;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_set_mouse_viewport(
+    state: CInputState,
+    device: DeviceId,
+    width: f32,
+    height: f32,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        return state.set_mouse_viewport(&device, [width, height]);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_set_cursor_locked(
+    state: CInputState,
+    device: DeviceId,
+    locked: bool,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        return state.set_cursor_locked(&device, locked);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_is_cursor_locked(
+    state: CInputState,
+    device: DeviceId,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref(state) else {
+            return false;
+        };
+
+        return state.is_cursor_locked(&device) == Some(true);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_set_controller_style(
+    state: CInputState,
+    device: DeviceId,
+    style: u8,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        let Ok(style) = ControllerStyle::try_from_primitive(style) else {
+            return false;
+        };
+
+        return state.set_controller_style(&device, style);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_get_controller_style(
+    state: CInputState,
+    device: DeviceId,
+) -> u8 {
+    unsafe {
+        let Some(state) = ptr_to_ref(state) else {
+            return ControllerStyle::Generic as u8;
+        };
+
+        return state.controller_style(&device).unwrap_or_default() as u8;
+    }
+}
+
+/// Replaces the layout of a keyboard. `logical[i]` is the key printed on `physical[i]`.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_set_keyboard_layout(
+    state: CInputState,
+    device: DeviceId,
+    physical: *const u8,
+    logical: *const u8,
+    len: usize,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        let mut layout = KeyboardLayout::new();
+
+        let (Some(physical), Some(logical)) =
+            (ptr_to_slice(physical, len), ptr_to_slice(logical, len))
+        else {
+            return false;
+        };
+
+        for (physical, logical) in physical.iter().zip(logical) {
+            let (Ok(physical), Ok(logical)) = (
+                KeyboardButton::try_from_primitive(*physical),
+                KeyboardButton::try_from_primitive(*logical),
+            ) else {
+                return false;
+            };
+
+            layout.set(physical, logical);
+        }
+
+        return state.set_keyboard_layout(&device, layout);
+    }
+}
+
+/// Returns the key printed on a physical key, or the key itself if the device isn't a keyboard.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_get_logical_key(
+    state: CInputState,
+    device: DeviceId,
+    physical: u8,
+) -> u8 {
+    unsafe {
+        let Some(state) = ptr_to_ref(state) else {
+            return physical;
+        };
+
+        let Ok(key) = KeyboardButton::try_from_primitive(physical) else {
+            return physical;
+        };
+
+        let Some(layout) = state.keyboard_layout(&device) else {
+            return physical;
+        };
+
+        return layout.logical(key) as u8;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_set_text_input_active(
+    state: CInputState,
+    device: DeviceId,
+    active: bool,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        return state.set_text_input_active(&device, active);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_is_text_input_active(
+    state: CInputState,
+    device: DeviceId,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref(state) else {
+            return false;
+        };
+
+        return state.is_text_input_active(&device) == Some(true);
+    }
+}
+
+unsafe fn push_text_event(state: CInputState, device: DeviceId, event: TextEvent) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        return state.push_text_event(&device, event);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_commit_text(
+    state: CInputState,
+    device: DeviceId,
+    cstr: *const c_char,
+    len: usize,
+) -> bool {
+    unsafe {
+        let Some(text) = cstr_to_str(cstr, len) else {
+            return false;
+        };
+
+        return push_text_event(state, device, TextEvent::Commit { text: text.into() });
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_start_composition(
+    state: CInputState,
+    device: DeviceId,
+) -> bool {
+    unsafe {
+        return push_text_event(state, device, TextEvent::CompositionStart);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_update_composition(
+    state: CInputState,
+    device: DeviceId,
+    cstr: *const c_char,
+    len: usize,
+    cursor_start: usize,
+    cursor_end: usize,
+) -> bool {
+    unsafe {
+        let Some(text) = cstr_to_str(cstr, len) else {
+            return false;
+        };
+
+        return push_text_event(
+            state,
+            device,
+            TextEvent::CompositionUpdate(TextComposition {
+                text: text.into(),
+                cursor: cursor_start..cursor_end,
+            }),
+        );
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_end_composition(
+    state: CInputState,
+    device: DeviceId,
+) -> bool {
+    unsafe {
+        return push_text_event(state, device, TextEvent::CompositionEnd);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_poll_text_event(
+    state: CInputState,
+    device: DeviceId,
+    event: *mut CTextEvent,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        // The text is handed to the caller to free, so don't take it without somewhere to put it.
+        if event.is_null() {
+            return false;
+        }
+
+        let Some(value) = state.pop_text_event(&device) else {
+            return false;
+        };
+
+        let mut len = 0;
+
+        let value = match value {
+            TextEvent::Commit { text } => CTextEvent {
+                kind: TEXT_EVENT_COMMIT,
+                text: disown_str_to_cstr(&text, &mut len),
+                len,
+                cursor_start: 0,
+                cursor_end: 0,
+            },
+            TextEvent::CompositionStart => CTextEvent {
+                kind: TEXT_EVENT_COMPOSITION_START,
+                text: null(),
+                len: 0,
+                cursor_start: 0,
+                cursor_end: 0,
+            },
+            TextEvent::CompositionUpdate(composition) => CTextEvent {
+                kind: TEXT_EVENT_COMPOSITION_UPDATE,
+                text: disown_str_to_cstr(&composition.text, &mut len),
+                len,
+                cursor_start: composition.cursor.start,
+                cursor_end: composition.cursor.end,
+            },
+            TextEvent::CompositionEnd => CTextEvent {
+                kind: TEXT_EVENT_COMPOSITION_END,
+                text: null(),
+                len: 0,
+                cursor_start: 0,
+                cursor_end: 0,
+            },
+        };
+
+        set_if_valid(event, value);
+
+        return true;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_next_frame(state: CInputState) {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return;
+        };
+
+        state.next_frame();
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_start_recording(state: CInputState) {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return;
+        };
+
+        state.start_recording();
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_stop_recording(state: CInputState) -> CInputRecording {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return null_mut();
+        };
+
+        let Some(recording) = state.stop_recording() else {
+            return null_mut();
+        };
+
+        return move_to_heap(recording);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_recording_load(buf: *mut VfsBuffer) -> CInputRecording {
+    unsafe {
+        let Some(buf) = ptr_to_ref_mut(buf) else {
+            return null_mut();
+        };
+
+        let Ok(recording) = InputRecording::read(buf) else {
+            return null_mut();
+        };
+
+        return move_to_heap(recording);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_recording_save(recording: CInputRecording) -> *mut VfsBuffer {
+    unsafe {
+        let Some(recording) = ptr_to_ref(recording) else {
+            return null_mut();
+        };
+
+        return move_to_heap(recording.to_buffer());
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_recording_destroy(recording: CInputRecording) {
+    unsafe {
+        drop_from_heap(recording);
+    }
+}
+
+/// Takes ownership of the recording.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_replay_create(recording: CInputRecording) -> CInputReplay {
+    unsafe {
+        let Some(recording) = move_to_stack(recording) else {
+            return null_mut();
+        };
+
+        return move_to_heap(InputReplay::new(recording));
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_replay_destroy(replay: CInputReplay) {
+    unsafe {
+        drop_from_heap(replay);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_replay_step(replay: CInputReplay, state: CInputState) {
+    unsafe {
+        let Some(replay) = ptr_to_ref_mut(replay) else {
+            return;
+        };
+
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return;
+        };
+
+        replay.step(state);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_replay_is_finished(replay: CInputReplay) -> bool {
+    unsafe {
+        let Some(replay) = ptr_to_ref(replay) else {
+            return true;
+        };
+
+        return replay.is_finished();
+    }
+}
+
+/// Threshold and deadzone values <= 0 use the defaults.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_capture_begin(
+    state: CInputState,
+    kind: CBindingKind,
+    threshold: f32,
+    deadzone: f32,
+    cancel: *const CDigitalBinding,
+    cancel_len: usize,
+    logical_keys: bool,
+) -> CInputCapture {
+    unsafe {
+        let Some(state) = ptr_to_ref(state) else {
+            return null_mut();
+        };
+
+        let kind = match kind {
+            BINDING_TYPE_DIGITAL => BindingKind::Digital,
+            BINDING_TYPE_ANALOG => BindingKind::Analog,
+            BINDING_TYPE_VECTOR => BindingKind::Vector,
+            _ => return null_mut(),
+        };
+
+        let mut options = CaptureOptions::new(kind);
+        options.logical_keys = logical_keys;
+
+        if threshold > 0f32 {
+            options.threshold = threshold;
+        }
+
+        if deadzone > 0f32 {
+            options.deadzone = deadzone;
+        }
+
+        if let Some(cancel) = ptr_to_slice(cancel, cancel_len) {
+            options.cancel = cancel
+                .iter()
+                .cloned()
+                .filter_map(convert_digital_binding)
+                .collect();
+        }
+
+        return move_to_heap(CaptureState {
+            capture: state.begin_capture(options),
+            captured: None,
+        });
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_capture_destroy(capture: CInputCapture) {
+    unsafe {
+        drop_from_heap(capture);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_capture_poll(
+    capture: CInputCapture,
+    state: CInputState,
+) -> CCaptureStatus {
+    unsafe {
+        let Some(capture) = ptr_to_ref_mut(capture) else {
+            return CAPTURE_STATUS_CANCELLED;
+        };
+
+        let Some(state) = ptr_to_ref(state) else {
+            return CAPTURE_STATUS_CANCELLED;
+        };
+
+        if capture.captured.is_some() {
+            return CAPTURE_STATUS_CAPTURED;
+        }
+
+        match capture.capture.poll(state) {
+            CaptureResult::Pending => return CAPTURE_STATUS_PENDING,
+            CaptureResult::Cancelled => return CAPTURE_STATUS_CANCELLED,
+            CaptureResult::Captured(binding) => {
+                capture.captured = Some(binding);
+
+                return CAPTURE_STATUS_CAPTURED;
+            }
+        }
+    }
+}
+
+/// Adds the captured binding to the manager.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_capture_apply(
+    capture: CInputCapture,
+    manager: CInputManager,
+    name: *const u8,
+) -> bool {
+    unsafe {
+        let Some(capture) = ptr_to_ref(capture) else {
+            return false;
+        };
+
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(binding) = capture.captured.clone() else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.add_captured_binding(name.clone(), binding).is_ok();
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_create(map: CBindingMap) -> CInputManager {
+    unsafe {
+        let Some(map) = move_to_stack(map) else {
+            return null_mut();
+        };
+
+        return move_to_heap(InputManager::new(map));
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_destroy(manager: CInputManager) {
+    unsafe {
+        drop_from_heap(manager);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_connect_device(manager: CInputManager, device: DeviceId) {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return;
+        };
+
+        manager.connect_device(device);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_disconnect_device(
+    manager: CInputManager,
+    device: DeviceId,
+) {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return;
+        };
+
+        manager.disconnect_device(device);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_update(
+    manager: CInputManager,
+    state: CInputState,
+    delta_time: f32,
+) {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return;
+        };
+
+        let Some(state) = ptr_to_ref(state) else {
+            return;
+        };
+
+        manager.update(state, delta_time);
+    }
+}
+
+/// Takes ownership of the binding map.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_add_context(
+    manager: CInputManager,
+    name: *const u8,
+    map: CBindingMap,
+    priority: i32,
+    consumption: CInputConsumption,
+) -> bool {
+    unsafe {
+        let Some(map) = move_to_stack(map) else {
+            return false;
+        };
+
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let consumption = match consumption {
+            INPUT_CONSUMPTION_PASSTHROUGH => InputConsumption::Passthrough,
+            INPUT_CONSUMPTION_BOUND => InputConsumption::Bound,
+            INPUT_CONSUMPTION_ALL => InputConsumption::All,
+            _ => return false,
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.add_context(
+            name.clone(),
+            map,
+            ContextOptions {
+                priority,
+                consumption,
+            },
+        );
+
+        forget(name);
+
+        return result;
+    }
+}
+
+unsafe fn manager_context_op(
+    manager: CInputManager,
+    name: *const u8,
+    op: fn(&mut InputManager, &InternString) -> bool,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = op(manager, &name);
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_remove_context(
+    manager: CInputManager,
+    name: *const u8,
+) -> bool {
+    unsafe {
+        return manager_context_op(manager, name, InputManager::remove_context);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_push_context(
+    manager: CInputManager,
+    name: *const u8,
+) -> bool {
+    unsafe {
+        return manager_context_op(manager, name, InputManager::push_context);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_pop_context(manager: CInputManager) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        return manager.pop_context().is_some();
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_deactivate_context(
+    manager: CInputManager,
+    name: *const u8,
+) -> bool {
+    unsafe {
+        return manager_context_op(manager, name, InputManager::deactivate_context);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_is_context_active(
+    manager: CInputManager,
+    name: *const u8,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref(manager) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.is_context_active(&name);
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_set_interactions(
+    manager: CInputManager,
+    name: *const u8,
+    interactions: CDigitalInteractions,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager
+            .set_interactions(&name, convert_interactions(interactions))
+            .is_ok();
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_consume(manager: CInputManager, name: *const u8) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.consume(&name);
+
+        forget(name);
+
+        return result;
+    }
+}
+
+unsafe fn manager_play_haptic(
+    manager: CInputManager,
+    state: CInputState,
+    name: *const u8,
+    effect: HapticEffect,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref(manager) else {
+            return false;
+        };
+
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.play_haptic(state, &name, effect);
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_play_rumble(
+    manager: CInputManager,
+    state: CInputState,
+    name: *const u8,
+    low_frequency: f32,
+    high_frequency: f32,
+    envelope: HapticEnvelope,
+) -> bool {
+    unsafe {
+        return manager_play_haptic(
+            manager,
+            state,
+            name,
+            HapticEffect::Rumble {
+                low_frequency,
+                high_frequency,
+                envelope,
+            },
+        );
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_play_haptic_json(
+    manager: CInputManager,
+    state: CInputState,
+    name: *const u8,
+    cstr: *const c_char,
+    len: usize,
+) -> bool {
+    unsafe {
+        let Some(effect) = haptic_effect_from_json(cstr, len) else {
+            return false;
+        };
+
+        return manager_play_haptic(manager, state, name, effect);
+    }
+}
+
+/// Gets a single glyph of one of an action's bindings, for the device the player last used.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_get_glyph(
+    manager: CInputManager,
+    state: CInputState,
+    name: *const u8,
+    binding: usize,
+    index: usize,
+    glyph: *mut CInputGlyph,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref(manager) else {
+            return false;
+        };
+
+        let Some(state) = ptr_to_ref(state) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let glyphs = manager.action_glyphs(state, &name);
+
+        forget(name);
+
+        let Some(value) = glyphs.get(binding).and_then(|it| it.get(index)) else {
+            return false;
+        };
+
+        set_if_valid(
+            glyph,
+            CInputGlyph {
+                id: value.id.as_ptr() as *const c_char,
+                id_len: value.id.len(),
+                name: value.name.as_ptr() as *const c_char,
+                name_len: value.name.len(),
+            },
+        );
+
+        return true;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_get(
+    manager: CInputManager,
+    name: *const u8,
+    frame: *mut RawInputFrame,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref(manager) else {
+            return false;
+        };
+
+        let Some(frame) = ptr_to_ref_mut(frame) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let Some(value) = manager.get_binding_raw(&name) else {
+            forget(name);
+
+            return false;
+        };
+
+        *frame = value;
+
+        forget(name);
+
+        return true;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_add_digital_binding(
+    manager: CInputManager,
+    name: *const u8,
+    binding: CDigitalBinding,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(value) = convert_digital_binding(binding) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.add_digital_binding(name.clone(), value).is_ok();
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_add_analog_binding(
+    manager: CInputManager,
+    name: *const u8,
+    binding: CAnalogBinding,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(value) = convert_analog_binding(binding) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.add_analog_binding(name.clone(), value).is_ok();
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_add_vector_binding(
+    manager: CInputManager,
+    name: *const u8,
+    binding: CVectorBinding,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(value) = convert_vector_binding(binding) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.add_vector_binding(name.clone(), value).is_ok();
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_remove_digital_binding(
+    manager: CInputManager,
+    name: *const u8,
+    binding: CDigitalBinding,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(value) = convert_digital_binding(binding) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.remove_digital_binding(name.clone(), value).is_ok();
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_remove_analog_binding(
+    manager: CInputManager,
+    name: *const u8,
+    binding: CAnalogBinding,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(value) = convert_analog_binding(binding) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.remove_analog_binding(name.clone(), value).is_ok();
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_remove_vector_binding(
+    manager: CInputManager,
+    name: *const u8,
+    binding: CVectorBinding,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        let Some(value) = convert_vector_binding(binding) else {
+            return false;
+        };
+
+        let Some(name) = InternString::from_raw(name) else {
+            return false;
+        };
+
+        let result = manager.remove_vector_binding(name.clone(), value).is_ok();
+
+        forget(name);
+
+        return result;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_mappings_create() -> CGamepadMappings {
+    unsafe {
+        return move_to_heap(GamepadMappings::new());
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_mappings_destroy(mappings: CGamepadMappings) {
+    unsafe {
+        drop_from_heap(mappings);
+    }
+}
+
+/// Adds the mappings in the text of a `gamecontrollerdb.txt` file, returning how many were added.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_mappings_parse(
+    mappings: CGamepadMappings,
+    cstr: *const c_char,
+    len: usize,
+) -> usize {
+    unsafe {
+        let Some(mappings) = ptr_to_ref_mut(mappings) else {
+            return 0;
+        };
+
+        let Some(text) = cstr_to_str(cstr, len) else {
+            return 0;
+        };
+
+        return mappings.parse(text);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_mappings_load(
+    mappings: CGamepadMappings,
+    vfs: *mut Vfs,
+    path: *const c_char,
+    path_len: usize,
+) -> bool {
+    unsafe {
+        let Some(mappings) = ptr_to_ref_mut(mappings) else {
+            return false;
+        };
+
+        let Some(vfs) = ptr_to_ref(vfs) else {
+            return false;
+        };
+
+        let Some(path) = cstr_to_str(path, path_len) else {
+            return false;
+        };
+
+        return mappings.load(vfs, path).is_ok();
+    }
+}
+
+/// Returns the generic mapping if the device isn't in the database.
+/// The mapping is owned by the database, and is only valid until it's changed or destroyed.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_mappings_get(
+    mappings: CGamepadMappings,
+    guid: *const [u8; 16],
+) -> CGamepadMapping {
+    unsafe {
+        let Some(mappings) = ptr_to_ref(mappings) else {
+            return null();
+        };
+
+        let Some(guid) = ptr_to_ref(guid) else {
+            return null();
+        };
+
+        return mappings.get_or_fallback(GamepadGuid(*guid));
+    }
+}
+
+/// The string is owned by the mapping, and isn't null terminated.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_mapping_get_name(
+    mapping: CGamepadMapping,
+    len: *mut usize,
+) -> *const c_char {
+    unsafe {
+        let Some(mapping) = ptr_to_ref(mapping) else {
+            return null();
+        };
+
+        let Some(len) = ptr_to_ref_mut(len) else {
+            return null();
+        };
+
+        return str_to_cstr(&mapping.name, len);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_mapping_set_button(
+    mapping: CGamepadMapping,
+    state: CInputState,
+    device: DeviceId,
+    button: u32,
+    value: bool,
+) {
+    unsafe {
+        let (Some(mapping), Some(state)) = (ptr_to_ref(mapping), ptr_to_ref_mut(state)) else {
+            return;
+        };
+
+        mapping.set_button(state, &device, button, value);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_mapping_set_hat(
+    mapping: CGamepadMapping,
+    state: CInputState,
+    device: DeviceId,
+    hat: u32,
+    value: u8,
+) {
+    unsafe {
+        let (Some(mapping), Some(state)) = (ptr_to_ref(mapping), ptr_to_ref_mut(state)) else {
+            return;
+        };
+
+        mapping.set_hat(state, &device, hat, value);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_mapping_set_axis(
+    mapping: CGamepadMapping,
+    state: CInputState,
+    device: DeviceId,
+    axis: u32,
+    value: f32,
+) {
+    unsafe {
+        let (Some(mapping), Some(state)) = (ptr_to_ref(mapping), ptr_to_ref_mut(state)) else {
+            return;
+        };
+
+        mapping.set_axis(state, &device, axis, value);
+    }
+}
+
+fn snapshot_options(analog_precision: f32, vector_precision: f32) -> SnapshotOptions {
+    let mut options = SnapshotOptions::default();
+
+    if analog_precision > 0f32 {
+        options.analog_precision = analog_precision;
+    }
+
+    if vector_precision > 0f32 {
+        options.vector_precision = vector_precision;
+    }
+
+    return options;
+}
+
+/// Every action in the manager, sorted by name. Both sides of a connection need the same layout.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_action_layout_create(manager: CInputManager) -> CActionLayout {
+    unsafe {
+        let Some(manager) = ptr_to_ref(manager) else {
+            return null_mut();
+        };
+
+        return move_to_heap(ActionLayout::from_manager(manager));
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_action_layout_destroy(layout: CActionLayout) {
+    unsafe {
+        drop_from_heap(layout);
+    }
+}
+
+/// Precision values <= 0 use the defaults. The snapshot is freed with `pawkit_free_array`.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_snapshot_encode(
+    layout: CActionLayout,
+    manager: CInputManager,
+    analog_precision: f32,
+    vector_precision: f32,
+    len: *mut usize,
+) -> *mut u8 {
+    unsafe {
+        let (Some(layout), Some(manager), Some(len)) =
+            (ptr_to_ref(layout), ptr_to_ref(manager), ptr_to_ref_mut(len))
+        else {
+            return null_mut();
+        };
+
+        let options = snapshot_options(analog_precision, vector_precision);
+
+        return move_slice_to_heap(&layout.encode(manager, &options), len);
+    }
+}
+
+/// The layout is copied, so it can be destroyed afterwards.
+/// The precision values have to match the ones the snapshots were encoded with.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_remote_manager_create(
+    layout: CActionLayout,
+    analog_precision: f32,
+    vector_precision: f32,
+) -> CRemoteInputManager {
+    unsafe {
+        let Some(layout) = ptr_to_ref(layout) else {
+            return null_mut();
+        };
+
+        let options = snapshot_options(analog_precision, vector_precision);
+
+        return move_to_heap(RemoteInputManager::new(layout.clone(), options));
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_remote_manager_destroy(manager: CRemoteInputManager) {
+    unsafe {
+        drop_from_heap(manager);
+    }
+}
+
+/// Returns false, leaving the frames alone, if the snapshot doesn't match the layout.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_remote_manager_apply(
+    manager: CRemoteInputManager,
+    snapshot: *const u8,
+    len: usize,
+) -> bool {
+    unsafe {
+        let (Some(manager), Some(snapshot)) =
+            (ptr_to_ref_mut(manager), ptr_to_slice(snapshot, len))
+        else {
+            return false;
+        };
+
+        return manager.apply(snapshot).is_ok();
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_remote_manager_get(
+    manager: CRemoteInputManager,
+    name: *const u8,
+    frame: *mut RawInputFrame,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref(manager) else {
+            return false;
+        };
+
+        let Some(frame) = ptr_to_ref_mut(frame) else {
             return false;
         };
 
@@ -722,26 +2609,71 @@ unsafe extern "C" fn pawkit_input_manager_add_digital_binding(
             return false;
         };
 
-        let result = manager.add_digital_binding(name.clone(), value).is_ok();
+        let Some(value) = manager.get_binding_raw(&name) else {
+            forget(name);
+
+            return false;
+        };
+
+        *frame = value;
 
         forget(name);
 
-        return result;
+        return true;
     }
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_add_analog_binding(
+unsafe extern "C" fn pawkit_input_manager_set_history_length(
     manager: CInputManager,
-    name: *const u8,
-    binding: CAnalogBinding,
-) -> bool {
+    length: usize,
+) {
     unsafe {
         let Some(manager) = ptr_to_ref_mut(manager) else {
-            return false;
+            return;
         };
 
-        let Some(value) = convert_analog_binding(binding) else {
+        manager.set_history_length(length);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_get_tick(manager: CInputManager) -> u64 {
+    unsafe {
+        let Some(manager) = ptr_to_ref(manager) else {
+            return 0;
+        };
+
+        return manager.tick();
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_get_history_range(
+    manager: CInputManager,
+    start: *mut u64,
+    end: *mut u64,
+) {
+    unsafe {
+        let Some(manager) = ptr_to_ref(manager) else {
+            return;
+        };
+
+        let range = manager.history_range();
+
+        set_if_valid(start, range.start);
+        set_if_valid(end, range.end);
+    }
+}
+
+unsafe fn manager_set_tick_input(
+    manager: CInputManager,
+    tick: u64,
+    name: *const u8,
+    value: ActionValue,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
             return false;
         };
 
@@ -749,7 +2681,7 @@ unsafe extern "C" fn pawkit_input_manager_add_analog_binding(
             return false;
         };
 
-        let result = manager.add_analog_binding(name.clone(), value).is_ok();
+        let result = manager.set_tick_input(tick, &name, value);
 
         forget(name);
 
@@ -758,17 +2690,80 @@ unsafe extern "C" fn pawkit_input_manager_add_analog_binding(
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_add_vector_binding(
+unsafe extern "C" fn pawkit_input_manager_set_tick_input_digital(
     manager: CInputManager,
+    tick: u64,
     name: *const u8,
-    binding: CVectorBinding,
+    pressed: bool,
+) -> bool {
+    unsafe {
+        return manager_set_tick_input(manager, tick, name, ActionValue::Digital(pressed));
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_set_tick_input_analog(
+    manager: CInputManager,
+    tick: u64,
+    name: *const u8,
+    value: f32,
+) -> bool {
+    unsafe {
+        return manager_set_tick_input(manager, tick, name, ActionValue::Analog(value));
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_set_tick_input_vector(
+    manager: CInputManager,
+    tick: u64,
+    name: *const u8,
+    x: f32,
+    y: f32,
 ) -> bool {
+    unsafe {
+        return manager_set_tick_input(manager, tick, name, ActionValue::Vector([x, y]));
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_rewind(manager: CInputManager, tick: u64) -> bool {
     unsafe {
         let Some(manager) = ptr_to_ref_mut(manager) else {
             return false;
         };
 
-        let Some(value) = convert_vector_binding(binding) else {
+        return manager.rewind(tick);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_replay_tick(manager: CInputManager) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return false;
+        };
+
+        return manager.replay_tick();
+    }
+}
+
+/// Copies an action's bindings out of the map. Returns `None` if the action isn't in the map.
+unsafe fn map_action(map: CBindingMap, name: &InternString) -> Option<BindingList> {
+    unsafe {
+        return ptr_to_ref(map)?.get_bindings(name).cloned();
+    }
+}
+
+/// Runs `f` on the manager with the context, or `None` for the bindings the manager was created with.
+unsafe fn manager_modify_action(
+    manager: CInputManager,
+    context: *const u8,
+    name: *const u8,
+    f: impl FnOnce(&mut InputManager, Option<&InternString>, &InternString) -> bool,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
             return false;
         };
 
@@ -776,26 +2771,106 @@ unsafe extern "C" fn pawkit_input_manager_add_vector_binding(
             return false;
         };
 
-        let result = manager.add_vector_binding(name.clone(), value).is_ok();
+        let context = InternString::from_raw(context);
+
+        let result = f(manager, context.as_ref(), &name);
 
         forget(name);
+        forget(context);
 
         return result;
     }
 }
 
+/// The map is only read from. `context` may be null for the bindings the manager was created with.
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_remove_digital_binding(
+unsafe extern "C" fn pawkit_input_manager_register_action(
     manager: CInputManager,
+    context: *const u8,
+    map: CBindingMap,
     name: *const u8,
-    binding: CDigitalBinding,
 ) -> bool {
     unsafe {
-        let Some(manager) = ptr_to_ref_mut(manager) else {
-            return false;
+        return manager_modify_action(manager, context, name, |manager, context, name| {
+            let Some(bindings) = map_action(map, name) else {
+                return false;
+            };
+
+            match context {
+                Some(context) => {
+                    return manager.register_context_action(context, name.clone(), bindings);
+                }
+                None => return manager.register_action(name.clone(), bindings),
+            }
+        });
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_replace_action(
+    manager: CInputManager,
+    context: *const u8,
+    map: CBindingMap,
+    name: *const u8,
+) -> bool {
+    unsafe {
+        return manager_modify_action(manager, context, name, |manager, context, name| {
+            let Some(bindings) = map_action(map, name) else {
+                return false;
+            };
+
+            match context {
+                Some(context) => {
+                    return manager.replace_context_action(context, name.clone(), bindings);
+                }
+                None => return manager.replace_action(name.clone(), bindings),
+            }
+        });
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_unregister_action(
+    manager: CInputManager,
+    context: *const u8,
+    name: *const u8,
+) -> bool {
+    unsafe {
+        return manager_modify_action(
+            manager,
+            context,
+            name,
+            |manager, context, name| match context {
+                Some(context) => return manager.unregister_context_action(context, name),
+                None => return manager.unregister_action(name),
+            },
+        );
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_connect_virtual(
+    state: CInputState,
+    buttons: u8,
+    axes: u8,
+) -> DeviceId {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
+            return DeviceId::null();
         };
 
-        let Some(value) = convert_digital_binding(binding) else {
+        return state.connect_virtual(VirtualLayout { buttons, axes });
+    }
+}
+
+unsafe fn state_set_virtual_action(
+    state: CInputState,
+    device: DeviceId,
+    name: *const u8,
+    value: ActionValue,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
             return false;
         };
 
@@ -803,7 +2878,7 @@ unsafe extern "C" fn pawkit_input_manager_remove_digital_binding(
             return false;
         };
 
-        let result = manager.remove_digital_binding(name.clone(), value).is_ok();
+        let result = state.set_virtual_action(&device, name.clone(), value);
 
         forget(name);
 
@@ -812,17 +2887,50 @@ unsafe extern "C" fn pawkit_input_manager_remove_digital_binding(
 }
 
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_remove_analog_binding(
-    manager: CInputManager,
+unsafe extern "C" fn pawkit_input_state_set_virtual_action_digital(
+    state: CInputState,
+    device: DeviceId,
     name: *const u8,
-    binding: CAnalogBinding,
+    pressed: bool,
 ) -> bool {
     unsafe {
-        let Some(manager) = ptr_to_ref_mut(manager) else {
-            return false;
-        };
+        return state_set_virtual_action(state, device, name, ActionValue::Digital(pressed));
+    }
+}
 
-        let Some(value) = convert_analog_binding(binding) else {
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_set_virtual_action_analog(
+    state: CInputState,
+    device: DeviceId,
+    name: *const u8,
+    value: f32,
+) -> bool {
+    unsafe {
+        return state_set_virtual_action(state, device, name, ActionValue::Analog(value));
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_set_virtual_action_vector(
+    state: CInputState,
+    device: DeviceId,
+    name: *const u8,
+    x: f32,
+    y: f32,
+) -> bool {
+    unsafe {
+        return state_set_virtual_action(state, device, name, ActionValue::Vector([x, y]));
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_state_clear_virtual_action(
+    state: CInputState,
+    device: DeviceId,
+    name: *const u8,
+) -> bool {
+    unsafe {
+        let Some(state) = ptr_to_ref_mut(state) else {
             return false;
         };
 
@@ -830,7 +2938,7 @@ unsafe extern "C" fn pawkit_input_manager_remove_analog_binding(
             return false;
         };
 
-        let result = manager.remove_analog_binding(name.clone(), value).is_ok();
+        let result = state.clear_virtual_action(&device, &name);
 
         forget(name);
 
@@ -838,29 +2946,103 @@ unsafe extern "C" fn pawkit_input_manager_remove_analog_binding(
     }
 }
 
+/// Gets the device that most recently drove an action, and its family.
 #[unsafe(no_mangle)]
-unsafe extern "C" fn pawkit_input_manager_remove_vector_binding(
+unsafe extern "C" fn pawkit_input_manager_get_last_device(
     manager: CInputManager,
-    name: *const u8,
-    binding: CVectorBinding,
+    device: *mut DeviceId,
+    family: *mut CInputFamily,
 ) -> bool {
     unsafe {
-        let Some(manager) = ptr_to_ref_mut(manager) else {
+        let Some(manager) = ptr_to_ref(manager) else {
             return false;
         };
 
-        let Some(value) = convert_vector_binding(binding) else {
+        let (Some(last_device), Some(last_family)) = (manager.last_device(), manager.last_family())
+        else {
             return false;
         };
 
-        let Some(name) = InternString::from_raw(name) else {
+        set_if_valid(device, last_device);
+        set_if_valid(family, c_family(last_family));
+
+        return true;
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_set_device_switch_threshold(
+    manager: CInputManager,
+    threshold: f32,
+) {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
+            return;
+        };
+
+        manager.set_device_switch_threshold(threshold);
+    }
+}
+
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_poll_device_change(
+    manager: CInputManager,
+    change: *mut CDeviceChange,
+) -> bool {
+    unsafe {
+        let Some(manager) = ptr_to_ref_mut(manager) else {
             return false;
         };
 
-        let result = manager.remove_vector_binding(name.clone(), value).is_ok();
+        let Some(value) = manager.pop_device_change() else {
+            return false;
+        };
 
-        forget(name);
+        set_if_valid(
+            change,
+            CDeviceChange {
+                device: value.device,
+                family: c_family(value.family),
+                has_previous: value.previous.is_some(),
+                previous: value.previous.unwrap_or(DeviceId::null()),
+                previous_family: value.previous_family.map(c_family).unwrap_or(0),
+            },
+        );
 
-        return result;
+        return true;
+    }
+}
+
+/// `device` is only read for `INPUT_AGGREGATION_DEVICE`. `context` may be null for the bindings the manager was created with.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn pawkit_input_manager_set_aggregation(
+    manager: CInputManager,
+    context: *const u8,
+    name: *const u8,
+    aggregation: CDeviceAggregation,
+    device: DeviceId,
+) -> bool {
+    unsafe {
+        let aggregation = match aggregation {
+            INPUT_AGGREGATION_MAX_MAGNITUDE => DeviceAggregation::MaxMagnitude,
+            INPUT_AGGREGATION_SUM_CLAMP => DeviceAggregation::SumClamp,
+            INPUT_AGGREGATION_MOST_RECENT => DeviceAggregation::MostRecent,
+            INPUT_AGGREGATION_DEVICE => DeviceAggregation::Device(device),
+            _ => return false,
+        };
+
+        return manager_modify_action(
+            manager,
+            context,
+            name,
+            |manager, context, name| match context {
+                Some(context) => {
+                    return manager
+                        .set_context_aggregation(context, name, aggregation)
+                        .is_ok();
+                }
+                None => return manager.set_aggregation(name, aggregation).is_ok(),
+            },
+        );
     }
 }
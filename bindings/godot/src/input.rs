@@ -0,0 +1,294 @@
+use godot::{
+    builtin::{GString, Vector2},
+    global::godot_error,
+    obj::Gd,
+    prelude::{GodotClass, godot_api},
+};
+use pawkit_input::{
+    DeviceId,
+    binding::{
+        axis::{GamepadAxis, MouseAxis},
+        button::{GamepadButton, KeyboardButton, MouseButton},
+        map::BindingMap,
+    },
+    manager::{DeviceChange, InputFrame, InputManager},
+    state::{InputFamily, InputState},
+};
+use pawkit_interner::InternString;
+use serde::{
+    Deserialize,
+    de::value::{Error, StrDeserializer},
+};
+
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum PawkitInputFamily {
+    Keyboard = 0,
+    Mouse = 1,
+    Gamepad = 2,
+    Touch = 3,
+    Virtual = 4,
+}
+
+impl From<InputFamily> for PawkitInputFamily {
+    fn from(value: InputFamily) -> Self {
+        return match value {
+            InputFamily::Keyboard => Self::Keyboard,
+            InputFamily::Mouse => Self::Mouse,
+            InputFamily::Gamepad => Self::Gamepad,
+            InputFamily::Touch => Self::Touch,
+            InputFamily::Virtual => Self::Virtual,
+        };
+    }
+}
+
+/// One of the `FAMILY_` constants, which is `FAMILY_NONE` if there's no family.
+fn family_constant(family: Option<InputFamily>) -> i32 {
+    return family.map_or(PawkitInputManager::FAMILY_NONE, |it| {
+        PawkitInputFamily::from(it) as i32
+    });
+}
+
+/// Buttons and axes are named the same as in the bindings json, such as `"Space"` or `"LeftX"`.
+fn from_name<'de, T: Deserialize<'de>>(name: &'de str) -> Option<T> {
+    return match T::deserialize(StrDeserializer::<Error>::new(name)) {
+        Ok(it) => Some(it),
+        Err(err) => {
+            godot_error!("{}", err);
+            None
+        }
+    };
+}
+
+#[derive(GodotClass)]
+#[class(no_init)]
+struct PawkitInputDevice {
+    id: DeviceId,
+}
+
+#[godot_api]
+impl PawkitInputDevice {
+    fn new(id: DeviceId) -> Gd<Self> {
+        return Gd::from_init_fn(|_| Self { id });
+    }
+
+    #[func]
+    fn equals(&self, other: Gd<PawkitInputDevice>) -> bool {
+        return self.id == other.bind().id;
+    }
+}
+
+#[derive(GodotClass)]
+#[class(no_init)]
+struct PawkitInputState {
+    state: InputState,
+}
+
+#[godot_api]
+impl PawkitInputState {
+    #[func]
+    fn new() -> Gd<Self> {
+        return Gd::from_init_fn(|_| Self {
+            state: InputState::new(),
+        });
+    }
+
+    #[func]
+    fn connect_keyboard(&mut self) -> Gd<PawkitInputDevice> {
+        return PawkitInputDevice::new(self.state.connect_keyboard());
+    }
+
+    #[func]
+    fn connect_mouse(&mut self) -> Gd<PawkitInputDevice> {
+        return PawkitInputDevice::new(self.state.connect_mouse());
+    }
+
+    #[func]
+    fn connect_gamepad(&mut self) -> Gd<PawkitInputDevice> {
+        return PawkitInputDevice::new(self.state.connect_gamepad());
+    }
+
+    #[func]
+    fn disconnect_device(&mut self, device: Gd<PawkitInputDevice>) -> bool {
+        return self.state.disconnect_device(&device.bind().id);
+    }
+
+    #[func]
+    fn set_button(
+        &mut self,
+        device: Gd<PawkitInputDevice>,
+        button: GString,
+        pressed: bool,
+    ) -> bool {
+        let device = device.bind().id;
+        let button = button.to_string();
+
+        return match self.state.device_family(&device) {
+            Some(InputFamily::Keyboard) => from_name::<KeyboardButton>(&button)
+                .is_some_and(|it| self.state.set_keyboard_button(&device, it, pressed)),
+            Some(InputFamily::Mouse) => from_name::<MouseButton>(&button)
+                .is_some_and(|it| self.state.set_mouse_button(&device, it, pressed)),
+            Some(InputFamily::Gamepad) => from_name::<GamepadButton>(&button)
+                .is_some_and(|it| self.state.set_gamepad_button(&device, it, pressed)),
+            _ => false,
+        };
+    }
+
+    #[func]
+    fn set_axis(&mut self, device: Gd<PawkitInputDevice>, axis: GString, value: f32) -> bool {
+        let device = device.bind().id;
+        let axis = axis.to_string();
+
+        return match self.state.device_family(&device) {
+            Some(InputFamily::Mouse) => from_name::<MouseAxis>(&axis)
+                .is_some_and(|it| self.state.set_mouse_axis(&device, it, value)),
+            Some(InputFamily::Gamepad) => from_name::<GamepadAxis>(&axis)
+                .is_some_and(|it| self.state.set_gamepad_axis(&device, it, value)),
+            _ => false,
+        };
+    }
+
+    #[func]
+    fn next_frame(&mut self) {
+        self.state.next_frame();
+    }
+}
+
+#[derive(GodotClass)]
+#[class(no_init)]
+struct PawkitInputManager {
+    manager: InputManager,
+}
+
+#[godot_api]
+impl PawkitInputManager {
+    #[constant]
+    const FAMILY_NONE: i32 = -1;
+    #[constant]
+    const FAMILY_KEYBOARD: i32 = PawkitInputFamily::Keyboard as i32;
+    #[constant]
+    const FAMILY_MOUSE: i32 = PawkitInputFamily::Mouse as i32;
+    #[constant]
+    const FAMILY_GAMEPAD: i32 = PawkitInputFamily::Gamepad as i32;
+    #[constant]
+    const FAMILY_TOUCH: i32 = PawkitInputFamily::Touch as i32;
+    #[constant]
+    const FAMILY_VIRTUAL: i32 = PawkitInputFamily::Virtual as i32;
+
+    /// Takes the bindings as json.
+    #[func]
+    fn new(bindings: GString) -> Option<Gd<Self>> {
+        let bindings = match serde_json::from_str::<BindingMap>(&bindings.to_string()) {
+            Ok(it) => it,
+            Err(err) => {
+                godot_error!("{}", err);
+                return None;
+            }
+        };
+
+        return Some(Gd::from_init_fn(|_| Self {
+            manager: InputManager::new(bindings),
+        }));
+    }
+
+    #[func]
+    fn connect_device(&mut self, device: Gd<PawkitInputDevice>) {
+        self.manager.connect_device(device.bind().id);
+    }
+
+    #[func]
+    fn disconnect_device(&mut self, device: Gd<PawkitInputDevice>) {
+        self.manager.disconnect_device(device.bind().id);
+    }
+
+    #[func]
+    fn update(&mut self, state: Gd<PawkitInputState>, delta_time: f32) {
+        self.manager.update(&state.bind().state, delta_time);
+    }
+
+    #[func]
+    fn is_pressed(&self, name: GString) -> bool {
+        let Some(InputFrame::Digital(frame)) = self.frame(name) else {
+            return false;
+        };
+
+        return frame.pressed;
+    }
+
+    #[func]
+    fn get_analog(&self, name: GString) -> f32 {
+        let Some(InputFrame::Analog(frame)) = self.frame(name) else {
+            return 0f32;
+        };
+
+        return frame.value;
+    }
+
+    #[func]
+    fn get_vector(&self, name: GString) -> Vector2 {
+        let Some(InputFrame::Vector(frame)) = self.frame(name) else {
+            return Vector2::ZERO;
+        };
+
+        return Vector2::new(frame.value[0], frame.value[1]);
+    }
+
+    fn frame(&self, name: GString) -> Option<InputFrame> {
+        return self
+            .manager
+            .get_binding(&InternString::new(&name.to_string()));
+    }
+
+    /// The device that most recently drove an action, for switching prompts between keyboard and controller.
+    #[func]
+    fn last_device(&self) -> Option<Gd<PawkitInputDevice>> {
+        return self.manager.last_device().map(PawkitInputDevice::new);
+    }
+
+    /// One of the `FAMILY_` constants.
+    #[func]
+    fn last_family(&self) -> i32 {
+        return family_constant(self.manager.last_family());
+    }
+
+    #[func]
+    fn set_device_switch_threshold(&mut self, threshold: f32) {
+        self.manager.set_device_switch_threshold(threshold);
+    }
+
+    #[func]
+    fn poll_device_change(&mut self) -> Option<Gd<PawkitInputDeviceChange>> {
+        let change = self.manager.pop_device_change()?;
+
+        return Some(Gd::from_init_fn(|_| PawkitInputDeviceChange { change }));
+    }
+}
+
+#[derive(GodotClass)]
+#[class(no_init)]
+struct PawkitInputDeviceChange {
+    change: DeviceChange,
+}
+
+#[godot_api]
+impl PawkitInputDeviceChange {
+    #[func]
+    fn device(&self) -> Gd<PawkitInputDevice> {
+        return PawkitInputDevice::new(self.change.device);
+    }
+
+    #[func]
+    fn family(&self) -> i32 {
+        return family_constant(Some(self.change.family));
+    }
+
+    #[func]
+    fn previous(&self) -> Option<Gd<PawkitInputDevice>> {
+        return self.change.previous.map(PawkitInputDevice::new);
+    }
+
+    #[func]
+    fn previous_family(&self) -> i32 {
+        return family_constant(self.change.previous_family);
+    }
+}
@@ -1,6 +1,7 @@
 use godot::prelude::*;
 use pawkit_logger::{LoggerCallbacks, set_logger_callbacks};
 
+pub mod input;
 pub mod net;
 
 struct PawkitGodot;
@@ -1,344 +1,1030 @@
-// use mlua::prelude::*;
-// use pawkit_input::{
-//     InputManager,
-//     bindings::{
-//         axis::{GamepadAxis, MouseAxis},
-//         button::{GamepadButton, KeyboardButton, MouseButton},
-//     },
-//     manager::InputFamily,
-// };
-
-// use crate::lua_enum;
-
-// lua_enum!(keyboard_buttons : str {
-//     A, B, C, D, E, F, G, H, I, J, K, L, M,
-//     N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
-
-//     Number0, Number1, Number2, Number3,
-//     Number4, Number5, Number6, Number7,
-//     Number8, Number9,
-
-//     Up, Down, Left, Right,
-
-//     Tilde, Grave, Minus, Plus,
-//     LeftBracket, RightBracket,
-//     Semicolon, Quote, Comma,
-//     Period, Slash, BackSlash,
-
-//     LeftShift, RightShift,
-//     LeftControl, RightControl,
-//     LeftAlt, RightAlt,
-//     LeftMeta, RightMeta,
-
-//     Menu, Enter, Escape, Space,
-//     Tab, Backspace, Insert,
-//     Delete, PageUp, PageDown,
-//     Home, End, CapsLock, ScrollLock,
-//     PrintScreen, Pause, NumLock,
-//     Clear, Sleep,
-
-//     Numpad0, Numpad1, Numpad2, Numpad3,
-//     Numpad4, Numpad5, Numpad6, Numpad7,
-//     Numpad8, Numpad9, NumpadDivide,
-//     NumpadMultiply, NumpadMinus,
-//     NumpadPlus, NumpadDecimal,
-//     NumpadPeriod, NumpadEnter,
-
-//     F1, F2, F3, F4, F5, F6, F7, F8, F9,
-//     F10, F11, F12, F13, F14, F15, F16,
-//     F17, F18, F19, F20, F21, F22, F23,
-//     F24,
-// });
-
-// lua_enum!(mouse_buttons : str {
-//     Left,
-//     Right,
-//     Middle,
-//     Side1,
-//     Side2,
-// });
-
-// lua_enum!(gamepad_buttons : str {
-//     South,
-//     East,
-//     West,
-//     North,
-//     Back,
-//     Guide,
-//     Start,
-//     LeftStick,
-//     RightStick,
-//     LeftShoulder,
-//     RightShoulder,
-//     DpadUp,
-//     DpadDown,
-//     DpadLeft,
-//     DpadRight,
-//     Misc1,
-//     RightPaddle1,
-//     LeftPaddle1,
-//     RightPaddle2,
-//     LeftPaddle2,
-//     Touchpad,
-//     Misc2,
-//     Misc3,
-//     Misc4,
-//     Misc5,
-//     Misc6,
-// });
-
-// lua_enum!(mouse_axes : str {
-//     DeltaX,
-//     DeltaY,
-//     WheelX,
-//     WheelY,
-// });
-
-// lua_enum!(gamepad_axes : str {
-//     LeftX,
-//     LeftY,
-//     RightX,
-//     RightY,
-//     LeftTrigger,
-//     RightTrigger,
-// });
-
-// lua_enum!(input_family : str {
-//     Keyboard,
-//     Mouse,
-//     Gamepad,
-// });
-
-// pub(super) fn init(lua: &Lua) -> LuaResult<LuaTable> {
-//     let exports = lua.create_table()?;
-
-//     exports.set("keyboard_buttons", keyboard_buttons(lua)?)?;
-//     exports.set("mouse_buttons", mouse_buttons(lua)?)?;
-//     exports.set("gamepad_buttons", gamepad_buttons(lua)?)?;
-
-//     exports.set("mouse_axes", mouse_axes(lua)?)?;
-//     exports.set("gamepad_axes", gamepad_axes(lua)?)?;
-
-//     exports.set("families", input_family(lua)?)?;
-
-//     exports.set("create_manager", lua.create_function(LuaInputManager::new)?)?;
-
-//     return Ok(exports);
-// }
-
-// struct LuaInputManager {
-//     manager: InputManager,
-// }
-
-// impl LuaInputManager {
-//     fn new(_lua: &Lua, _args: ()) -> LuaResult<Self> {
-//         return Ok(Self {
-//             manager: InputManager::new(),
-//         });
-//     }
-
-//     fn register_digital_binding(
-//         lua: &Lua,
-//         this: &mut Self,
-//         args: (String, LuaValue),
-//     ) -> LuaResult<()> {
-//         let bindings: Vec<_> = lua.from_value(args.1)?;
-
-//         if let Err(err) = this.manager.register_digital(&args.0, &bindings) {
-//             return Err(LuaError::RuntimeError(err.to_string()));
-//         }
-
-//         return Ok(());
-//     }
-
-//     fn register_analog_binding(
-//         lua: &Lua,
-//         this: &mut Self,
-//         args: (String, LuaValue),
-//     ) -> LuaResult<()> {
-//         let bindings: Vec<_> = lua.from_value(args.1)?;
-
-//         if let Err(err) = this.manager.register_analog(&args.0, &bindings) {
-//             return Err(LuaError::RuntimeError(err.to_string()));
-//         }
-
-//         return Ok(());
-//     }
-
-//     fn register_vector_binding(
-//         lua: &Lua,
-//         this: &mut Self,
-//         args: (String, LuaValue),
-//     ) -> LuaResult<()> {
-//         let bindings: Vec<_> = lua.from_value(args.1)?;
-
-//         if let Err(err) = this.manager.register_vector(&args.0, &bindings) {
-//             return Err(LuaError::RuntimeError(err.to_string()));
-//         }
-
-//         return Ok(());
-//     }
-
-//     fn lock_bindings(_lua: &Lua, this: &mut Self, _args: ()) -> LuaResult<()> {
-//         this.manager.lock();
-
-//         return Ok(());
-//     }
-
-//     fn device_connected(lua: &Lua, this: &Self, args: (LuaValue, usize)) -> LuaResult<usize> {
-//         let family = lua.from_value(args.0)?;
-
-//         let id = match family {
-//             InputFamily::Keyboard => &this.manager.devices.keyboard_manager,
-//             InputFamily::Mouse => &this.manager.devices.mouse_manager,
-//             InputFamily::Gamepad => &this.manager.devices.gamepad_manager,
-//         }
-//         .device_connected(args.1);
-
-//         return Ok(id);
-//     }
-
-//     fn device_disconnected(lua: &Lua, this: &Self, args: (LuaValue, usize)) -> LuaResult<()> {
-//         let family = lua.from_value(args.0)?;
-
-//         match family {
-//             InputFamily::Keyboard => &this.manager.devices.keyboard_manager,
-//             InputFamily::Mouse => &this.manager.devices.mouse_manager,
-//             InputFamily::Gamepad => &this.manager.devices.gamepad_manager,
-//         }
-//         .device_disconnected(args.1);
-
-//         return Ok(());
-//     }
-
-//     fn set_button(
-//         lua: &Lua,
-//         this: &Self,
-//         args: (LuaValue, usize, LuaValue, bool),
-//     ) -> LuaResult<()> {
-//         let family = lua.from_value(args.0)?;
-
-//         let Some(state) = match family {
-//             InputFamily::Keyboard => &this.manager.devices.keyboard_manager,
-//             InputFamily::Mouse => &this.manager.devices.mouse_manager,
-//             InputFamily::Gamepad => &this.manager.devices.gamepad_manager,
-//         }
-//         .get_state(args.1) else {
-//             return Err(LuaError::RuntimeError("Index does not exist.".into()));
-//         };
-
-//         let index = match family {
-//             InputFamily::Keyboard => lua.from_value::<KeyboardButton>(args.2)? as usize,
-//             InputFamily::Mouse => lua.from_value::<MouseButton>(args.2)? as usize,
-//             InputFamily::Gamepad => lua.from_value::<GamepadButton>(args.2)? as usize,
-//         };
-
-//         state.set_digital(index, args.3);
-
-//         return Ok(());
-//     }
-
-//     fn set_axis(lua: &Lua, this: &Self, args: (LuaValue, usize, LuaValue, f32)) -> LuaResult<()> {
-//         let family = lua.from_value(args.0)?;
-
-//         let Some(state) = match family {
-//             InputFamily::Keyboard => &this.manager.devices.keyboard_manager,
-//             InputFamily::Mouse => &this.manager.devices.mouse_manager,
-//             InputFamily::Gamepad => &this.manager.devices.gamepad_manager,
-//         }
-//         .get_state(args.1) else {
-//             return Err(LuaError::RuntimeError("Index does not exist.".into()));
-//         };
-
-//         let index = match family {
-//             InputFamily::Keyboard => {
-//                 return Err(LuaError::RuntimeError(
-//                     "Keyboard does not have any axes".into(),
-//                 ));
-//             }
-//             InputFamily::Mouse => lua.from_value::<MouseAxis>(args.2)? as usize,
-//             InputFamily::Gamepad => lua.from_value::<GamepadAxis>(args.2)? as usize,
-//         };
-
-//         state.set_analog(index, args.3);
-
-//         return Ok(());
-//     }
-
-//     fn create_handler(_lua: &Lua, this: &Self, _args: ()) -> LuaResult<Option<usize>> {
-//         return Ok(this.manager.create_handler());
-//     }
-
-//     fn destroy_handler(_lua: &Lua, this: &Self, args: (usize,)) -> LuaResult<()> {
-//         this.manager.destroy_handler(args.0);
-
-//         return Ok(());
-//     }
-
-//     fn update(_lua: &Lua, this: &Self, _args: ()) -> LuaResult<()> {
-//         this.manager.update();
-
-//         return Ok(());
-//     }
-
-//     fn get_frame(lua: &Lua, this: &Self, args: (usize, String)) -> LuaResult<Option<LuaValue>> {
-//         let Some(frame) = this.manager.get_frame(args.0, &args.1) else {
-//             return Ok(None);
-//         };
-
-//         return Ok(Some(lua.to_value(&frame)?));
-//     }
-
-//     fn connect_device_to_handler(
-//         lua: &Lua,
-//         this: &Self,
-//         args: (usize, LuaValue, usize),
-//     ) -> LuaResult<()> {
-//         let family = lua.from_value(args.1)?;
-
-//         this.manager
-//             .connect_device_to_handler(args.0, family, args.2);
-
-//         return Ok(());
-//     }
-
-//     fn disconnect_device_from_handler(
-//         lua: &Lua,
-//         this: &Self,
-//         args: (usize, LuaValue, usize),
-//     ) -> LuaResult<()> {
-//         let family = lua.from_value(args.1)?;
-
-//         this.manager
-//             .disconnect_device_from_handler(args.0, family, args.2);
-
-//         return Ok(());
-//     }
-// }
-
-// impl LuaUserData for LuaInputManager {
-//     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
-//         methods.add_method_mut("register_digital_binding", Self::register_digital_binding);
-//         methods.add_method_mut("register_analog_binding", Self::register_analog_binding);
-//         methods.add_method_mut("register_vector_binding", Self::register_vector_binding);
-//         methods.add_method_mut("lock_bindings", Self::lock_bindings);
-
-//         methods.add_method("device_connected", Self::device_connected);
-//         methods.add_method("device_disconnected", Self::device_disconnected);
-
-//         methods.add_method("set_button", Self::set_button);
-//         methods.add_method("set_axis", Self::set_axis);
-
-//         methods.add_method("create_handler", Self::create_handler);
-//         methods.add_method("destroy_handler", Self::destroy_handler);
-
-//         methods.add_method("update", Self::update);
-//         methods.add_method("get_frame", Self::get_frame);
-
-//         methods.add_method("connect_device_to_handler", Self::connect_device_to_handler);
-//         methods.add_method(
-//             "disconnect_device_from_handler",
-//             Self::disconnect_device_from_handler,
-//         );
-//     }
-// }
+use mlua::prelude::*;
+use pawkit_input::{
+    DeviceId,
+    aggregation::DeviceAggregation,
+    binding::{
+        BindingKind, BindingList, DigitalBinding,
+        axis::{GamepadAxis, MouseAxis, VirtualAxis},
+        button::{GamepadButton, KeyboardButton, MouseButton, VirtualButton},
+        map::BindingMap,
+    },
+    capture::{CaptureOptions, CaptureResult, CapturedBinding, InputCapture},
+    context::ContextOptions,
+    glyph::ControllerStyle,
+    haptics::HapticEffect,
+    history::ActionValue,
+    layout::KeyboardLayout,
+    manager::{InputFrame, InputManager},
+    motion::MotionSample,
+    state::{InputFamily, InputState},
+    text::TextEvent,
+    touch::TouchContact,
+    virtual_device::VirtualLayout,
+};
+use pawkit_interner::InternString;
+
+use crate::lua_enum;
+
+lua_enum!(keyboard_buttons : str {
+    A, B, C, D, E, F, G, H, I, J, K, L, M,
+    N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+
+    Number0, Number1, Number2, Number3,
+    Number4, Number5, Number6, Number7,
+    Number8, Number9,
+
+    Up, Down, Left, Right,
+
+    Tilde, Grave, Minus, Plus,
+    LeftBracket, RightBracket,
+    Semicolon, Quote, Comma,
+    Period, Slash, BackSlash,
+
+    LeftShift, RightShift,
+    LeftControl, RightControl,
+    LeftAlt, RightAlt,
+    LeftMeta, RightMeta,
+
+    Menu, Enter, Escape, Space,
+    Tab, Backspace, Insert,
+    Delete, PageUp, PageDown,
+    Home, End, CapsLock, ScrollLock,
+    PrintScreen, Pause, NumLock,
+    Clear, Sleep,
+
+    Numpad0, Numpad1, Numpad2, Numpad3,
+    Numpad4, Numpad5, Numpad6, Numpad7,
+    Numpad8, Numpad9, NumpadDivide,
+    NumpadMultiply, NumpadMinus,
+    NumpadPlus, NumpadDecimal,
+    NumpadPeriod, NumpadEnter,
+
+    F1, F2, F3, F4, F5, F6, F7, F8, F9,
+    F10, F11, F12, F13, F14, F15, F16,
+    F17, F18, F19, F20, F21, F22, F23,
+    F24,
+});
+
+lua_enum!(mouse_buttons : str {
+    Left,
+    Right,
+    Middle,
+    Side1,
+    Side2,
+});
+
+lua_enum!(gamepad_buttons : str {
+    South,
+    East,
+    West,
+    North,
+    Back,
+    Guide,
+    Start,
+    LeftStick,
+    RightStick,
+    LeftShoulder,
+    RightShoulder,
+    DpadUp,
+    DpadDown,
+    DpadLeft,
+    DpadRight,
+    Misc1,
+    RightPaddle1,
+    LeftPaddle1,
+    RightPaddle2,
+    LeftPaddle2,
+    Touchpad,
+    Misc2,
+    Misc3,
+    Misc4,
+    Misc5,
+    Misc6,
+});
+
+lua_enum!(touch_buttons : str {
+    Touch,
+    MultiTouch,
+    Tap,
+    SwipeLeft,
+    SwipeRight,
+    SwipeUp,
+    SwipeDown,
+});
+
+lua_enum!(mouse_axes : str {
+    DeltaX,
+    DeltaY,
+    WheelX,
+    WheelY,
+    X,
+    Y,
+    NormalizedX,
+    NormalizedY,
+});
+
+lua_enum!(gamepad_axes : str {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+    GyroX,
+    GyroY,
+    GyroZ,
+    AccelX,
+    AccelY,
+    AccelZ,
+});
+
+lua_enum!(touch_axes : str {
+    X,
+    Y,
+    Pressure,
+    Pinch,
+    PanX,
+    PanY,
+});
+
+lua_enum!(input_family : str {
+    Keyboard,
+    Mouse,
+    Gamepad,
+    Touch,
+    Virtual,
+});
+
+lua_enum!(device_aggregations : str {
+    MaxMagnitude,
+    SumClamp,
+    MostRecent,
+    Device,
+});
+
+lua_enum!(controller_styles : str {
+    Generic,
+    Xbox,
+    PlayStation,
+    Nintendo,
+});
+
+lua_enum!(input_consumption : str {
+    Passthrough,
+    Bound,
+    All,
+});
+
+pub(super) fn init(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+
+    exports.set("keyboard_buttons", keyboard_buttons(lua)?)?;
+    exports.set("mouse_buttons", mouse_buttons(lua)?)?;
+    exports.set("gamepad_buttons", gamepad_buttons(lua)?)?;
+    exports.set("touch_buttons", touch_buttons(lua)?)?;
+
+    exports.set("mouse_axes", mouse_axes(lua)?)?;
+    exports.set("gamepad_axes", gamepad_axes(lua)?)?;
+    exports.set("touch_axes", touch_axes(lua)?)?;
+
+    exports.set("families", input_family(lua)?)?;
+    exports.set("aggregations", device_aggregations(lua)?)?;
+    exports.set("consumption", input_consumption(lua)?)?;
+    exports.set("controller_styles", controller_styles(lua)?)?;
+
+    exports.set("create_state", lua.create_function(LuaInputState::new)?)?;
+    exports.set("create_manager", lua.create_function(LuaInputManager::new)?)?;
+
+    return Ok(exports);
+}
+
+fn family_name(family: InputFamily) -> &'static str {
+    return match family {
+        InputFamily::Keyboard => "Keyboard",
+        InputFamily::Mouse => "Mouse",
+        InputFamily::Gamepad => "Gamepad",
+        InputFamily::Touch => "Touch",
+        InputFamily::Virtual => "Virtual",
+    };
+}
+
+#[derive(Clone, Copy)]
+struct LuaDeviceId(DeviceId);
+
+impl LuaUserData for LuaDeviceId {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method(LuaMetaMethod::Eq, |_lua, this, args: (LuaAnyUserData,)| {
+            return Ok(this.0 == args.0.borrow::<Self>()?.0);
+        });
+
+        methods.add_meta_method(LuaMetaMethod::ToString, |_lua, this, _args: ()| {
+            return Ok(format!("{:?}", this.0));
+        });
+    }
+}
+
+fn device_id(value: &LuaAnyUserData) -> LuaResult<DeviceId> {
+    return Ok(value.borrow::<LuaDeviceId>()?.0);
+}
+
+struct LuaInputState {
+    state: InputState,
+}
+
+impl LuaInputState {
+    fn new(_lua: &Lua, _args: ()) -> LuaResult<Self> {
+        return Ok(Self {
+            state: InputState::new(),
+        });
+    }
+
+    fn connect_keyboard(_lua: &Lua, this: &mut Self, _args: ()) -> LuaResult<LuaDeviceId> {
+        return Ok(LuaDeviceId(this.state.connect_keyboard()));
+    }
+
+    fn connect_mouse(_lua: &Lua, this: &mut Self, _args: ()) -> LuaResult<LuaDeviceId> {
+        return Ok(LuaDeviceId(this.state.connect_mouse()));
+    }
+
+    fn connect_gamepad(_lua: &Lua, this: &mut Self, _args: ()) -> LuaResult<LuaDeviceId> {
+        return Ok(LuaDeviceId(this.state.connect_gamepad()));
+    }
+
+    fn connect_touch(_lua: &Lua, this: &mut Self, _args: ()) -> LuaResult<LuaDeviceId> {
+        return Ok(LuaDeviceId(this.state.connect_touch()));
+    }
+
+    fn connect_virtual(_lua: &Lua, this: &mut Self, args: (u8, u8)) -> LuaResult<LuaDeviceId> {
+        return Ok(LuaDeviceId(this.state.connect_virtual(VirtualLayout {
+            buttons: args.0,
+            axes: args.1,
+        })));
+    }
+
+    fn disconnect_device(_lua: &Lua, this: &mut Self, args: (LuaAnyUserData,)) -> LuaResult<bool> {
+        return Ok(this.state.disconnect_device(&device_id(&args.0)?));
+    }
+
+    fn device_family(
+        _lua: &Lua,
+        this: &Self,
+        args: (LuaAnyUserData,),
+    ) -> LuaResult<Option<&'static str>> {
+        return Ok(this
+            .state
+            .device_family(&device_id(&args.0)?)
+            .map(family_name));
+    }
+
+    fn set_button(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, LuaValue, bool),
+    ) -> LuaResult<bool> {
+        let device = device_id(&args.0)?;
+
+        let Some(family) = this.state.device_family(&device) else {
+            return Ok(false);
+        };
+
+        return Ok(match family {
+            InputFamily::Keyboard => {
+                let button = lua.from_value::<KeyboardButton>(args.1)?;
+
+                this.state.set_keyboard_button(&device, button, args.2)
+            }
+            InputFamily::Mouse => {
+                let button = lua.from_value::<MouseButton>(args.1)?;
+
+                this.state.set_mouse_button(&device, button, args.2)
+            }
+            InputFamily::Gamepad => {
+                let button = lua.from_value::<GamepadButton>(args.1)?;
+
+                this.state.set_gamepad_button(&device, button, args.2)
+            }
+            InputFamily::Touch => {
+                return Err(LuaError::RuntimeError(
+                    "Touch buttons are set with set_touch_contact".into(),
+                ));
+            }
+            InputFamily::Virtual => {
+                let button = lua.from_value::<VirtualButton>(args.1)?;
+
+                this.state.set_virtual_button(&device, button, args.2)
+            }
+        });
+    }
+
+    fn set_axis(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, LuaValue, f32),
+    ) -> LuaResult<bool> {
+        let device = device_id(&args.0)?;
+
+        let Some(family) = this.state.device_family(&device) else {
+            return Ok(false);
+        };
+
+        return Ok(match family {
+            InputFamily::Keyboard => {
+                return Err(LuaError::RuntimeError(
+                    "Keyboard does not have any axes".into(),
+                ));
+            }
+            InputFamily::Mouse => {
+                let axis = lua.from_value::<MouseAxis>(args.1)?;
+
+                this.state.set_mouse_axis(&device, axis, args.2)
+            }
+            InputFamily::Gamepad => {
+                let axis = lua.from_value::<GamepadAxis>(args.1)?;
+
+                this.state.set_gamepad_axis(&device, axis, args.2)
+            }
+            InputFamily::Touch => {
+                return Err(LuaError::RuntimeError(
+                    "Touch axes are set with set_touch_contact".into(),
+                ));
+            }
+            InputFamily::Virtual => {
+                let axis = lua.from_value::<VirtualAxis>(args.1)?;
+
+                this.state.set_virtual_axis(&device, axis, args.2)
+            }
+        });
+    }
+
+    /// Takes the device, the finger id, x and y normalized to the screen, and an optional pressure.
+    fn set_touch_contact(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, u64, f32, f32, Option<f32>),
+    ) -> LuaResult<bool> {
+        let contact = TouchContact {
+            id: args.1,
+            position: [args.2, args.3],
+            pressure: args.4.unwrap_or(1f32),
+        };
+
+        return Ok(this.state.set_touch_contact(&device_id(&args.0)?, contact));
+    }
+
+    /// Takes the device, the timestamp in microseconds, and the gyro and accelerometer readings as `{ x, y, z }` tables.
+    fn set_gamepad_motion(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, u64, LuaTable, LuaTable),
+    ) -> LuaResult<bool> {
+        let vector = |table: &LuaTable| -> LuaResult<[f32; 3]> {
+            return Ok([table.get("x")?, table.get("y")?, table.get("z")?]);
+        };
+
+        let sample = MotionSample {
+            timestamp: args.1,
+            gyro: vector(&args.2)?,
+            accel: vector(&args.3)?,
+        };
+
+        return Ok(this.state.set_gamepad_motion(&device_id(&args.0)?, sample));
+    }
+
+    fn set_mouse_viewport(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, f32, f32),
+    ) -> LuaResult<bool> {
+        return Ok(this
+            .state
+            .set_mouse_viewport(&device_id(&args.0)?, [args.1, args.2]));
+    }
+
+    fn set_cursor_locked(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, bool),
+    ) -> LuaResult<bool> {
+        return Ok(this.state.set_cursor_locked(&device_id(&args.0)?, args.1));
+    }
+
+    fn is_cursor_locked(_lua: &Lua, this: &Self, args: (LuaAnyUserData,)) -> LuaResult<bool> {
+        return Ok(this.state.is_cursor_locked(&device_id(&args.0)?) == Some(true));
+    }
+
+    fn set_controller_style(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, LuaValue),
+    ) -> LuaResult<bool> {
+        let style = lua.from_value::<ControllerStyle>(args.1)?;
+
+        return Ok(this.state.set_controller_style(&device_id(&args.0)?, style));
+    }
+
+    /// Takes a table from each physical key to the key printed on it, such as `{ Q = "A", A = "Q" }`.
+    fn set_keyboard_layout(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, LuaValue),
+    ) -> LuaResult<bool> {
+        let layout = lua.from_value::<KeyboardLayout>(args.1)?;
+
+        return Ok(this.state.set_keyboard_layout(&device_id(&args.0)?, layout));
+    }
+
+    fn set_text_input_active(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, bool),
+    ) -> LuaResult<bool> {
+        return Ok(this
+            .state
+            .set_text_input_active(&device_id(&args.0)?, args.1));
+    }
+
+    /// Pushes an event table, such as `{ type = "Commit", text = "hi" }`.
+    fn push_text_event(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, LuaValue),
+    ) -> LuaResult<bool> {
+        let event = lua.from_value::<TextEvent>(args.1)?;
+
+        return Ok(this.state.push_text_event(&device_id(&args.0)?, event));
+    }
+
+    /// Returns every queued text event as a list of tables.
+    fn drain_text_events(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData,),
+    ) -> LuaResult<LuaValue> {
+        return lua.to_value(&this.state.drain_text_events(&device_id(&args.0)?));
+    }
+
+    /// Returns the text an IME is composing, and the cursor as a byte range, or nil.
+    fn text_composition(
+        lua: &Lua,
+        this: &Self,
+        args: (LuaAnyUserData,),
+    ) -> LuaResult<Option<LuaValue>> {
+        let Some(composition) = this.state.text_composition(&device_id(&args.0)?) else {
+            return Ok(None);
+        };
+
+        return Ok(Some(lua.to_value(composition)?));
+    }
+
+    /// Takes the device and an effect table, such as `{ type = "Rumble", low_frequency = 1, high_frequency = 0.5, envelope = { hold = 0.2 } }`.
+    fn play_haptic(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, LuaValue),
+    ) -> LuaResult<bool> {
+        let effect = lua.from_value::<HapticEffect>(args.1)?;
+
+        return Ok(this.state.play_haptic(&device_id(&args.0)?, effect));
+    }
+
+    fn stop_haptics(_lua: &Lua, this: &mut Self, args: (LuaAnyUserData,)) -> LuaResult<bool> {
+        return Ok(this.state.stop_haptics(&device_id(&args.0)?));
+    }
+
+    /// Returns the motor strengths to apply this frame, or nil if the motors should be left alone.
+    fn drain_haptics(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, f32),
+    ) -> LuaResult<Option<LuaValue>> {
+        let Some(output) = this.state.drain_haptics(&device_id(&args.0)?, args.1) else {
+            return Ok(None);
+        };
+
+        return Ok(Some(lua.to_value(&output)?));
+    }
+
+    fn begin_gyro_calibration(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData,),
+    ) -> LuaResult<bool> {
+        return Ok(this.state.begin_gyro_calibration(&device_id(&args.0)?));
+    }
+
+    /// Returns whether the calibration succeeded.
+    fn end_gyro_calibration(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData,),
+    ) -> LuaResult<bool> {
+        return Ok(this
+            .state
+            .end_gyro_calibration(&device_id(&args.0)?)
+            .is_some());
+    }
+
+    fn remove_touch_contact(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, u64),
+    ) -> LuaResult<bool> {
+        return Ok(this
+            .state
+            .remove_touch_contact(&device_id(&args.0)?, args.1));
+    }
+
+    /// Takes the device, the action name, and a value shaped like the action's kind.
+    fn set_virtual_action(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, String, LuaValue),
+    ) -> LuaResult<bool> {
+        let value = lua.from_value::<ActionValue>(args.2)?;
+
+        return Ok(this.state.set_virtual_action(
+            &device_id(&args.0)?,
+            InternString::new(&args.1),
+            value,
+        ));
+    }
+
+    fn clear_virtual_action(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData, String),
+    ) -> LuaResult<bool> {
+        return Ok(this
+            .state
+            .clear_virtual_action(&device_id(&args.0)?, &InternString::new(&args.1)));
+    }
+
+    fn next_frame(_lua: &Lua, this: &mut Self, _args: ()) -> LuaResult<()> {
+        this.state.next_frame();
+
+        return Ok(());
+    }
+
+    /// Takes the binding kind, and an optional table with `threshold`, `deadzone`, `cancel` bindings
+    /// and `logical_keys`.
+    fn begin_capture(
+        lua: &Lua,
+        this: &Self,
+        args: (LuaValue, Option<LuaTable>),
+    ) -> LuaResult<LuaInputCapture> {
+        let mut options = CaptureOptions::new(lua.from_value::<BindingKind>(args.0)?);
+
+        if let Some(table) = args.1 {
+            if let Some(threshold) = table.get::<Option<f32>>("threshold")? {
+                options.threshold = threshold;
+            }
+
+            if let Some(deadzone) = table.get::<Option<f32>>("deadzone")? {
+                options.deadzone = deadzone;
+            }
+
+            if let Some(cancel) = table.get::<Option<LuaValue>>("cancel")? {
+                options.cancel = lua.from_value::<Vec<DigitalBinding>>(cancel)?;
+            }
+
+            if let Some(logical_keys) = table.get::<Option<bool>>("logical_keys")? {
+                options.logical_keys = logical_keys;
+            }
+        }
+
+        return Ok(LuaInputCapture {
+            capture: this.state.begin_capture(options),
+        });
+    }
+}
+
+impl LuaUserData for LuaInputState {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("connect_keyboard", Self::connect_keyboard);
+        methods.add_method_mut("connect_mouse", Self::connect_mouse);
+        methods.add_method_mut("connect_gamepad", Self::connect_gamepad);
+        methods.add_method_mut("connect_touch", Self::connect_touch);
+        methods.add_method_mut("connect_virtual", Self::connect_virtual);
+        methods.add_method_mut("disconnect_device", Self::disconnect_device);
+        methods.add_method("device_family", Self::device_family);
+
+        methods.add_method_mut("set_button", Self::set_button);
+        methods.add_method_mut("set_axis", Self::set_axis);
+        methods.add_method_mut("next_frame", Self::next_frame);
+        methods.add_method_mut("set_touch_contact", Self::set_touch_contact);
+        methods.add_method_mut("set_virtual_action", Self::set_virtual_action);
+        methods.add_method_mut("clear_virtual_action", Self::clear_virtual_action);
+        methods.add_method_mut("remove_touch_contact", Self::remove_touch_contact);
+        methods.add_method_mut("set_gamepad_motion", Self::set_gamepad_motion);
+        methods.add_method_mut("begin_gyro_calibration", Self::begin_gyro_calibration);
+        methods.add_method_mut("end_gyro_calibration", Self::end_gyro_calibration);
+
+        methods.add_method_mut("set_mouse_viewport", Self::set_mouse_viewport);
+        methods.add_method_mut("set_cursor_locked", Self::set_cursor_locked);
+        methods.add_method("is_cursor_locked", Self::is_cursor_locked);
+
+        methods.add_method_mut("set_controller_style", Self::set_controller_style);
+
+        methods.add_method_mut("set_keyboard_layout", Self::set_keyboard_layout);
+        methods.add_method_mut("set_text_input_active", Self::set_text_input_active);
+        methods.add_method_mut("push_text_event", Self::push_text_event);
+        methods.add_method_mut("drain_text_events", Self::drain_text_events);
+        methods.add_method("text_composition", Self::text_composition);
+
+        methods.add_method_mut("play_haptic", Self::play_haptic);
+        methods.add_method_mut("stop_haptics", Self::stop_haptics);
+        methods.add_method_mut("drain_haptics", Self::drain_haptics);
+
+        methods.add_method("begin_capture", Self::begin_capture);
+    }
+}
+
+struct LuaInputCapture {
+    capture: InputCapture,
+}
+
+impl LuaInputCapture {
+    /// Returns "Pending", "Cancelled", or "Captured" along with the binding.
+    fn poll(
+        lua: &Lua,
+        this: &mut Self,
+        args: (LuaAnyUserData,),
+    ) -> LuaResult<(&'static str, Option<LuaValue>)> {
+        let state = args.0.borrow::<LuaInputState>()?;
+
+        match this.capture.poll(&state.state) {
+            CaptureResult::Pending => return Ok(("Pending", None)),
+            CaptureResult::Cancelled => return Ok(("Cancelled", None)),
+            CaptureResult::Captured(binding) => {
+                return Ok(("Captured", Some(lua.to_value(&binding)?)));
+            }
+        }
+    }
+}
+
+impl LuaUserData for LuaInputCapture {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("poll", Self::poll);
+    }
+}
+
+struct LuaInputManager {
+    manager: InputManager,
+}
+
+impl LuaInputManager {
+    fn new(lua: &Lua, args: (LuaValue,)) -> LuaResult<Self> {
+        let bindings = lua.from_value::<BindingMap>(args.0)?;
+
+        return Ok(Self {
+            manager: InputManager::new(bindings),
+        });
+    }
+
+    fn connect_device(_lua: &Lua, this: &mut Self, args: (LuaAnyUserData,)) -> LuaResult<()> {
+        this.manager.connect_device(device_id(&args.0)?);
+
+        return Ok(());
+    }
+
+    fn disconnect_device(_lua: &Lua, this: &mut Self, args: (LuaAnyUserData,)) -> LuaResult<()> {
+        this.manager.disconnect_device(device_id(&args.0)?);
+
+        return Ok(());
+    }
+
+    /// Registers a named context, with options such as `{ priority = 10, consumption = "All" }`.
+    fn add_context(
+        lua: &Lua,
+        this: &mut Self,
+        args: (String, LuaValue, Option<LuaValue>),
+    ) -> LuaResult<bool> {
+        let bindings = lua.from_value::<BindingMap>(args.1)?;
+
+        let options = match args.2 {
+            Some(options) => lua.from_value::<ContextOptions>(options)?,
+            None => ContextOptions::default(),
+        };
+
+        return Ok(this
+            .manager
+            .add_context(InternString::new(&args.0), bindings, options));
+    }
+
+    fn remove_context(_lua: &Lua, this: &mut Self, args: (String,)) -> LuaResult<bool> {
+        return Ok(this.manager.remove_context(&InternString::new(&args.0)));
+    }
+
+    fn push_context(_lua: &Lua, this: &mut Self, args: (String,)) -> LuaResult<bool> {
+        return Ok(this.manager.push_context(&InternString::new(&args.0)));
+    }
+
+    fn pop_context(_lua: &Lua, this: &mut Self, _args: ()) -> LuaResult<Option<String>> {
+        return Ok(this.manager.pop_context().map(|it| it.as_str().to_owned()));
+    }
+
+    fn deactivate_context(_lua: &Lua, this: &mut Self, args: (String,)) -> LuaResult<bool> {
+        return Ok(this.manager.deactivate_context(&InternString::new(&args.0)));
+    }
+
+    fn is_context_active(_lua: &Lua, this: &Self, args: (String,)) -> LuaResult<bool> {
+        return Ok(this.manager.is_context_active(&InternString::new(&args.0)));
+    }
+
+    fn update(_lua: &Lua, this: &mut Self, args: (LuaAnyUserData, f32)) -> LuaResult<()> {
+        let state = args.0.borrow::<LuaInputState>()?;
+
+        this.manager.update(&state.state, args.1);
+
+        return Ok(());
+    }
+
+    fn get(lua: &Lua, this: &Self, args: (String,)) -> LuaResult<Option<LuaTable>> {
+        let Some(frame) = this.manager.get_binding(&InternString::new(&args.0)) else {
+            return Ok(None);
+        };
+
+        let table = lua.create_table()?;
+
+        match frame {
+            InputFrame::Digital(frame) => {
+                table.set("pressed", frame.pressed)?;
+                table.set("just_pressed", frame.just_pressed)?;
+                table.set("just_released", frame.just_released)?;
+                table.set("held", frame.held)?;
+                table.set("long_pressed", frame.long_pressed)?;
+                table.set("tapped", frame.tapped)?;
+                table.set("double_tapped", frame.double_tapped)?;
+                table.set("repeated", frame.repeated)?;
+                table.set("buffered", frame.buffered)?;
+            }
+
+            InputFrame::Analog(frame) => {
+                table.set("value", frame.value)?;
+                table.set("delta", frame.delta)?;
+            }
+
+            InputFrame::Vector(frame) => {
+                table.set("x", frame.value[0])?;
+                table.set("y", frame.value[1])?;
+                table.set("delta_x", frame.delta[0])?;
+                table.set("delta_y", frame.delta[1])?;
+            }
+        }
+
+        return Ok(Some(table));
+    }
+
+    /// Plays an effect on the device that last drove the action, or the device the player last used.
+    fn play_haptic(
+        lua: &Lua,
+        this: &Self,
+        args: (LuaAnyUserData, String, LuaValue),
+    ) -> LuaResult<bool> {
+        let mut state = args.0.borrow_mut::<LuaInputState>()?;
+        let effect = lua.from_value::<HapticEffect>(args.2)?;
+
+        return Ok(this
+            .manager
+            .play_haptic(&mut state.state, &InternString::new(&args.1), effect));
+    }
+
+    /// The glyphs of each of an action's bindings, as `{ { { id = "ps_cross", name = "Cross" } } }`.
+    fn glyphs(lua: &Lua, this: &Self, args: (LuaAnyUserData, String)) -> LuaResult<LuaValue> {
+        let state = args.0.borrow::<LuaInputState>()?;
+
+        return lua.to_value(
+            &this
+                .manager
+                .action_glyphs(&state.state, &InternString::new(&args.1)),
+        );
+    }
+
+    fn consume(_lua: &Lua, this: &mut Self, args: (String,)) -> LuaResult<bool> {
+        return Ok(this.manager.consume(&InternString::new(&args.0)));
+    }
+
+    /// Adds a binding returned by `InputCapture:poll`.
+    fn add_binding(lua: &Lua, this: &mut Self, args: (String, LuaValue)) -> LuaResult<bool> {
+        let binding = lua.from_value::<CapturedBinding>(args.1)?;
+
+        return Ok(this
+            .manager
+            .add_captured_binding(InternString::new(&args.0), binding)
+            .is_ok());
+    }
+
+    /// Leaving out the context adds the action to the bindings the manager was created with.
+    fn register_action(
+        lua: &Lua,
+        this: &mut Self,
+        args: (String, LuaValue, Option<String>),
+    ) -> LuaResult<bool> {
+        let name = InternString::new(&args.0);
+        let bindings = lua.from_value::<BindingList>(args.1)?;
+
+        let Some(context) = args.2 else {
+            return Ok(this.manager.register_action(name, bindings));
+        };
+
+        return Ok(this.manager.register_context_action(
+            &InternString::new(&context),
+            name,
+            bindings,
+        ));
+    }
+
+    fn replace_action(
+        lua: &Lua,
+        this: &mut Self,
+        args: (String, LuaValue, Option<String>),
+    ) -> LuaResult<bool> {
+        let name = InternString::new(&args.0);
+        let bindings = lua.from_value::<BindingList>(args.1)?;
+
+        let Some(context) = args.2 else {
+            return Ok(this.manager.replace_action(name, bindings));
+        };
+
+        return Ok(this.manager.replace_context_action(
+            &InternString::new(&context),
+            name,
+            bindings,
+        ));
+    }
+
+    fn unregister_action(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (String, Option<String>),
+    ) -> LuaResult<bool> {
+        let name = InternString::new(&args.0);
+
+        let Some(context) = args.1 else {
+            return Ok(this.manager.unregister_action(&name));
+        };
+
+        return Ok(this
+            .manager
+            .unregister_context_action(&InternString::new(&context), &name));
+    }
+
+    /// Takes the action name, one of `aggregations`, the device for `"Device"`, and an optional context.
+    fn set_aggregation(
+        _lua: &Lua,
+        this: &mut Self,
+        args: (String, String, Option<LuaAnyUserData>, Option<String>),
+    ) -> LuaResult<bool> {
+        let aggregation = match args.1.as_str() {
+            "MaxMagnitude" => DeviceAggregation::MaxMagnitude,
+            "SumClamp" => DeviceAggregation::SumClamp,
+            "MostRecent" => DeviceAggregation::MostRecent,
+            "Device" => {
+                let Some(device) = &args.2 else {
+                    return Err(LuaError::RuntimeError(
+                        "Device aggregation needs a device".into(),
+                    ));
+                };
+
+                DeviceAggregation::Device(device_id(device)?)
+            }
+            _ => {
+                return Err(LuaError::RuntimeError(format!(
+                    "Unknown aggregation {}",
+                    args.1
+                )));
+            }
+        };
+
+        let name = InternString::new(&args.0);
+
+        let result = match args.3 {
+            Some(context) => this.manager.set_context_aggregation(
+                &InternString::new(&context),
+                &name,
+                aggregation,
+            ),
+            None => this.manager.set_aggregation(&name, aggregation),
+        };
+
+        return Ok(result.is_ok());
+    }
+
+    fn set_history_length(_lua: &Lua, this: &mut Self, args: (usize,)) -> LuaResult<()> {
+        this.manager.set_history_length(args.0);
+
+        return Ok(());
+    }
+
+    fn tick(_lua: &Lua, this: &Self, _args: ()) -> LuaResult<u64> {
+        return Ok(this.manager.tick());
+    }
+
+    fn history_range(_lua: &Lua, this: &Self, _args: ()) -> LuaResult<(u64, u64)> {
+        let range = this.manager.history_range();
+
+        return Ok((range.start, range.end));
+    }
+
+    fn tick_input(lua: &Lua, this: &Self, args: (u64, String)) -> LuaResult<LuaValue> {
+        let Some(value) = this.manager.tick_input(args.0, &InternString::new(&args.1)) else {
+            return Ok(LuaValue::Nil);
+        };
+
+        return lua.to_value(&value);
+    }
+
+    fn set_tick_input(
+        lua: &Lua,
+        this: &mut Self,
+        args: (u64, String, LuaValue),
+    ) -> LuaResult<bool> {
+        let value = lua.from_value::<ActionValue>(args.2)?;
+
+        return Ok(this
+            .manager
+            .set_tick_input(args.0, &InternString::new(&args.1), value));
+    }
+
+    fn rewind(_lua: &Lua, this: &mut Self, args: (u64,)) -> LuaResult<bool> {
+        return Ok(this.manager.rewind(args.0));
+    }
+
+    fn replay_tick(_lua: &Lua, this: &mut Self, _args: ()) -> LuaResult<bool> {
+        return Ok(this.manager.replay_tick());
+    }
+
+    /// Returns the device that most recently drove an action and its family, or nothing.
+    fn last_device(
+        _lua: &Lua,
+        this: &Self,
+        _args: (),
+    ) -> LuaResult<(Option<LuaDeviceId>, Option<&'static str>)> {
+        return Ok((
+            this.manager.last_device().map(LuaDeviceId),
+            this.manager.last_family().map(family_name),
+        ));
+    }
+
+    fn set_device_switch_threshold(_lua: &Lua, this: &mut Self, args: (f32,)) -> LuaResult<()> {
+        this.manager.set_device_switch_threshold(args.0);
+
+        return Ok(());
+    }
+
+    fn poll_device_change(lua: &Lua, this: &mut Self, _args: ()) -> LuaResult<Option<LuaTable>> {
+        let Some(change) = this.manager.pop_device_change() else {
+            return Ok(None);
+        };
+
+        let table = lua.create_table()?;
+
+        table.set("device", LuaDeviceId(change.device))?;
+        table.set("family", family_name(change.family))?;
+        table.set("previous", change.previous.map(LuaDeviceId))?;
+        table.set("previous_family", change.previous_family.map(family_name))?;
+
+        return Ok(Some(table));
+    }
+}
+
+impl LuaUserData for LuaInputManager {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("connect_device", Self::connect_device);
+        methods.add_method_mut("disconnect_device", Self::disconnect_device);
+
+        methods.add_method_mut("add_context", Self::add_context);
+        methods.add_method_mut("remove_context", Self::remove_context);
+        methods.add_method_mut("push_context", Self::push_context);
+        methods.add_method_mut("pop_context", Self::pop_context);
+        methods.add_method_mut("deactivate_context", Self::deactivate_context);
+        methods.add_method("is_context_active", Self::is_context_active);
+
+        methods.add_method_mut("update", Self::update);
+        methods.add_method("get", Self::get);
+        methods.add_method_mut("consume", Self::consume);
+        methods.add_method("play_haptic", Self::play_haptic);
+        methods.add_method("glyphs", Self::glyphs);
+
+        methods.add_method_mut("add_binding", Self::add_binding);
+        methods.add_method_mut("register_action", Self::register_action);
+        methods.add_method_mut("replace_action", Self::replace_action);
+        methods.add_method_mut("unregister_action", Self::unregister_action);
+        methods.add_method_mut("set_aggregation", Self::set_aggregation);
+
+        methods.add_method_mut("set_history_length", Self::set_history_length);
+        methods.add_method("tick", Self::tick);
+        methods.add_method("history_range", Self::history_range);
+        methods.add_method("tick_input", Self::tick_input);
+        methods.add_method_mut("set_tick_input", Self::set_tick_input);
+        methods.add_method_mut("rewind", Self::rewind);
+        methods.add_method_mut("replay_tick", Self::replay_tick);
+
+        methods.add_method("last_device", Self::last_device);
+        methods.add_method_mut(
+            "set_device_switch_threshold",
+            Self::set_device_switch_threshold,
+        );
+        methods.add_method_mut("poll_device_change", Self::poll_device_change);
+    }
+}
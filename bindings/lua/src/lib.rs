@@ -45,7 +45,7 @@ pub fn pawkit(lua: &Lua) -> LuaResult<LuaTable> {
 
     exports.set("logger", logger::init(lua)?)?;
     exports.set("net", net::init(lua)?)?;
-    // exports.set("input", input::init(lua)?)?;
+    exports.set("input", input::init(lua)?)?;
     exports.set("fs", fs::init(lua)?)?;
 
     return Ok(exports);
@@ -1,7 +1,7 @@
 use std::{iter::FilterMap, slice::Iter};
 
 /// An array that allows holes in data, and automatically manages free slots
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HolyArray<T> {
     data: Vec<Option<T>>,
     free_slots: Vec<usize>,
@@ -0,0 +1,266 @@
+use crate::{DeviceId, history::ActionValue, length_squared};
+
+/// How an action combines the values it reads from several devices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeviceAggregation {
+    /// The value furthest from zero wins, keeping its sign.
+    #[default]
+    MaxMagnitude,
+    /// Every device's value is added together, then clamped to a length of 1.
+    SumClamp,
+    /// The device that most recently started driving the action wins, even if another one is pushed further.
+    MostRecent,
+    /// Only this device is read, such as for splitting one action between two players.
+    Device(DeviceId),
+}
+
+impl DeviceAggregation {
+    /// Combines the value each device read, given as the device's index and its value.
+    /// `recent` is the devices driving the action, oldest first, and is only read by `MostRecent`.
+    /// Returns the combined value, and the index of the device that drove it, if any did.
+    pub(crate) fn combine(
+        &self,
+        released: ActionValue,
+        readings: &[(usize, ActionValue)],
+        devices: &[DeviceId],
+        recent: &[DeviceId],
+    ) -> (ActionValue, Option<usize>) {
+        match self {
+            Self::MaxMagnitude => return strongest(released, readings),
+
+            Self::SumClamp => {
+                let (_, source) = strongest(released, readings);
+
+                return (sum(released, readings), source);
+            }
+
+            Self::MostRecent => {
+                let newest = recent.iter().rev().find_map(|device| {
+                    readings
+                        .iter()
+                        .find(|(i, value)| devices[*i] == *device && value.magnitude() > 0f32)
+                });
+
+                let Some((i, value)) = newest else {
+                    return (released, None);
+                };
+
+                return (*value, Some(*i));
+            }
+
+            Self::Device(device) => {
+                let Some((i, value)) = readings.iter().find(|(i, _)| devices[*i] == *device) else {
+                    return (released, None);
+                };
+
+                let source = if value.magnitude() > 0f32 {
+                    Some(*i)
+                } else {
+                    None
+                };
+
+                return (*value, source);
+            }
+        }
+    }
+}
+
+/// The first value furthest from zero.
+fn strongest(
+    released: ActionValue,
+    readings: &[(usize, ActionValue)],
+) -> (ActionValue, Option<usize>) {
+    let mut value = released;
+    let mut source = None;
+    let mut magnitude = 0f32;
+
+    for (i, reading) in readings {
+        let current = reading.magnitude();
+
+        if current > magnitude {
+            value = *reading;
+            source = Some(*i);
+            magnitude = current;
+        }
+    }
+
+    return (value, source);
+}
+
+fn sum(released: ActionValue, readings: &[(usize, ActionValue)]) -> ActionValue {
+    let mut value = released;
+
+    for (_, reading) in readings {
+        value = match (value, *reading) {
+            (ActionValue::Digital(a), ActionValue::Digital(b)) => ActionValue::Digital(a || b),
+            (ActionValue::Analog(a), ActionValue::Analog(b)) => ActionValue::Analog(a + b),
+            (ActionValue::Vector(a), ActionValue::Vector(b)) => {
+                ActionValue::Vector([a[0] + b[0], a[1] + b[1]])
+            }
+            _ => continue,
+        };
+    }
+
+    match value {
+        ActionValue::Digital(_) => return value,
+        ActionValue::Analog(value) => return ActionValue::Analog(value.clamp(-1f32, 1f32)),
+        ActionValue::Vector(value) => {
+            let len_sqr = length_squared(value);
+
+            if len_sqr <= 1f32 {
+                return ActionValue::Vector(value);
+            }
+
+            let len = len_sqr.sqrt();
+
+            return ActionValue::Vector([value[0] / len, value[1] / len]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pawkit_interner::InternString;
+
+    use super::*;
+    use crate::{
+        binding::{
+            AnalogBinding, AnalogBindingKind, BindingList, BoundAxis, VectorBinding,
+            VectorBindingKind, axis::GamepadAxis, map::BindingMap,
+        },
+        manager::{InputFrame, InputManager},
+        state::InputState,
+    };
+
+    fn bindings() -> BindingMap {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("steer"),
+            BindingList::Analog(vec![AnalogBinding {
+                axis: AnalogBindingKind::Gamepad(BoundAxis::Analog(GamepadAxis::LeftX)),
+                deadzone: 0f32,
+                scale: 1f32,
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+            }]),
+        );
+
+        bindings.register_binding(
+            InternString::new("move"),
+            BindingList::Vector(vec![VectorBinding {
+                axes: VectorBindingKind::Gamepad {
+                    x: BoundAxis::Analog(GamepadAxis::RightX),
+                    y: BoundAxis::Analog(GamepadAxis::RightY),
+                },
+                deadzone: 0f32,
+                scale: (1f32, 1f32),
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+                ratchet: None,
+            }]),
+        );
+
+        return bindings;
+    }
+
+    fn steer(manager: &InputManager) -> f32 {
+        let Some(InputFrame::Analog(frame)) = manager.get_binding(&InternString::new("steer"))
+        else {
+            panic!();
+        };
+
+        return frame.value;
+    }
+
+    #[test]
+    fn max_magnitude_keeps_negative_values() {
+        let mut state = InputState::new();
+        let first = state.connect_gamepad();
+        let second = state.connect_gamepad();
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(first);
+        manager.connect_device(second);
+
+        state.set_gamepad_axis(&first, GamepadAxis::LeftX, -0.8);
+        state.set_gamepad_axis(&second, GamepadAxis::LeftX, 0.3);
+        manager.update(&state, 0.1);
+
+        assert_eq!(steer(&manager), -0.8);
+
+        state.set_gamepad_axis(&first, GamepadAxis::RightX, 0.5);
+        manager.update(&state, 0.1);
+
+        let Some(InputFrame::Vector(moved)) = manager.get_binding(&InternString::new("move"))
+        else {
+            panic!();
+        };
+
+        assert_eq!(moved.delta, [0.5, 0f32]);
+    }
+
+    #[test]
+    fn aggregation_is_set_per_action() {
+        let mut state = InputState::new();
+        let first = state.connect_gamepad();
+        let second = state.connect_gamepad();
+
+        let name = InternString::new("steer");
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(first);
+        manager.connect_device(second);
+
+        manager
+            .set_aggregation(&name, DeviceAggregation::SumClamp)
+            .unwrap();
+
+        state.set_gamepad_axis(&first, GamepadAxis::LeftX, 0.7);
+        state.set_gamepad_axis(&second, GamepadAxis::LeftX, 0.6);
+        manager.update(&state, 0.1);
+
+        assert_eq!(steer(&manager), 1f32);
+
+        state.set_gamepad_axis(&second, GamepadAxis::LeftX, -0.5);
+        manager.update(&state, 0.1);
+
+        assert!((steer(&manager) - 0.2).abs() < 0.0001);
+
+        manager
+            .set_aggregation(&name, DeviceAggregation::MostRecent)
+            .unwrap();
+
+        state.set_gamepad_axis(&second, GamepadAxis::LeftX, 0f32);
+        manager.update(&state, 0.1);
+
+        assert_eq!(steer(&manager), 0.7);
+
+        // The second gamepad started pushing later, so it wins until it lets go, even though it's pushed less.
+        state.set_gamepad_axis(&second, GamepadAxis::LeftX, -0.2);
+        manager.update(&state, 0.1);
+
+        assert_eq!(steer(&manager), -0.2);
+
+        state.set_gamepad_axis(&second, GamepadAxis::LeftX, 0f32);
+        manager.update(&state, 0.1);
+
+        assert_eq!(steer(&manager), 0.7);
+
+        manager
+            .set_aggregation(&name, DeviceAggregation::Device(second))
+            .unwrap();
+
+        manager.update(&state, 0.1);
+
+        assert_eq!(steer(&manager), 0f32);
+
+        assert!(
+            manager
+                .set_aggregation(&InternString::new("jump"), DeviceAggregation::SumClamp)
+                .is_err()
+        );
+    }
+}
@@ -23,10 +23,18 @@ implement_into!(KeyboardAxis);
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TryFromPrimitive)]
 pub enum MouseAxis {
+    /// Movement since the `InputManager` last updated. Values set in between add up.
     DeltaX,
     DeltaY,
+    /// Scrolling since the `InputManager` last updated. Values set in between add up.
     WheelX,
     WheelY,
+    /// The cursor position, in window pixels.
+    X,
+    Y,
+    /// The cursor position from 0 to 1 across the viewport, derived from `X` and `Y`.
+    NormalizedX,
+    NormalizedY,
 }
 
 implement_into!(MouseAxis);
@@ -40,6 +48,55 @@ pub enum GamepadAxis {
     RightY,
     LeftTrigger,
     RightTrigger,
+    /// Rotation around each axis since the last frame, in radians, with the calibration offset removed.
+    GyroX,
+    GyroY,
+    GyroZ,
+    /// The latest accelerometer reading, in meters per second squared.
+    AccelX,
+    AccelY,
+    AccelZ,
 }
 
 implement_into!(GamepadAxis);
+
+/// Touch axes are driven by the gesture recognizer, rather than set directly.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TryFromPrimitive)]
+pub enum TouchAxis {
+    /// The position of the first finger on the screen.
+    X,
+    Y,
+    /// The pressure of the first finger on the screen.
+    Pressure,
+    /// How much the distance between the first two fingers changed this frame.
+    Pinch,
+    /// How much the midpoint between the first two fingers moved this frame.
+    PanX,
+    PanY,
+}
+
+implement_into!(TouchAxis);
+
+/// An axis on a virtual device, by index. What each index means is up to the code driving the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VirtualAxis(pub u8);
+
+impl From<VirtualAxis> for usize {
+    fn from(value: VirtualAxis) -> Self {
+        return value.0 as usize;
+    }
+}
+
+impl TryFromPrimitive for VirtualAxis {
+    type Primitive = u8;
+
+    type Error = ();
+
+    const NAME: &'static str = "VirtualAxis";
+
+    fn try_from_primitive(value: Self::Primitive) -> Result<Self, Self::Error> {
+        return Ok(Self(value));
+    }
+}
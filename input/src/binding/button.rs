@@ -3,9 +3,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::binding::implement_into;
 
+/// A key by its position on a US QWERTY keyboard, or by what's printed on it for logical bindings.
 #[repr(u8)]
 #[rustfmt::skip]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, TryFromPrimitive)]
 #[serde(rename_all = "PascalCase")]
 pub enum KeyboardButton {
     A, B, C, D, E, F, G, H, I, J, K, L, M,
@@ -95,3 +96,44 @@ pub enum GamepadButton {
 }
 
 implement_into!(GamepadButton);
+
+/// Touch buttons are driven by the gesture recognizer, rather than set directly.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TryFromPrimitive)]
+#[serde(rename_all = "PascalCase")]
+pub enum TouchButton {
+    /// At least one finger is on the screen.
+    Touch,
+    /// At least two fingers are on the screen.
+    MultiTouch,
+    Tap,
+    SwipeLeft,
+    SwipeRight,
+    SwipeUp,
+    SwipeDown,
+}
+
+implement_into!(TouchButton);
+
+/// A button on a virtual device, by index. What each index means is up to the code driving the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VirtualButton(pub u8);
+
+impl From<VirtualButton> for usize {
+    fn from(value: VirtualButton) -> Self {
+        return value.0 as usize;
+    }
+}
+
+impl TryFromPrimitive for VirtualButton {
+    type Primitive = u8;
+
+    type Error = ();
+
+    const NAME: &'static str = "VirtualButton";
+
+    fn try_from_primitive(value: Self::Primitive) -> Result<Self, Self::Error> {
+        return Ok(Self(value));
+    }
+}
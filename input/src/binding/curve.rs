@@ -0,0 +1,247 @@
+use std::ops::Deref;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::length_squared;
+
+/// The most control points a piecewise curve can have, so curves and the bindings holding them stay `Copy`.
+pub const MAX_CURVE_POINTS: usize = 8;
+
+/// Remaps the magnitude of an input after the deadzone has been applied.
+/// The sign (or direction, for vectors) of the input is always preserved.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    /// `x^exponent`, where the exponent is above zero.
+    Power {
+        #[serde(deserialize_with = "positive_exponent")]
+        exponent: f32,
+    },
+    /// `(e^(strength * x) - 1) / (e^strength - 1)`
+    Exponential { strength: f32 },
+    /// Linearly interpolates between control points, sorted by their x coordinate.
+    /// Inputs outside of the points are clamped to the first or last point.
+    Piecewise { points: CurvePoints },
+}
+
+/// A zero or negative exponent would send small inputs to full strength, or to infinity.
+fn positive_exponent<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let exponent = f32::deserialize(deserializer)?;
+
+    if !(exponent > 0f32 && exponent.is_finite()) {
+        return Err(D::Error::custom(format!(
+            "Power curve exponent must be above zero, got {}",
+            exponent
+        )));
+    }
+
+    return Ok(exponent);
+}
+
+/// Up to `MAX_CURVE_POINTS` control points, sorted by their x coordinate.
+#[derive(Debug, Clone, Copy)]
+pub struct CurvePoints {
+    len: u8,
+    points: [[f32; 2]; MAX_CURVE_POINTS],
+}
+
+impl CurvePoints {
+    /// Sorts the points, since points written by hand may be in any order.
+    /// Returns `None` if there are more than `MAX_CURVE_POINTS`.
+    pub fn new(points: &[[f32; 2]]) -> Option<Self> {
+        if points.len() > MAX_CURVE_POINTS {
+            return None;
+        }
+
+        let mut sorted = Self {
+            len: points.len() as u8,
+            points: [[0f32; 2]; MAX_CURVE_POINTS],
+        };
+
+        sorted.points[..points.len()].copy_from_slice(points);
+        sorted.points[..points.len()].sort_by(|a, b| a[0].total_cmp(&b[0]));
+
+        return Some(sorted);
+    }
+}
+
+impl Deref for CurvePoints {
+    type Target = [[f32; 2]];
+
+    fn deref(&self) -> &Self::Target {
+        return &self.points[..self.len as usize];
+    }
+}
+
+impl PartialEq for CurvePoints {
+    fn eq(&self, other: &Self) -> bool {
+        return **self == **other;
+    }
+}
+
+impl Serialize for CurvePoints {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return (**self).serialize(serializer);
+    }
+}
+
+impl<'de> Deserialize<'de> for CurvePoints {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let points = Vec::<[f32; 2]>::deserialize(deserializer)?;
+
+        return Self::new(&points).ok_or_else(|| {
+            D::Error::custom(format!(
+                "Piecewise curve has {} points, but at most {} are allowed",
+                points.len(),
+                MAX_CURVE_POINTS
+            ))
+        });
+    }
+}
+
+impl ResponseCurve {
+    /// Returns `None` if there are more than `MAX_CURVE_POINTS`.
+    pub fn piecewise(points: &[[f32; 2]]) -> Option<Self> {
+        return Some(Self::Piecewise {
+            points: CurvePoints::new(points)?,
+        });
+    }
+
+    pub fn evaluate(&self, magnitude: f32) -> f32 {
+        match self {
+            Self::Linear => return magnitude,
+
+            Self::Power { exponent } => return magnitude.powf(*exponent),
+
+            Self::Exponential { strength } => {
+                if *strength == 0f32 {
+                    return magnitude;
+                }
+
+                return (strength * magnitude).exp_m1() / strength.exp_m1();
+            }
+
+            Self::Piecewise { points } => {
+                let (Some(first), Some(last)) = (points.first(), points.last()) else {
+                    return magnitude;
+                };
+
+                if magnitude <= first[0] {
+                    return first[1];
+                }
+
+                for segment in points.windows(2) {
+                    let [start, end] = [segment[0], segment[1]];
+
+                    if magnitude > end[0] {
+                        continue;
+                    }
+
+                    let width = end[0] - start[0];
+
+                    if width <= 0f32 {
+                        return end[1];
+                    }
+
+                    let t = (magnitude - start[0]) / width;
+
+                    return start[1] + (end[1] - start[1]) * t;
+                }
+
+                return last[1];
+            }
+        }
+    }
+
+    pub fn apply_axis(&self, value: f32) -> f32 {
+        if value == 0f32 {
+            return 0f32;
+        }
+
+        return self.evaluate(value.abs()).copysign(value);
+    }
+
+    pub fn apply_vector(&self, value: [f32; 2]) -> [f32; 2] {
+        let length = length_squared(value).sqrt();
+
+        if length == 0f32 {
+            return [0f32; 2];
+        }
+
+        let factor = self.evaluate(length) / length;
+
+        return [value[0] * factor, value[1] * factor];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn power_preserves_sign() {
+        let curve = ResponseCurve::Power { exponent: 2.0 };
+
+        assert_close(curve.apply_axis(0.5), 0.25);
+        assert_close(curve.apply_axis(-0.5), -0.25);
+    }
+
+    #[test]
+    fn exponential_keeps_endpoints() {
+        let curve = ResponseCurve::Exponential { strength: 3.0 };
+
+        assert_close(curve.evaluate(0.0), 0.0);
+        assert_close(curve.evaluate(1.0), 1.0);
+        assert!(curve.evaluate(0.5) < 0.5);
+    }
+
+    #[test]
+    fn piecewise_interpolates() {
+        let curve = ResponseCurve::piecewise(&[[1.0, 1.0], [0.0, 0.0], [0.5, 0.2]]).unwrap();
+
+        assert_close(curve.evaluate(0.25), 0.1);
+        assert_close(curve.evaluate(0.75), 0.6);
+        assert_close(curve.evaluate(2.0), 1.0);
+        assert_close(curve.apply_axis(-0.5), -0.2);
+    }
+
+    #[test]
+    fn curve_round_trips() {
+        let curve = ResponseCurve::piecewise(&[[0.0, 0.0], [0.5, 0.2], [1.0, 1.0]]).unwrap();
+
+        let json = serde_json::to_string(&curve).unwrap();
+
+        assert_eq!(serde_json::from_str::<ResponseCurve>(&json).unwrap(), curve);
+    }
+
+    #[test]
+    fn deserialized_points_are_sorted() {
+        let json = r#"{"type":"Piecewise","points":[[1.0,1.0],[0.0,0.0],[0.5,0.2]]}"#;
+
+        let curve = serde_json::from_str::<ResponseCurve>(json).unwrap();
+
+        assert_close(curve.evaluate(0.25), 0.1);
+        assert_close(curve.evaluate(0.75), 0.6);
+    }
+
+    #[test]
+    fn invalid_curves_are_rejected() {
+        for exponent in ["0.0", "-2.0"] {
+            let json = format!(r#"{{"type":"Power","exponent":{}}}"#, exponent);
+
+            assert!(serde_json::from_str::<ResponseCurve>(&json).is_err());
+        }
+
+        let points = [[0f32; 2]; MAX_CURVE_POINTS + 1];
+        let json = format!(r#"{{"type":"Piecewise","points":{:?}}}"#, points);
+
+        assert!(serde_json::from_str::<ResponseCurve>(&json).is_err());
+        assert_eq!(ResponseCurve::piecewise(&points), None);
+    }
+}
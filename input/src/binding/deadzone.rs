@@ -0,0 +1,151 @@
+use num_enum::TryFromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::length_squared;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, TryFromPrimitive)]
+pub enum DeadzoneShape {
+    /// Each axis is cut off independently.
+    /// Keeps the stick snapped to the cardinal directions near the center.
+    Axial,
+    /// The whole vector is cut off when its length is inside the deadzone.
+    Radial,
+    /// Like `Radial`, but the remaining range is rescaled to start at zero,
+    /// so there is no jump in value when leaving the deadzone.
+    #[default]
+    ScaledRadial,
+}
+
+impl DeadzoneShape {
+    fn apply_magnitude(self, magnitude: f32, deadzone: f32, outer: Option<f32>) -> f32 {
+        if magnitude < deadzone {
+            return 0f32;
+        }
+
+        match self {
+            Self::Axial | Self::Radial => {
+                let Some(outer) = outer else {
+                    return magnitude;
+                };
+
+                if outer <= 0f32 {
+                    return 1f32;
+                }
+
+                return (magnitude / outer).min(1f32);
+            }
+
+            Self::ScaledRadial => {
+                let range = outer.unwrap_or(1f32) - deadzone;
+
+                if range <= 0f32 {
+                    return 1f32;
+                }
+
+                let value = (magnitude - deadzone) / range;
+
+                if outer.is_some() {
+                    return value.min(1f32);
+                }
+
+                return value;
+            }
+        }
+    }
+
+    /// Applies the deadzone to a single axis.
+    /// `Axial` and `Radial` behave identically in one dimension.
+    pub fn apply_axis(self, value: f32, deadzone: f32, outer: Option<f32>) -> f32 {
+        return self
+            .apply_magnitude(value.abs(), deadzone, outer)
+            .copysign(value);
+    }
+
+    pub fn apply_vector(self, value: [f32; 2], deadzone: f32, outer: Option<f32>) -> [f32; 2] {
+        if self == Self::Axial {
+            return [
+                self.apply_axis(value[0], deadzone, outer),
+                self.apply_axis(value[1], deadzone, outer),
+            ];
+        }
+
+        let length = length_squared(value).sqrt();
+
+        if length == 0f32 {
+            return [0f32; 2];
+        }
+
+        let factor = self.apply_magnitude(length, deadzone, outer) / length;
+
+        return [value[0] * factor, value[1] * factor];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn axis_inside_deadzone_is_zero() {
+        for shape in [
+            DeadzoneShape::Axial,
+            DeadzoneShape::Radial,
+            DeadzoneShape::ScaledRadial,
+        ] {
+            assert_eq!(shape.apply_axis(0.1, 0.2, None), 0f32);
+            assert_eq!(shape.apply_axis(-0.1, 0.2, None), 0f32);
+        }
+    }
+
+    #[test]
+    fn scaled_radial_rescales() {
+        let shape = DeadzoneShape::ScaledRadial;
+
+        assert_close(shape.apply_axis(0.2, 0.2, None), 0.0);
+        assert_close(shape.apply_axis(-0.6, 0.2, None), -0.5);
+        assert_close(shape.apply_axis(1.0, 0.2, None), 1.0);
+    }
+
+    #[test]
+    fn outer_deadzone_saturates() {
+        let shape = DeadzoneShape::ScaledRadial;
+
+        assert_close(shape.apply_axis(0.95, 0.1, Some(0.9)), 1.0);
+        assert_close(shape.apply_axis(0.5, 0.1, Some(0.9)), 0.5);
+
+        let shape = DeadzoneShape::Radial;
+
+        assert_close(shape.apply_axis(-0.45, 0.1, Some(0.9)), -0.5);
+    }
+
+    #[test]
+    fn axial_keeps_cardinal_directions() {
+        let value = DeadzoneShape::Axial.apply_vector([0.1, 0.8], 0.2, None);
+
+        assert_eq!(value, [0.0, 0.8]);
+    }
+
+    #[test]
+    fn radial_keeps_direction() {
+        let value = DeadzoneShape::Radial.apply_vector([0.1, 0.8], 0.2, None);
+
+        assert_eq!(value, [0.1, 0.8]);
+
+        let value = DeadzoneShape::Radial.apply_vector([0.1, 0.1], 0.2, None);
+
+        assert_eq!(value, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn scaled_radial_vector() {
+        let [x, y] = DeadzoneShape::ScaledRadial.apply_vector([0.0, -0.6], 0.2, None);
+
+        assert_close(x, 0.0);
+        assert_close(y, -0.5);
+    }
+}
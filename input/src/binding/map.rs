@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use pawkit_interner::InternString;
 use serde::{Deserialize, Serialize};
 
-use crate::binding::{AnalogBinding, BindingKind, BindingList, DigitalBinding, VectorBinding};
+use crate::binding::{
+    AnalogBinding, BindingKind, BindingList, DigitalBinding, PhysicalInput, VectorBinding,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct BindingMap {
@@ -11,6 +13,7 @@ pub struct BindingMap {
     map: HashMap<InternString, BindingList>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BindingMapModificaitonError {
     NotFound,
     TypeMismatch,
@@ -18,6 +21,68 @@ pub enum BindingMapModificaitonError {
     BindingNotExists,
 }
 
+/// A problem found by `BindingMap::validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindingIssue {
+    /// The same input, or the same chord, is bound to more than one action.
+    /// A chord is listed as its modifiers followed by its button.
+    Conflict {
+        inputs: Vec<PhysicalInput>,
+        actions: Vec<InternString>,
+    },
+    /// An axis bound as a button has a threshold outside -1..1, so it's either always or never pressed.
+    ThresholdOutOfRange {
+        action: InternString,
+        threshold: f32,
+    },
+    /// A deadzone of 1 or more swallows the whole range of the axis.
+    DeadzoneTooLarge { action: InternString, deadzone: f32 },
+    /// The action has no bindings, so it can never be triggered.
+    Empty { action: InternString },
+}
+
+/// The combinations of inputs that trigger any of the bindings.
+fn triggers(bindings: &BindingList) -> Vec<Vec<PhysicalInput>> {
+    let mut triggers = vec![];
+
+    match bindings {
+        BindingList::Digital(bindings) => {
+            for binding in bindings {
+                binding.triggers(&mut triggers);
+            }
+        }
+
+        BindingList::Analog(bindings) => {
+            let mut inputs = vec![];
+
+            for binding in bindings {
+                binding.inputs(&mut inputs);
+            }
+
+            triggers.extend(inputs.into_iter().map(|it| vec![it]));
+        }
+
+        BindingList::Vector(bindings) => {
+            let mut inputs = vec![];
+
+            for binding in bindings {
+                binding.axes.inputs(&mut inputs);
+
+                if let Some(ratchet) = &binding.ratchet {
+                    ratchet.triggers(&mut triggers);
+                }
+            }
+
+            triggers.extend(inputs.into_iter().map(|it| vec![it]));
+        }
+    }
+
+    triggers.sort();
+    triggers.dedup();
+
+    return triggers;
+}
+
 impl BindingMap {
     pub fn new() -> Self {
         return Self {
@@ -55,6 +120,10 @@ impl BindingMap {
         self.map.insert(name, values);
     }
 
+    pub fn unregister_binding(&mut self, name: &InternString) -> Option<BindingList> {
+        return self.map.remove(name);
+    }
+
     pub(crate) fn add_digital_binding(
         &mut self,
         name: InternString,
@@ -187,18 +256,130 @@ impl BindingMap {
         return Ok(());
     }
 
+    /// Checks for conflicting inputs and values that make a binding useless.
+    /// Issues with a single action come first, sorted by action name, followed by conflicts
+    /// sorted by input, so the report is the same every time.
+    pub fn validate(&self) -> Vec<BindingIssue> {
+        let mut issues = vec![];
+
+        let mut names = self.map.keys().collect::<Vec<_>>();
+        names.sort_by_key(|it| it.as_str());
+
+        let mut bound = BTreeMap::<Vec<PhysicalInput>, Vec<InternString>>::new();
+
+        for name in names {
+            let bindings = &self.map[name];
+
+            for trigger in triggers(bindings) {
+                bound.entry(trigger).or_default().push(name.clone());
+            }
+
+            let mut thresholds = vec![];
+            let mut deadzones = vec![];
+
+            let empty = match bindings {
+                BindingList::Digital(bindings) => {
+                    thresholds.extend(bindings.iter().filter_map(DigitalBinding::threshold));
+                    bindings.is_empty()
+                }
+                BindingList::Analog(bindings) => {
+                    deadzones.extend(bindings.iter().map(|it| it.deadzone));
+                    bindings.is_empty()
+                }
+                BindingList::Vector(bindings) => {
+                    thresholds.extend(
+                        bindings
+                            .iter()
+                            .filter_map(|it| it.ratchet.as_ref()?.threshold()),
+                    );
+                    deadzones.extend(bindings.iter().map(|it| it.deadzone));
+                    bindings.is_empty()
+                }
+            };
+
+            if empty {
+                issues.push(BindingIssue::Empty {
+                    action: name.clone(),
+                });
+            }
+
+            for threshold in thresholds {
+                if !(0f32..1f32).contains(&threshold.abs()) {
+                    issues.push(BindingIssue::ThresholdOutOfRange {
+                        action: name.clone(),
+                        threshold,
+                    });
+                }
+            }
+
+            for deadzone in deadzones {
+                if deadzone >= 1f32 {
+                    issues.push(BindingIssue::DeadzoneTooLarge {
+                        action: name.clone(),
+                        deadzone,
+                    });
+                }
+            }
+        }
+
+        for (inputs, actions) in bound {
+            if actions.len() < 2 {
+                continue;
+            }
+
+            issues.push(BindingIssue::Conflict { inputs, actions });
+        }
+
+        return issues;
+    }
+
+    /// Every action that reads the given input, including as part of a chord, sorted by name.
+    pub fn actions_bound_to(&self, input: PhysicalInput) -> Vec<InternString> {
+        let mut actions = vec![];
+        let mut inputs = vec![];
+
+        for (name, bindings) in &self.map {
+            inputs.clear();
+            bindings.inputs(&mut inputs);
+
+            if inputs.contains(&input) {
+                actions.push(name.clone());
+            }
+        }
+
+        actions.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        return actions;
+    }
+
+    /// The actions that adding `value` to `name` would conflict with, for the rebinding UI to ask about first.
+    pub fn digital_conflicts(
+        &self,
+        name: &InternString,
+        value: &DigitalBinding,
+    ) -> Vec<InternString> {
+        let mut wanted = vec![];
+        value.triggers(&mut wanted);
+
+        let mut actions = self
+            .map
+            .iter()
+            .filter(|(it, _)| *it != name)
+            .filter(|(_, bindings)| triggers(bindings).iter().any(|it| wanted.contains(it)))
+            .map(|(it, _)| it.clone())
+            .collect::<Vec<_>>();
+
+        actions.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        return actions;
+    }
+
     pub fn get_bindings(&self, name: &InternString) -> Option<&BindingList> {
         return self.map.get(name);
     }
 
     pub fn get_binding_kind(&self, name: &InternString) -> Option<BindingKind> {
-        let bindings = self.get_bindings(name)?;
-
-        match bindings {
-            BindingList::Digital(_) => return Some(BindingKind::Digital),
-            BindingList::Analog(_) => return Some(BindingKind::Analog),
-            BindingList::Vector(_) => return Some(BindingKind::Vector),
-        };
+        return Some(self.get_bindings(name)?.kind());
     }
 }
 
@@ -221,3 +402,153 @@ impl<'a> IntoIterator for &'a BindingMap {
 
     type IntoIter = <&'a HashMap<InternString, BindingList> as IntoIterator>::IntoIter;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{
+            AnalogBindingKind, BoundAxis, BoundButton, ChordModifiers,
+            axis::GamepadAxis,
+            button::{GamepadButton, KeyboardButton},
+        },
+        state::InputFamily,
+    };
+
+    fn key(button: KeyboardButton) -> DigitalBinding {
+        return DigitalBinding::Keyboard(BoundButton::Digital(button));
+    }
+
+    #[test]
+    fn validate_reports_issues() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("pause"),
+            BindingList::Digital(vec![key(KeyboardButton::Escape)]),
+        );
+        bindings.register_binding(
+            InternString::new("back"),
+            BindingList::Digital(vec![
+                key(KeyboardButton::Escape),
+                DigitalBinding::Gamepad(BoundButton::Analog {
+                    axis: GamepadAxis::LeftTrigger,
+                    threshold: 1.5,
+                }),
+            ]),
+        );
+        bindings.register_binding(
+            InternString::new("throttle"),
+            BindingList::Analog(vec![AnalogBinding {
+                axis: AnalogBindingKind::Gamepad(BoundAxis::Analog(GamepadAxis::RightTrigger)),
+                deadzone: 1.0,
+                scale: 1.0,
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+            }]),
+        );
+        bindings.register_binding(InternString::new("unused"), BindingList::Vector(vec![]));
+
+        assert_eq!(
+            bindings.validate(),
+            [
+                BindingIssue::ThresholdOutOfRange {
+                    action: InternString::new("back"),
+                    threshold: 1.5,
+                },
+                BindingIssue::DeadzoneTooLarge {
+                    action: InternString::new("throttle"),
+                    deadzone: 1.0,
+                },
+                BindingIssue::Empty {
+                    action: InternString::new("unused"),
+                },
+                BindingIssue::Conflict {
+                    inputs: vec![PhysicalInput::Button(
+                        InputFamily::Keyboard,
+                        KeyboardButton::Escape as usize,
+                    )],
+                    actions: vec![InternString::new("back"), InternString::new("pause")],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn chords_only_conflict_with_the_same_chord() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("z"),
+            BindingList::Digital(vec![key(KeyboardButton::Z)]),
+        );
+        bindings.register_binding(
+            InternString::new("undo"),
+            BindingList::Digital(vec![DigitalBinding::Keyboard(BoundButton::Chord {
+                modifiers: ChordModifiers::new(&[
+                    KeyboardButton::LeftShift,
+                    KeyboardButton::LeftControl,
+                ])
+                .unwrap(),
+                button: KeyboardButton::Z,
+            })]),
+        );
+        bindings.register_binding(
+            InternString::new("jump"),
+            BindingList::Digital(vec![DigitalBinding::Gamepad(BoundButton::Digital(
+                GamepadButton::South,
+            ))]),
+        );
+
+        assert!(bindings.validate().is_empty());
+
+        let redo = DigitalBinding::Keyboard(BoundButton::Chord {
+            modifiers: ChordModifiers::new(&[
+                KeyboardButton::LeftControl,
+                KeyboardButton::LeftShift,
+            ])
+            .unwrap(),
+            button: KeyboardButton::Z,
+        });
+
+        assert_eq!(
+            bindings.digital_conflicts(&InternString::new("redo"), &redo),
+            [InternString::new("undo")]
+        );
+        assert_eq!(
+            bindings.actions_bound_to(PhysicalInput::Button(
+                InputFamily::Keyboard,
+                KeyboardButton::Z as usize
+            )),
+            [InternString::new("undo"), InternString::new("z")]
+        );
+    }
+
+    #[test]
+    fn chord_modifiers_ignore_order() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(InternString::new("undo"), BindingList::Digital(vec![]));
+
+        let shift_control: DigitalBinding = serde_json::from_str(
+            r#"{"family":"Keyboard","mode":"Chord","modifiers":["LeftShift","LeftControl","LeftShift"],"button":"Z"}"#,
+        )
+        .unwrap();
+        let control_shift: DigitalBinding = serde_json::from_str(
+            r#"{"family":"Keyboard","mode":"Chord","modifiers":["LeftControl","LeftShift"],"button":"Z"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(shift_control, control_shift);
+
+        bindings
+            .add_digital_binding(InternString::new("undo"), shift_control)
+            .unwrap();
+
+        assert_eq!(
+            bindings.add_digital_binding(InternString::new("undo"), control_shift),
+            Err(BindingMapModificaitonError::BindingAlreadyExists)
+        );
+    }
+}
@@ -1,14 +1,21 @@
 use std::fmt::Debug;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
 
-use crate::binding::{
-    axis::{GamepadAxis, KeyboardAxis, MouseAxis},
-    button::{GamepadButton, KeyboardButton, MouseButton},
+use crate::{
+    binding::{
+        axis::{GamepadAxis, KeyboardAxis, MouseAxis, TouchAxis, VirtualAxis},
+        button::{GamepadButton, KeyboardButton, MouseButton, TouchButton, VirtualButton},
+        curve::ResponseCurve,
+        deadzone::DeadzoneShape,
+    },
+    state::InputFamily,
 };
 
 pub mod axis;
 pub mod button;
+pub mod curve;
+pub mod deadzone;
 pub mod map;
 
 pub(self) macro implement_into($ty:ty) {
@@ -19,16 +26,106 @@ pub(self) macro implement_into($ty:ty) {
     }
 }
 
+/// The most modifiers a chord can have, so chords and the bindings holding them stay `Copy`.
+pub const MAX_CHORD_MODIFIERS: usize = 4;
+
+/// The modifiers of a chord, sorted and without duplicates,
+/// so a chord is the same binding whichever order its modifiers are written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChordModifiers<TButton> {
+    /// The modifiers come first, followed by empty slots.
+    buttons: [Option<TButton>; MAX_CHORD_MODIFIERS],
+}
+
+impl<TButton> ChordModifiers<TButton>
+where
+    TButton: Copy + Into<usize>,
+{
+    /// Returns `None` if there are more than `MAX_CHORD_MODIFIERS` different modifiers.
+    pub fn new(modifiers: &[TButton]) -> Option<Self> {
+        let mut sorted = modifiers.to_vec();
+        sorted.sort_by_key(|it| (*it).into());
+        sorted.dedup_by_key(|it| (*it).into());
+
+        if sorted.len() > MAX_CHORD_MODIFIERS {
+            return None;
+        }
+
+        let mut buttons = [None; MAX_CHORD_MODIFIERS];
+
+        for (slot, button) in buttons.iter_mut().zip(sorted) {
+            *slot = Some(button);
+        }
+
+        return Some(Self { buttons });
+    }
+}
+
+impl<TButton> ChordModifiers<TButton> {
+    pub fn iter(&self) -> impl Iterator<Item = &TButton> {
+        return self.buttons.iter().map_while(|it| it.as_ref());
+    }
+
+    pub fn len(&self) -> usize {
+        return self.iter().count();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.buttons[0].is_none();
+    }
+}
+
+impl<TButton> Default for ChordModifiers<TButton> {
+    fn default() -> Self {
+        return Self {
+            buttons: std::array::from_fn(|_| None),
+        };
+    }
+}
+
+impl<TButton: Serialize> Serialize for ChordModifiers<TButton> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_seq(self.iter());
+    }
+}
+
+impl<'de, TButton> Deserialize<'de> for ChordModifiers<TButton>
+where
+    TButton: Deserialize<'de> + Copy + Into<usize>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let modifiers = Vec::<TButton>::deserialize(deserializer)?;
+
+        return Self::new(&modifiers).ok_or_else(|| {
+            D::Error::custom(format!(
+                "Chord has more than {} modifiers",
+                MAX_CHORD_MODIFIERS
+            ))
+        });
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-#[serde(bound = "TButton: for<'a> Deserialize<'a>, TAxis: for<'a> Deserialize<'a>")]
+#[serde(bound = "TButton: for<'a> Deserialize<'a> + Into<usize>, TAxis: for<'a> Deserialize<'a>")]
 #[serde(tag = "mode")]
 pub enum BoundButton<TButton, TAxis>
 where
     TButton: Debug + Copy + PartialEq + Serialize,
     TAxis: Debug + Copy + PartialEq + Serialize,
 {
-    Analog { axis: TAxis, threshold: f32 },
+    /// Pressed while the axis is above the threshold,
+    /// or below it if the threshold is negative.
+    Analog {
+        axis: TAxis,
+        threshold: f32,
+    },
     Digital(TButton),
+    /// Pressed while every modifier and the button are held on the same device.
+    /// While a chord is held, bindings to the same button with fewer modifiers are suppressed.
+    Chord {
+        modifiers: ChordModifiers<TButton>,
+        button: TButton,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -47,20 +144,95 @@ where
     },
 }
 
+/// A single button or axis of a device family, that bindings read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PhysicalInput {
+    Button(InputFamily, usize),
+    Axis(InputFamily, usize),
+    /// A keyboard key by what's printed on it, which is found through the keyboard's layout.
+    LogicalKey(usize),
+}
+
+/// Turns keyboard buttons into logical keys, for bindings that follow the printed key.
+fn logical_inputs(inputs: &mut [PhysicalInput]) {
+    for input in inputs {
+        if let PhysicalInput::Button(InputFamily::Keyboard, key) = *input {
+            *input = PhysicalInput::LogicalKey(key);
+        }
+    }
+}
+
+impl<TButton, TAxis> BoundButton<TButton, TAxis>
+where
+    TButton: Debug + Copy + PartialEq + Serialize + Into<usize>,
+    TAxis: Debug + Copy + PartialEq + Serialize + Into<usize>,
+{
+    fn inputs(&self, family: InputFamily, inputs: &mut Vec<PhysicalInput>) {
+        match self {
+            Self::Analog { axis, .. } => inputs.push(PhysicalInput::Axis(family, (*axis).into())),
+            Self::Digital(button) => inputs.push(PhysicalInput::Button(family, (*button).into())),
+            Self::Chord { modifiers, button } => {
+                for modifier in modifiers.iter() {
+                    inputs.push(PhysicalInput::Button(family, (*modifier).into()));
+                }
+
+                inputs.push(PhysicalInput::Button(family, (*button).into()));
+            }
+        }
+    }
+
+    fn threshold(&self) -> Option<f32> {
+        let Self::Analog { threshold, .. } = self else {
+            return None;
+        };
+
+        return Some(*threshold);
+    }
+
+    fn is_chord(&self) -> bool {
+        return matches!(self, Self::Chord { .. });
+    }
+}
+
+impl<TButton, TAxis> BoundAxis<TButton, TAxis>
+where
+    TButton: Debug + Copy + PartialEq + Serialize + Into<usize>,
+    TAxis: Debug + Copy + PartialEq + Serialize + Into<usize>,
+{
+    fn inputs(&self, family: InputFamily, inputs: &mut Vec<PhysicalInput>) {
+        match self {
+            Self::Analog(axis) => inputs.push(PhysicalInput::Axis(family, (*axis).into())),
+            Self::Digital(button) => inputs.push(PhysicalInput::Button(family, (*button).into())),
+            Self::MultiDigital { negative, positive } => {
+                inputs.push(PhysicalInput::Button(family, (*negative).into()));
+                inputs.push(PhysicalInput::Button(family, (*positive).into()));
+            }
+        }
+    }
+}
+
+/// `Keyboard` bindings are to a key's position, while `LogicalKeyboard` bindings
+/// are to the key printed on it, which depends on the keyboard's layout.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "family")]
 pub enum DigitalBinding {
     Keyboard(BoundButton<KeyboardButton, KeyboardAxis>),
+    LogicalKeyboard(BoundButton<KeyboardButton, KeyboardAxis>),
     Mouse(BoundButton<MouseButton, MouseAxis>),
     Gamepad(BoundButton<GamepadButton, GamepadAxis>),
+    Touch(BoundButton<TouchButton, TouchAxis>),
+    Virtual(BoundButton<VirtualButton, VirtualAxis>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "family")]
 pub enum AnalogBindingKind {
     Keyboard(BoundAxis<KeyboardButton, KeyboardAxis>),
+    LogicalKeyboard(BoundAxis<KeyboardButton, KeyboardAxis>),
     Mouse(BoundAxis<MouseButton, MouseAxis>),
     Gamepad(BoundAxis<GamepadButton, GamepadAxis>),
+    Touch(BoundAxis<TouchButton, TouchAxis>),
+    Virtual(BoundAxis<VirtualButton, VirtualAxis>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -69,6 +241,12 @@ pub struct AnalogBinding {
     pub axis: AnalogBindingKind,
     pub deadzone: f32,
     pub scale: f32,
+    #[serde(default)]
+    pub deadzone_shape: DeadzoneShape,
+    #[serde(default)]
+    pub outer_deadzone: Option<f32>,
+    #[serde(default)]
+    pub curve: ResponseCurve,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -78,6 +256,10 @@ pub enum VectorBindingKind {
         x: BoundAxis<KeyboardButton, KeyboardAxis>,
         y: BoundAxis<KeyboardButton, KeyboardAxis>,
     },
+    LogicalKeyboard {
+        x: BoundAxis<KeyboardButton, KeyboardAxis>,
+        y: BoundAxis<KeyboardButton, KeyboardAxis>,
+    },
     Mouse {
         x: BoundAxis<MouseButton, MouseAxis>,
         y: BoundAxis<MouseButton, MouseAxis>,
@@ -86,6 +268,14 @@ pub enum VectorBindingKind {
         x: BoundAxis<GamepadButton, GamepadAxis>,
         y: BoundAxis<GamepadButton, GamepadAxis>,
     },
+    Touch {
+        x: BoundAxis<TouchButton, TouchAxis>,
+        y: BoundAxis<TouchButton, TouchAxis>,
+    },
+    Virtual {
+        x: BoundAxis<VirtualButton, VirtualAxis>,
+        y: BoundAxis<VirtualButton, VirtualAxis>,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -94,6 +284,154 @@ pub struct VectorBinding {
     pub axes: VectorBindingKind,
     pub deadzone: f32,
     pub scale: (f32, f32),
+    #[serde(default)]
+    pub deadzone_shape: DeadzoneShape,
+    #[serde(default)]
+    pub outer_deadzone: Option<f32>,
+    #[serde(default)]
+    pub curve: ResponseCurve,
+    /// The binding only produces a value while this is held on the same device,
+    /// such as only aiming with the gyro while a shoulder button is held.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ratchet: Option<DigitalBinding>,
+}
+
+impl DigitalBinding {
+    /// Adds every button and axis this binding reads to `inputs`.
+    pub fn inputs(&self, inputs: &mut Vec<PhysicalInput>) {
+        match self {
+            Self::Keyboard(button) => button.inputs(InputFamily::Keyboard, inputs),
+            Self::LogicalKeyboard(button) => {
+                let start = inputs.len();
+                button.inputs(InputFamily::Keyboard, inputs);
+                logical_inputs(&mut inputs[start..]);
+            }
+            Self::Mouse(button) => button.inputs(InputFamily::Mouse, inputs),
+            Self::Gamepad(button) => button.inputs(InputFamily::Gamepad, inputs),
+            Self::Touch(button) => button.inputs(InputFamily::Touch, inputs),
+            Self::Virtual(button) => button.inputs(InputFamily::Virtual, inputs),
+        }
+    }
+
+    /// The threshold an axis has to pass to count as pressed, if this is bound to an axis.
+    pub fn threshold(&self) -> Option<f32> {
+        return match self {
+            Self::Keyboard(button) | Self::LogicalKeyboard(button) => button.threshold(),
+            Self::Mouse(button) => button.threshold(),
+            Self::Gamepad(button) => button.threshold(),
+            Self::Touch(button) => button.threshold(),
+            Self::Virtual(button) => button.threshold(),
+        };
+    }
+
+    pub fn is_chord(&self) -> bool {
+        return match self {
+            Self::Keyboard(button) | Self::LogicalKeyboard(button) => button.is_chord(),
+            Self::Mouse(button) => button.is_chord(),
+            Self::Gamepad(button) => button.is_chord(),
+            Self::Touch(button) => button.is_chord(),
+            Self::Virtual(button) => button.is_chord(),
+        };
+    }
+
+    /// The combinations of inputs that trigger this binding, where no two actions should share one.
+    /// A chord is a single combination, since it suppresses bindings to its button alone.
+    pub(crate) fn triggers(&self, triggers: &mut Vec<Vec<PhysicalInput>>) {
+        let mut inputs = vec![];
+        self.inputs(&mut inputs);
+
+        if !self.is_chord() {
+            triggers.extend(inputs.into_iter().map(|it| vec![it]));
+            return;
+        }
+
+        // Modifiers can be held in any order, but the button has to come last.
+        let modifiers = inputs.len() - 1;
+        inputs[..modifiers].sort();
+
+        triggers.push(inputs);
+    }
+}
+
+impl AnalogBinding {
+    /// Adds every button and axis this binding reads to `inputs`.
+    pub fn inputs(&self, inputs: &mut Vec<PhysicalInput>) {
+        match &self.axis {
+            AnalogBindingKind::Keyboard(axis) => axis.inputs(InputFamily::Keyboard, inputs),
+            AnalogBindingKind::LogicalKeyboard(axis) => {
+                let start = inputs.len();
+                axis.inputs(InputFamily::Keyboard, inputs);
+                logical_inputs(&mut inputs[start..]);
+            }
+            AnalogBindingKind::Mouse(axis) => axis.inputs(InputFamily::Mouse, inputs),
+            AnalogBindingKind::Gamepad(axis) => axis.inputs(InputFamily::Gamepad, inputs),
+            AnalogBindingKind::Touch(axis) => axis.inputs(InputFamily::Touch, inputs),
+            AnalogBindingKind::Virtual(axis) => axis.inputs(InputFamily::Virtual, inputs),
+        }
+    }
+
+    pub fn apply(&self, value: f32) -> f32 {
+        let value = self
+            .deadzone_shape
+            .apply_axis(value, self.deadzone, self.outer_deadzone);
+
+        return self.curve.apply_axis(value) * self.scale;
+    }
+}
+
+impl VectorBindingKind {
+    /// Adds every button and axis this binding reads to `inputs`.
+    pub fn inputs(&self, inputs: &mut Vec<PhysicalInput>) {
+        match self {
+            Self::Keyboard { x, y } => {
+                x.inputs(InputFamily::Keyboard, inputs);
+                y.inputs(InputFamily::Keyboard, inputs);
+            }
+            Self::LogicalKeyboard { x, y } => {
+                let start = inputs.len();
+                x.inputs(InputFamily::Keyboard, inputs);
+                y.inputs(InputFamily::Keyboard, inputs);
+                logical_inputs(&mut inputs[start..]);
+            }
+            Self::Mouse { x, y } => {
+                x.inputs(InputFamily::Mouse, inputs);
+                y.inputs(InputFamily::Mouse, inputs);
+            }
+            Self::Gamepad { x, y } => {
+                x.inputs(InputFamily::Gamepad, inputs);
+                y.inputs(InputFamily::Gamepad, inputs);
+            }
+            Self::Touch { x, y } => {
+                x.inputs(InputFamily::Touch, inputs);
+                y.inputs(InputFamily::Touch, inputs);
+            }
+            Self::Virtual { x, y } => {
+                x.inputs(InputFamily::Virtual, inputs);
+                y.inputs(InputFamily::Virtual, inputs);
+            }
+        }
+    }
+}
+
+impl VectorBinding {
+    /// Adds every button and axis this binding reads to `inputs`, including the ratchet.
+    pub fn inputs(&self, inputs: &mut Vec<PhysicalInput>) {
+        self.axes.inputs(inputs);
+
+        if let Some(ratchet) = &self.ratchet {
+            ratchet.inputs(inputs);
+        }
+    }
+
+    pub fn apply(&self, value: [f32; 2]) -> [f32; 2] {
+        let value = self
+            .deadzone_shape
+            .apply_vector(value, self.deadzone, self.outer_deadzone);
+
+        let [x, y] = self.curve.apply_vector(value);
+
+        return [x * self.scale.0, y * self.scale.1];
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -104,6 +442,25 @@ pub enum BindingList {
     Vector(Vec<VectorBinding>),
 }
 
+impl BindingList {
+    pub fn kind(&self) -> BindingKind {
+        match self {
+            Self::Digital(_) => return BindingKind::Digital,
+            Self::Analog(_) => return BindingKind::Analog,
+            Self::Vector(_) => return BindingKind::Vector,
+        }
+    }
+
+    /// Adds every button and axis these bindings read to `inputs`.
+    pub fn inputs(&self, inputs: &mut Vec<PhysicalInput>) {
+        match self {
+            Self::Digital(bindings) => bindings.iter().for_each(|it| it.inputs(inputs)),
+            Self::Analog(bindings) => bindings.iter().for_each(|it| it.inputs(inputs)),
+            Self::Vector(bindings) => bindings.iter().for_each(|it| it.inputs(inputs)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum BindingKind {
     Digital,
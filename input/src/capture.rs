@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+
+use num_enum::TryFromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    DeviceId,
+    binding::{
+        AnalogBinding, AnalogBindingKind, BindingKind, BoundAxis, BoundButton, DigitalBinding,
+        VectorBinding, VectorBindingKind,
+        axis::{GamepadAxis, MouseAxis, TouchAxis, VirtualAxis},
+        button::{GamepadButton, KeyboardButton, MouseButton, TouchButton, VirtualButton},
+    },
+    state::{InputFamily, InputState},
+};
+
+/// A binding produced by an `InputCapture`, ready to be added to an `InputManager`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "binding")]
+pub enum CapturedBinding {
+    Digital(DigitalBinding),
+    Analog(AnalogBinding),
+    Vector(VectorBinding),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureResult {
+    /// Nothing has been pressed or moved yet.
+    Pending,
+    /// One of the cancel buttons was pressed.
+    Cancelled,
+    Captured(CapturedBinding),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureOptions {
+    /// The kind of binding to produce. Inputs that can't be turned into this kind are ignored.
+    pub kind: BindingKind,
+    /// How far an axis has to move from where it was resting to be captured.
+    pub threshold: f32,
+    /// The deadzone given to captured analog and vector bindings.
+    pub deadzone: f32,
+    /// Buttons that cancel the capture instead of being captured.
+    pub cancel: Vec<DigitalBinding>,
+    /// Captures keys by what's printed on them instead of by their position.
+    pub logical_keys: bool,
+}
+
+impl CaptureOptions {
+    pub fn new(kind: BindingKind) -> Self {
+        return Self {
+            kind,
+            threshold: 0.5,
+            deadzone: 0.1,
+            cancel: vec![],
+            logical_keys: false,
+        };
+    }
+
+    fn capture_button(&self, binding: DigitalBinding) -> Option<CapturedBinding> {
+        let axis = match self.kind {
+            BindingKind::Digital => return Some(CapturedBinding::Digital(binding)),
+            BindingKind::Analog => match binding {
+                DigitalBinding::Keyboard(BoundButton::Digital(button)) => {
+                    AnalogBindingKind::Keyboard(BoundAxis::Digital(button))
+                }
+                DigitalBinding::LogicalKeyboard(BoundButton::Digital(button)) => {
+                    AnalogBindingKind::LogicalKeyboard(BoundAxis::Digital(button))
+                }
+                DigitalBinding::Mouse(BoundButton::Digital(button)) => {
+                    AnalogBindingKind::Mouse(BoundAxis::Digital(button))
+                }
+                DigitalBinding::Gamepad(BoundButton::Digital(button)) => {
+                    AnalogBindingKind::Gamepad(BoundAxis::Digital(button))
+                }
+                DigitalBinding::Touch(BoundButton::Digital(button)) => {
+                    AnalogBindingKind::Touch(BoundAxis::Digital(button))
+                }
+                DigitalBinding::Virtual(BoundButton::Digital(button)) => {
+                    AnalogBindingKind::Virtual(BoundAxis::Digital(button))
+                }
+                _ => return None,
+            },
+            // A single button can't drive both axes of a vector.
+            BindingKind::Vector => return None,
+        };
+
+        return Some(CapturedBinding::Analog(AnalogBinding {
+            axis,
+            deadzone: self.deadzone,
+            scale: 1f32,
+            deadzone_shape: Default::default(),
+            outer_deadzone: None,
+            curve: Default::default(),
+        }));
+    }
+
+    fn capture_axis(
+        &self,
+        family: InputFamily,
+        axis: usize,
+        value: f32,
+    ) -> Option<CapturedBinding> {
+        let axis = axis as u8;
+
+        // The cursor position changes whenever the mouse moves, so it would always be captured.
+        if family == InputFamily::Mouse
+            && matches!(
+                MouseAxis::try_from_primitive(axis),
+                Ok(MouseAxis::X | MouseAxis::Y | MouseAxis::NormalizedX | MouseAxis::NormalizedY)
+            )
+        {
+            return None;
+        }
+
+        // Picking up or tilting the controller to reach a button moves the motion sensors,
+        // so they'd be captured first. Only vectors can bind them, for aiming with the gyro.
+        if family == InputFamily::Gamepad
+            && self.kind != BindingKind::Vector
+            && matches!(
+                GamepadAxis::try_from_primitive(axis),
+                Ok(GamepadAxis::GyroX
+                    | GamepadAxis::GyroY
+                    | GamepadAxis::GyroZ
+                    | GamepadAxis::AccelX
+                    | GamepadAxis::AccelY
+                    | GamepadAxis::AccelZ)
+            )
+        {
+            return None;
+        }
+
+        match self.kind {
+            BindingKind::Digital => {
+                // Moving an axis in the negative direction captures it with a negative threshold,
+                // so the button is pressed while the axis is below it.
+                let threshold = self.threshold.copysign(value);
+
+                let binding = match family {
+                    InputFamily::Keyboard => return None,
+                    InputFamily::Mouse => DigitalBinding::Mouse(BoundButton::Analog {
+                        axis: MouseAxis::try_from_primitive(axis).ok()?,
+                        threshold,
+                    }),
+                    InputFamily::Gamepad => DigitalBinding::Gamepad(BoundButton::Analog {
+                        axis: GamepadAxis::try_from_primitive(axis).ok()?,
+                        threshold,
+                    }),
+                    InputFamily::Touch => DigitalBinding::Touch(BoundButton::Analog {
+                        axis: TouchAxis::try_from_primitive(axis).ok()?,
+                        threshold,
+                    }),
+                    InputFamily::Virtual => DigitalBinding::Virtual(BoundButton::Analog {
+                        axis: VirtualAxis(axis),
+                        threshold,
+                    }),
+                };
+
+                return Some(CapturedBinding::Digital(binding));
+            }
+
+            BindingKind::Analog => {
+                let axis = match family {
+                    InputFamily::Keyboard => return None,
+                    InputFamily::Mouse => AnalogBindingKind::Mouse(BoundAxis::Analog(
+                        MouseAxis::try_from_primitive(axis).ok()?,
+                    )),
+                    InputFamily::Gamepad => AnalogBindingKind::Gamepad(BoundAxis::Analog(
+                        GamepadAxis::try_from_primitive(axis).ok()?,
+                    )),
+                    InputFamily::Touch => AnalogBindingKind::Touch(BoundAxis::Analog(
+                        TouchAxis::try_from_primitive(axis).ok()?,
+                    )),
+                    InputFamily::Virtual => {
+                        AnalogBindingKind::Virtual(BoundAxis::Analog(VirtualAxis(axis)))
+                    }
+                };
+
+                return Some(CapturedBinding::Analog(AnalogBinding {
+                    axis,
+                    deadzone: self.deadzone,
+                    // Moving an axis in the negative direction captures it inverted.
+                    scale: value.signum(),
+                    deadzone_shape: Default::default(),
+                    outer_deadzone: None,
+                    curve: Default::default(),
+                }));
+            }
+
+            BindingKind::Vector => {
+                let axes = match family {
+                    // Nothing says which axis a virtual axis pairs with.
+                    InputFamily::Keyboard | InputFamily::Virtual => return None,
+                    InputFamily::Mouse => {
+                        let (x, y) = match MouseAxis::try_from_primitive(axis).ok()? {
+                            MouseAxis::DeltaX | MouseAxis::DeltaY => {
+                                (MouseAxis::DeltaX, MouseAxis::DeltaY)
+                            }
+                            MouseAxis::WheelX | MouseAxis::WheelY => {
+                                (MouseAxis::WheelX, MouseAxis::WheelY)
+                            }
+                            MouseAxis::X
+                            | MouseAxis::Y
+                            | MouseAxis::NormalizedX
+                            | MouseAxis::NormalizedY => return None,
+                        };
+
+                        VectorBindingKind::Mouse {
+                            x: BoundAxis::Analog(x),
+                            y: BoundAxis::Analog(y),
+                        }
+                    }
+                    InputFamily::Gamepad => {
+                        let (x, y) = match GamepadAxis::try_from_primitive(axis).ok()? {
+                            GamepadAxis::LeftX | GamepadAxis::LeftY => {
+                                (GamepadAxis::LeftX, GamepadAxis::LeftY)
+                            }
+                            GamepadAxis::RightX | GamepadAxis::RightY => {
+                                (GamepadAxis::RightX, GamepadAxis::RightY)
+                            }
+                            // Yaw turns left and right, and pitch up and down, like aiming with a mouse.
+                            GamepadAxis::GyroX | GamepadAxis::GyroY => {
+                                (GamepadAxis::GyroY, GamepadAxis::GyroX)
+                            }
+                            GamepadAxis::LeftTrigger
+                            | GamepadAxis::RightTrigger
+                            | GamepadAxis::GyroZ
+                            | GamepadAxis::AccelX
+                            | GamepadAxis::AccelY
+                            | GamepadAxis::AccelZ => return None,
+                        };
+
+                        VectorBindingKind::Gamepad {
+                            x: BoundAxis::Analog(x),
+                            y: BoundAxis::Analog(y),
+                        }
+                    }
+                    InputFamily::Touch => {
+                        let (x, y) = match TouchAxis::try_from_primitive(axis).ok()? {
+                            TouchAxis::X | TouchAxis::Y => (TouchAxis::X, TouchAxis::Y),
+                            TouchAxis::PanX | TouchAxis::PanY => (TouchAxis::PanX, TouchAxis::PanY),
+                            TouchAxis::Pressure | TouchAxis::Pinch => return None,
+                        };
+
+                        VectorBindingKind::Touch {
+                            x: BoundAxis::Analog(x),
+                            y: BoundAxis::Analog(y),
+                        }
+                    }
+                };
+
+                return Some(CapturedBinding::Vector(VectorBinding {
+                    axes,
+                    deadzone: self.deadzone,
+                    scale: (1f32, 1f32),
+                    deadzone_shape: Default::default(),
+                    outer_deadzone: None,
+                    curve: Default::default(),
+                    ratchet: None,
+                }));
+            }
+        }
+    }
+}
+
+struct DeviceBaseline {
+    held: Vec<bool>,
+    axes: Vec<f32>,
+}
+
+/// Waits for the next button press or axis movement on any connected device.
+///
+/// Buttons that are already held, and axes at the position they were resting at,
+/// when the capture starts (or when a device connects) are ignored.
+/// A held button can be captured once it has been released and pressed again.
+pub struct InputCapture {
+    options: CaptureOptions,
+    baselines: HashMap<DeviceId, DeviceBaseline>,
+}
+
+impl InputCapture {
+    pub fn new(state: &InputState, options: CaptureOptions) -> Self {
+        let mut capture = Self {
+            options,
+            baselines: HashMap::new(),
+        };
+
+        for (device, _, digital, analog) in state.raw_devices() {
+            capture.baselines.insert(
+                device,
+                DeviceBaseline {
+                    held: digital.iter().map(|it| *it).collect(),
+                    axes: analog.to_vec(),
+                },
+            );
+        }
+
+        return capture;
+    }
+
+    pub fn options(&self) -> &CaptureOptions {
+        return &self.options;
+    }
+
+    pub fn poll(&mut self, state: &InputState) -> CaptureResult {
+        let mut devices = state.raw_devices().collect::<Vec<_>>();
+        devices.sort_by_key(|(device, _, _, _)| *device);
+
+        self.baselines
+            .retain(|device, _| devices.iter().any(|it| it.0 == *device));
+
+        for (device, family, digital, analog) in devices {
+            let Some(baseline) = self.baselines.get_mut(&device) else {
+                self.baselines.insert(
+                    device,
+                    DeviceBaseline {
+                        held: digital.iter().map(|it| *it).collect(),
+                        axes: analog.to_vec(),
+                    },
+                );
+                continue;
+            };
+
+            for (held, pressed) in baseline.held.iter_mut().zip(digital.iter()) {
+                *held &= *pressed;
+            }
+
+            for button in digital.iter_ones() {
+                if baseline.held[button] {
+                    continue;
+                }
+
+                let Some(binding) = digital_binding(family, button) else {
+                    continue;
+                };
+
+                if self.options.cancel.contains(&binding) {
+                    return CaptureResult::Cancelled;
+                }
+
+                let binding = match binding {
+                    DigitalBinding::Keyboard(BoundButton::Digital(key))
+                        if self.options.logical_keys =>
+                    {
+                        let logical = state
+                            .keyboard_layout(&device)
+                            .map_or(key, |it| it.logical(key));
+
+                        DigitalBinding::LogicalKeyboard(BoundButton::Digital(logical))
+                    }
+                    binding => binding,
+                };
+
+                let Some(captured) = self.options.capture_button(binding) else {
+                    continue;
+                };
+
+                return CaptureResult::Captured(captured);
+            }
+
+            for (axis, value) in analog.iter().enumerate() {
+                let rest = baseline.axes[axis];
+
+                if value.abs() < self.options.threshold
+                    || (value - rest).abs() < self.options.threshold
+                {
+                    continue;
+                }
+
+                let Some(captured) = self.options.capture_axis(family, axis, *value) else {
+                    continue;
+                };
+
+                return CaptureResult::Captured(captured);
+            }
+        }
+
+        return CaptureResult::Pending;
+    }
+}
+
+fn digital_binding(family: InputFamily, button: usize) -> Option<DigitalBinding> {
+    let button = button as u8;
+
+    return match family {
+        InputFamily::Keyboard => Some(DigitalBinding::Keyboard(BoundButton::Digital(
+            KeyboardButton::try_from_primitive(button).ok()?,
+        ))),
+        InputFamily::Mouse => Some(DigitalBinding::Mouse(BoundButton::Digital(
+            MouseButton::try_from_primitive(button).ok()?,
+        ))),
+        InputFamily::Gamepad => Some(DigitalBinding::Gamepad(BoundButton::Digital(
+            GamepadButton::try_from_primitive(button).ok()?,
+        ))),
+        InputFamily::Touch => Some(DigitalBinding::Touch(BoundButton::Digital(
+            TouchButton::try_from_primitive(button).ok()?,
+        ))),
+        InputFamily::Virtual => Some(DigitalBinding::Virtual(BoundButton::Digital(
+            VirtualButton(button),
+        ))),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_held_buttons() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::A, true);
+
+        let mut capture = state.begin_capture(CaptureOptions::new(BindingKind::Digital));
+
+        assert_eq!(capture.poll(&state), CaptureResult::Pending);
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::A, false);
+        assert_eq!(capture.poll(&state), CaptureResult::Pending);
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::A, true);
+        assert_eq!(
+            capture.poll(&state),
+            CaptureResult::Captured(CapturedBinding::Digital(DigitalBinding::Keyboard(
+                BoundButton::Digital(KeyboardButton::A)
+            )))
+        );
+    }
+
+    #[test]
+    fn cancel_buttons() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        let mut options = CaptureOptions::new(BindingKind::Digital);
+        options
+            .cancel
+            .push(DigitalBinding::Keyboard(BoundButton::Digital(
+                KeyboardButton::Escape,
+            )));
+
+        let mut capture = state.begin_capture(options);
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Escape, true);
+        assert_eq!(capture.poll(&state), CaptureResult::Cancelled);
+    }
+
+    #[test]
+    fn ignores_resting_axes() {
+        let mut state = InputState::new();
+        let gamepad = state.connect_gamepad();
+
+        state.set_gamepad_axis(&gamepad, GamepadAxis::LeftTrigger, -1.0);
+
+        let mut capture = state.begin_capture(CaptureOptions::new(BindingKind::Vector));
+
+        assert_eq!(capture.poll(&state), CaptureResult::Pending);
+
+        state.set_gamepad_axis(&gamepad, GamepadAxis::RightY, -0.8);
+
+        let CaptureResult::Captured(CapturedBinding::Vector(binding)) = capture.poll(&state) else {
+            panic!("expected a vector binding");
+        };
+
+        assert_eq!(
+            binding.axes,
+            VectorBindingKind::Gamepad {
+                x: BoundAxis::Analog(GamepadAxis::RightX),
+                y: BoundAxis::Analog(GamepadAxis::RightY),
+            }
+        );
+    }
+
+    #[test]
+    fn ignores_motion_for_buttons() {
+        let mut state = InputState::new();
+        let gamepad = state.connect_gamepad();
+
+        let mut capture = state.begin_capture(CaptureOptions::new(BindingKind::Digital));
+
+        state.set_gamepad_axis(&gamepad, GamepadAxis::GyroX, 2.0);
+        state.set_gamepad_axis(&gamepad, GamepadAxis::AccelZ, 1.0);
+
+        assert_eq!(capture.poll(&state), CaptureResult::Pending);
+
+        state.set_gamepad_button(&gamepad, GamepadButton::South, true);
+
+        assert_eq!(
+            capture.poll(&state),
+            CaptureResult::Captured(CapturedBinding::Digital(DigitalBinding::Gamepad(
+                BoundButton::Digital(GamepadButton::South)
+            )))
+        );
+    }
+
+    #[test]
+    fn negative_axis_is_inverted() {
+        let mut state = InputState::new();
+        let gamepad = state.connect_gamepad();
+
+        let mut capture = state.begin_capture(CaptureOptions::new(BindingKind::Analog));
+
+        state.set_gamepad_axis(&gamepad, GamepadAxis::LeftX, -0.9);
+
+        let CaptureResult::Captured(CapturedBinding::Analog(binding)) = capture.poll(&state) else {
+            panic!("expected an analog binding");
+        };
+
+        assert_eq!(
+            binding.axis,
+            AnalogBindingKind::Gamepad(BoundAxis::Analog(GamepadAxis::LeftX))
+        );
+        assert_eq!(binding.scale, -1.0);
+    }
+
+    #[test]
+    fn negative_axis_is_captured_below_threshold() {
+        let mut state = InputState::new();
+        let gamepad = state.connect_gamepad();
+
+        let mut capture = state.begin_capture(CaptureOptions::new(BindingKind::Digital));
+
+        state.set_gamepad_axis(&gamepad, GamepadAxis::LeftY, -0.9);
+
+        assert_eq!(
+            capture.poll(&state),
+            CaptureResult::Captured(CapturedBinding::Digital(DigitalBinding::Gamepad(
+                BoundButton::Analog {
+                    axis: GamepadAxis::LeftY,
+                    threshold: -0.5,
+                }
+            )))
+        );
+    }
+}
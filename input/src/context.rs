@@ -0,0 +1,662 @@
+use std::collections::HashMap;
+
+use pawkit_holy_array::HolyArray;
+use pawkit_interner::InternString;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    DeviceId,
+    aggregation::DeviceAggregation,
+    binding::{
+        BindingKind, BindingList, PhysicalInput,
+        map::{BindingMap, BindingMapModificaitonError},
+    },
+    history::ActionValue,
+    interaction::{DigitalInteractions, InteractionTimers},
+    manager::{AnalogInputFrame, DigitalInputFrame, InputFrame, RawInputFrame, VectorInputFrame},
+    state::{InputState, MouseReads},
+};
+
+/// What lower priority contexts are allowed to see while a context is active.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum InputConsumption {
+    /// Lower contexts see every input, such as for an overlay that only adds actions.
+    Passthrough,
+    /// Inputs bound in this context are hidden from lower contexts,
+    /// so Escape can close a menu without also pausing the game.
+    #[default]
+    Bound,
+    /// Lower contexts don't see any input, such as for a modal dialog.
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextOptions {
+    /// Active contexts with a higher priority see input first.
+    /// Contexts with the same priority are ordered by when they were pushed, newest first.
+    pub priority: i32,
+    pub consumption: InputConsumption,
+}
+
+/// The frames of a context at one point in time, and which devices were driving its actions.
+#[derive(Clone)]
+pub(crate) struct ContextState {
+    frames: HolyArray<RawInputFrame>,
+    interactions: HashMap<usize, (DigitalInteractions, InteractionTimers)>,
+    sources: Vec<Option<DeviceId>>,
+    recent: HashMap<usize, Vec<DeviceId>>,
+}
+
+/// A frame with nothing held, for a new action.
+fn released_frame(kind: BindingKind) -> RawInputFrame {
+    match kind {
+        BindingKind::Digital => {
+            return RawInputFrame {
+                digital: DigitalInputFrame {
+                    pressed: false,
+                    just_pressed: false,
+                    just_released: false,
+                    held: false,
+                    long_pressed: false,
+                    tapped: false,
+                    double_tapped: false,
+                    repeated: false,
+                    buffered: false,
+                },
+            };
+        }
+
+        BindingKind::Analog => {
+            return RawInputFrame {
+                analog: AnalogInputFrame {
+                    value: 0f32,
+                    delta: 0f32,
+                },
+            };
+        }
+
+        BindingKind::Vector => {
+            return RawInputFrame {
+                vector: VectorInputFrame {
+                    value: [0f32; 2],
+                    delta: [0f32; 2],
+                },
+            };
+        }
+    }
+}
+
+/// A set of actions with their own bindings and frames.
+pub(crate) struct ActionContext {
+    pub(crate) bindings: BindingMap,
+    pub(crate) options: ContextOptions,
+    frame_indices: im::HashMap<InternString, usize>,
+    /// Frames are never moved, so an action keeps its index while others come and go.
+    frames: HolyArray<RawInputFrame>,
+    interactions: HashMap<usize, (DigitalInteractions, InteractionTimers)>,
+    /// The device that last drove each action, by frame index.
+    sources: Vec<Option<DeviceId>>,
+    /// Actions missing from here use the default aggregation.
+    aggregation: HashMap<usize, DeviceAggregation>,
+    /// The devices driving each `MostRecent` action, oldest first.
+    recent: HashMap<usize, Vec<DeviceId>>,
+}
+
+impl ActionContext {
+    pub(crate) fn new(bindings: BindingMap, options: ContextOptions) -> Self {
+        let mut context = Self {
+            bindings: BindingMap::new(),
+            options,
+            frame_indices: im::HashMap::new(),
+            frames: HolyArray::new(),
+            interactions: HashMap::new(),
+            sources: vec![],
+            aggregation: HashMap::new(),
+            recent: HashMap::new(),
+        };
+
+        for (name, bindings) in bindings {
+            context.register(name, bindings);
+        }
+
+        return context;
+    }
+
+    /// Adds an action, or replaces the bindings of an existing one.
+    /// A replaced action keeps its frame unless it changes kind, in which case it starts out released.
+    pub(crate) fn register(&mut self, name: InternString, bindings: BindingList) {
+        let kind = bindings.kind();
+
+        if self.bindings.get_binding_kind(&name) == Some(kind) {
+            self.bindings.register_binding(name, bindings);
+            return;
+        }
+
+        self.unregister(&name);
+
+        let index = self.frames.acquire(released_frame(kind));
+
+        if index >= self.sources.len() {
+            self.sources.resize(index + 1, None);
+        }
+
+        self.frame_indices.insert(name.clone(), index);
+        self.bindings.register_binding(name, bindings);
+    }
+
+    /// Removes an action and frees its frame. Returns false if there's no such action.
+    pub(crate) fn unregister(&mut self, name: &InternString) -> bool {
+        let Some(index) = self.frame_indices.remove(name) else {
+            return false;
+        };
+
+        self.bindings.unregister_binding(name);
+        self.frames.release(index);
+        self.interactions.remove(&index);
+        self.aggregation.remove(&index);
+        self.recent.remove(&index);
+        self.sources[index] = None;
+
+        return true;
+    }
+
+    pub(crate) fn contains(&self, name: &InternString) -> bool {
+        return self.frame_indices.contains_key(name);
+    }
+
+    /// Adds every button and axis this context binds to `inputs`.
+    pub(crate) fn inputs(&self, inputs: &mut Vec<PhysicalInput>) {
+        for (_, bindings) in &self.bindings {
+            bindings.inputs(inputs);
+        }
+    }
+
+    pub(crate) fn source(&self, name: &InternString) -> Option<DeviceId> {
+        let index = self.frame_indices.get(name)?;
+
+        return self.sources[*index];
+    }
+
+    pub(crate) fn clear_source(&mut self, device: DeviceId) {
+        for source in &mut self.sources {
+            if *source == Some(device) {
+                *source = None;
+            }
+        }
+
+        for recent in self.recent.values_mut() {
+            recent.retain(|it| *it != device);
+        }
+    }
+
+    pub(crate) fn set_interactions(
+        &mut self,
+        name: &InternString,
+        interactions: DigitalInteractions,
+    ) -> Result<(), BindingMapModificaitonError> {
+        let Some(index) = self.frame_indices.get(name) else {
+            return Err(BindingMapModificaitonError::NotFound);
+        };
+
+        let Some(BindingKind::Digital) = self.bindings.get_binding_kind(name) else {
+            return Err(BindingMapModificaitonError::TypeMismatch);
+        };
+
+        self.interactions.entry(*index).or_default().0 = interactions;
+
+        return Ok(());
+    }
+
+    pub(crate) fn set_aggregation(
+        &mut self,
+        name: &InternString,
+        aggregation: DeviceAggregation,
+    ) -> Result<(), BindingMapModificaitonError> {
+        let Some(index) = self.frame_indices.get(name) else {
+            return Err(BindingMapModificaitonError::NotFound);
+        };
+
+        self.aggregation.insert(*index, aggregation);
+        self.recent.remove(index);
+
+        return Ok(());
+    }
+
+    /// Reads the value of every action from the given devices, by frame index, ignoring the inputs in `consumed`.
+    /// Raises `activity[i]` to how strongly `devices[i]` drove any action, from 0 to 1.
+    pub(crate) fn sample(
+        &mut self,
+        state: &InputState,
+        devices: &[DeviceId],
+        consumed: &[PhysicalInput],
+        reads: &MouseReads,
+        activity: &mut [f32],
+    ) -> Vec<ActionValue> {
+        let chords = devices
+            .iter()
+            .map(|device| {
+                let mut chords = vec![];
+
+                for (_, bindings) in &self.bindings {
+                    let BindingList::Digital(bindings) = bindings else {
+                        continue;
+                    };
+
+                    state.get_active_chords(device, bindings, consumed, reads, &mut chords);
+                }
+
+                return chords;
+            })
+            .collect::<Vec<_>>();
+
+        let mut values = self.released_values();
+
+        for (name, index) in &self.frame_indices {
+            let Some(bindings) = self.bindings.get_bindings(name) else {
+                continue;
+            };
+
+            let readings = devices
+                .iter()
+                .zip(&chords)
+                .enumerate()
+                .filter_map(|(i, (device, chords))| {
+                    let value = match (bindings, state.virtual_action(device, name)) {
+                        (BindingList::Digital(_), Some(value @ ActionValue::Digital(_)))
+                        | (BindingList::Analog(_), Some(value @ ActionValue::Analog(_)))
+                        | (BindingList::Vector(_), Some(value @ ActionValue::Vector(_))) => value,
+
+                        (BindingList::Digital(bindings), _) => ActionValue::Digital(
+                            state.get_digital(device, bindings, chords, consumed, reads)?,
+                        ),
+                        (BindingList::Analog(bindings), _) => ActionValue::Analog(
+                            state.get_analog(device, bindings, consumed, reads)?,
+                        ),
+                        (BindingList::Vector(bindings), _) => ActionValue::Vector(
+                            state.get_vector(device, bindings, consumed, reads)?,
+                        ),
+                    };
+
+                    return Some((i, value));
+                })
+                .collect::<Vec<_>>();
+
+            for (i, value) in &readings {
+                activity[*i] = activity[*i].max(value.magnitude().min(1f32));
+            }
+
+            let aggregation = self.aggregation.get(index).copied().unwrap_or_default();
+
+            if aggregation == DeviceAggregation::MostRecent {
+                let recent = self.recent.entry(*index).or_default();
+
+                recent.retain(|device| {
+                    readings
+                        .iter()
+                        .any(|(i, value)| devices[*i] == *device && value.magnitude() > 0f32)
+                });
+
+                for (i, value) in &readings {
+                    if value.magnitude() > 0f32 && !recent.contains(&devices[*i]) {
+                        recent.push(devices[*i]);
+                    }
+                }
+            }
+
+            let recent = self.recent.get(index).map(Vec::as_slice).unwrap_or(&[]);
+
+            let (value, source) = aggregation.combine(values[*index], &readings, devices, recent);
+
+            values[*index] = value;
+
+            if let Some(i) = source {
+                self.sources[*index] = Some(devices[i]);
+            }
+        }
+
+        return values;
+    }
+
+    /// The value of every action while nothing is held, by frame index.
+    pub(crate) fn released_values(&self) -> Vec<ActionValue> {
+        let mut values = vec![ActionValue::Digital(false); self.frames.len()];
+
+        for (name, index) in &self.frame_indices {
+            values[*index] = match self.bindings.get_binding_kind(name) {
+                Some(BindingKind::Analog) => ActionValue::Analog(0f32),
+                Some(BindingKind::Vector) => ActionValue::Vector([0f32; 2]),
+                _ => ActionValue::Digital(false),
+            };
+        }
+
+        return values;
+    }
+
+    /// Moves every frame on by one tick, from the values `sample` read.
+    pub(crate) fn advance(&mut self, values: &[ActionValue], delta_time: f32) {
+        for (name, index) in &self.frame_indices {
+            let Some(frame) = self.frames.get_mut(*index) else {
+                continue;
+            };
+
+            let (Some(kind), Some(value)) =
+                (self.bindings.get_binding_kind(name), values.get(*index))
+            else {
+                continue;
+            };
+
+            match (kind, *value) {
+                (BindingKind::Digital, ActionValue::Digital(pressed)) => {
+                    // SAFETY: We constructed the raw frame with the same type that the given name is
+                    let frame = unsafe { &mut frame.digital };
+
+                    let (interactions, timers) = self.interactions.entry(*index).or_default();
+
+                    interactions.update(frame, timers, pressed, delta_time);
+                }
+
+                (BindingKind::Analog, ActionValue::Analog(value)) => {
+                    // SAFETY: We constructed the raw frame with the same type that the given name is
+                    let frame = unsafe { &mut frame.analog };
+
+                    let old_value = frame.value;
+
+                    frame.value = value;
+                    frame.delta = value - old_value;
+                }
+
+                (BindingKind::Vector, ActionValue::Vector(value)) => {
+                    // SAFETY: We constructed the raw frame with the same type that the given name is
+                    let frame = unsafe { &mut frame.vector };
+
+                    let old_value = frame.value;
+
+                    frame.value = value;
+                    frame.delta = [value[0] - old_value[0], value[1] - old_value[1]];
+                }
+
+                _ => continue,
+            }
+        }
+    }
+
+    pub(crate) fn index_of(&self, name: &InternString) -> Option<usize> {
+        return self.frame_indices.get(name).copied();
+    }
+
+    /// Copies the frames, interaction timers and action sources, so they can be restored when rolling back.
+    pub(crate) fn save(&self) -> ContextState {
+        return ContextState {
+            frames: self.frames.clone(),
+            interactions: self.interactions.clone(),
+            sources: self.sources.clone(),
+            recent: self.recent.clone(),
+        };
+    }
+
+    /// Devices that aren't in `devices` any more are left out of the restored sources.
+    pub(crate) fn restore(&mut self, state: &ContextState, devices: &[DeviceId]) {
+        self.frames.clone_from(&state.frames);
+        self.interactions.clone_from(&state.interactions);
+        self.restore_sources(state, devices);
+    }
+
+    /// Puts back which devices were driving each action, without touching the frames.
+    pub(crate) fn restore_sources(&mut self, state: &ContextState, devices: &[DeviceId]) {
+        self.sources.clone_from(&state.sources);
+        self.recent.clone_from(&state.recent);
+
+        for source in &mut self.sources {
+            if source.is_some_and(|it| !devices.contains(&it)) {
+                *source = None;
+            }
+        }
+
+        for recent in self.recent.values_mut() {
+            recent.retain(|it| devices.contains(it));
+        }
+    }
+
+    /// Uses up a buffered press, returning whether there was one.
+    pub(crate) fn consume(&mut self, name: &InternString) -> bool {
+        let Some(index) = self.frame_indices.get(name) else {
+            return false;
+        };
+
+        let Some(BindingKind::Digital) = self.bindings.get_binding_kind(name) else {
+            return false;
+        };
+
+        // SAFETY: We constructed the raw frame with the same type that the given name is
+        let Some(frame) = self.frames.get_mut(*index) else {
+            return false;
+        };
+
+        // SAFETY: We constructed the raw frame with the same type that the given name is
+        let frame = unsafe { &mut frame.digital };
+
+        if !frame.buffered {
+            return false;
+        }
+
+        frame.buffered = false;
+
+        return true;
+    }
+
+    pub(crate) fn get_binding_raw(&self, name: &InternString) -> Option<RawInputFrame> {
+        let index = self.frame_indices.get(name)?;
+
+        return self.frames.get(*index).copied();
+    }
+
+    pub(crate) fn get_binding(&self, name: &InternString) -> Option<InputFrame> {
+        let index = self.frame_indices.get(name)?;
+
+        let frame = self.frames.get(*index)?;
+
+        match self.bindings.get_binding_kind(name)? {
+            BindingKind::Digital => return Some(InputFrame::Digital(unsafe { frame.digital })),
+            BindingKind::Analog => return Some(InputFrame::Analog(unsafe { frame.analog })),
+            BindingKind::Vector => return Some(InputFrame::Vector(unsafe { frame.vector })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{
+            AnalogBinding, AnalogBindingKind, BoundAxis, BoundButton, DigitalBinding,
+            button::KeyboardButton,
+        },
+        manager::InputManager,
+    };
+
+    fn bindings(actions: &[(&str, KeyboardButton)]) -> BindingMap {
+        let mut bindings = BindingMap::new();
+
+        for (name, button) in actions {
+            bindings.register_binding(
+                InternString::new(name),
+                BindingList::Digital(vec![DigitalBinding::Keyboard(BoundButton::Digital(
+                    *button,
+                ))]),
+            );
+        }
+
+        return bindings;
+    }
+
+    fn frame(manager: &InputManager, name: &str) -> DigitalInputFrame {
+        let Some(InputFrame::Digital(frame)) = manager.get_binding(&InternString::new(name)) else {
+            panic!("{} is not a digital binding", name);
+        };
+
+        return frame;
+    }
+
+    #[test]
+    fn higher_context_consumes_bound_inputs() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        let mut manager = InputManager::new(bindings(&[
+            ("pause", KeyboardButton::Escape),
+            ("jump", KeyboardButton::Space),
+        ]));
+        manager.connect_device(keyboard);
+
+        let menu = InternString::new("menu");
+
+        assert!(manager.add_context(
+            menu.clone(),
+            bindings(&[("back", KeyboardButton::Escape)]),
+            ContextOptions::default(),
+        ));
+        assert!(manager.push_context(&menu));
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Escape, true);
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        manager.update(&state, 0.1);
+
+        assert!(frame(&manager, "back").pressed);
+        assert!(!frame(&manager, "pause").pressed);
+        assert!(frame(&manager, "jump").pressed);
+
+        assert_eq!(manager.pop_context(), Some(menu));
+        manager.update(&state, 0.1);
+
+        assert!(frame(&manager, "back").just_released);
+        assert!(frame(&manager, "pause").just_pressed);
+    }
+
+    #[test]
+    fn priority_orders_contexts() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        let mut manager = InputManager::new(bindings(&[("jump", KeyboardButton::Space)]));
+        manager.connect_device(keyboard);
+
+        let dialog = InternString::new("dialog");
+        let vehicle = InternString::new("vehicle");
+
+        manager.add_context(
+            dialog.clone(),
+            bindings(&[("confirm", KeyboardButton::Enter)]),
+            ContextOptions {
+                priority: 10,
+                consumption: InputConsumption::All,
+            },
+        );
+        manager.add_context(
+            vehicle.clone(),
+            bindings(&[("honk", KeyboardButton::Enter)]),
+            ContextOptions::default(),
+        );
+
+        manager.push_context(&dialog);
+        manager.push_context(&vehicle);
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Enter, true);
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        manager.update(&state, 0.1);
+
+        // The dialog was pushed first, but its priority still puts it above the vehicle.
+        assert!(frame(&manager, "confirm").pressed);
+        assert!(!frame(&manager, "honk").pressed);
+        assert!(!frame(&manager, "jump").pressed);
+
+        assert!(manager.deactivate_context(&dialog));
+        manager.update(&state, 0.1);
+
+        assert!(frame(&manager, "honk").pressed);
+        assert!(frame(&manager, "jump").pressed);
+        assert_eq!(manager.active_contexts(), [vehicle]);
+    }
+
+    #[test]
+    fn actions_change_at_runtime() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        let mut manager = InputManager::new(bindings(&[("jump", KeyboardButton::Space)]));
+        manager.connect_device(keyboard);
+
+        let jump = InternString::new("jump");
+        let dash = InternString::new("dash");
+        let key = |button| {
+            BindingList::Digital(vec![DigitalBinding::Keyboard(BoundButton::Digital(button))])
+        };
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        state.set_keyboard_button(&keyboard, KeyboardButton::LeftShift, true);
+        manager.update(&state, 0.1);
+
+        assert!(manager.register_action(dash.clone(), key(KeyboardButton::LeftShift)));
+        assert!(!manager.register_action(dash.clone(), key(KeyboardButton::LeftShift)));
+        assert!(!frame(&manager, "dash").pressed);
+
+        manager.update(&state, 0.1);
+
+        // The new action starts from a released frame, and the old one isn't pressed again.
+        assert!(frame(&manager, "dash").just_pressed);
+        assert!(frame(&manager, "jump").pressed);
+        assert!(!frame(&manager, "jump").just_pressed);
+
+        // Replacing the bindings with the same kind keeps the frame.
+        assert!(manager.replace_action(jump.clone(), key(KeyboardButton::W)));
+        assert!(frame(&manager, "jump").pressed);
+
+        manager.update(&state, 0.1);
+
+        assert!(frame(&manager, "jump").just_released);
+
+        assert!(manager.replace_action(
+            dash.clone(),
+            BindingList::Analog(vec![AnalogBinding {
+                axis: AnalogBindingKind::Keyboard(BoundAxis::Digital(KeyboardButton::LeftShift)),
+                deadzone: 0f32,
+                scale: 1f32,
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+            }]),
+        ));
+
+        manager.update(&state, 0.1);
+
+        let Some(InputFrame::Analog(dashed)) = manager.get_binding(&dash) else {
+            panic!("dash is not an analog binding");
+        };
+
+        assert_eq!(dashed.value, 1f32);
+        assert_eq!(dashed.delta, 1f32);
+
+        assert!(manager.unregister_action(&jump));
+        assert!(!manager.unregister_action(&jump));
+        assert!(manager.get_binding(&jump).is_none());
+        assert!(!manager.replace_action(jump.clone(), key(KeyboardButton::Space)));
+
+        let menu = InternString::new("menu");
+
+        manager.add_context(menu.clone(), BindingMap::new(), ContextOptions::default());
+        manager.push_context(&menu);
+
+        assert!(manager.register_context_action(&menu, jump.clone(), key(KeyboardButton::Space)));
+
+        manager.update(&state, 0.1);
+
+        assert!(frame(&manager, "jump").just_pressed);
+        assert!(manager.unregister_context_action(&menu, &jump));
+        assert!(!manager.register_context_action(
+            &InternString::new("missing"),
+            jump,
+            key(KeyboardButton::Space)
+        ));
+    }
+}
@@ -0,0 +1,782 @@
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Read},
+    mem::size_of,
+};
+
+use num_enum::TryFromPrimitive;
+
+use crate::{
+    DeviceId,
+    binding::{
+        axis::{GamepadAxis, MouseAxis},
+        button::{GamepadButton, KeyboardButton, MouseButton},
+    },
+    state::InputState,
+};
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_ABS: u16 = 0x03;
+
+const SYN_REPORT: u16 = 0;
+const SYN_DROPPED: u16 = 3;
+
+const REL_X: u16 = 0x00;
+const REL_Y: u16 = 0x01;
+const REL_HWHEEL: u16 = 0x06;
+const REL_WHEEL: u16 = 0x08;
+
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+
+/// The size of a `struct input_event`, which starts with a `struct timeval` of two `long`s.
+const EVENT_SIZE: usize = size_of::<usize>() * 2 + 8;
+
+/// A single `struct input_event`, without its timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvdevEvent {
+    pub kind: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+impl EvdevEvent {
+    pub fn new(kind: u16, code: u16, value: i32) -> Self {
+        return Self { kind, code, value };
+    }
+
+    fn parse(bytes: &[u8]) -> Self {
+        let bytes = &bytes[size_of::<usize>() * 2..];
+
+        return Self {
+            kind: u16::from_ne_bytes([bytes[0], bytes[1]]),
+            code: u16::from_ne_bytes([bytes[2], bytes[3]]),
+            value: i32::from_ne_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        };
+    }
+
+    /// The event as the kernel would write it, with a zero timestamp.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; size_of::<usize>() * 2];
+
+        bytes.extend_from_slice(&self.kind.to_ne_bytes());
+        bytes.extend_from_slice(&self.code.to_ne_bytes());
+        bytes.extend_from_slice(&self.value.to_ne_bytes());
+
+        return bytes;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvdevKind {
+    Keyboard,
+    Mouse,
+    Gamepad,
+}
+
+/// The range of an absolute axis, from `EVIOCGABS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbsInfo {
+    pub minimum: i32,
+    pub maximum: i32,
+    /// Values this close to the center are reported as the center.
+    pub flat: i32,
+}
+
+impl AbsInfo {
+    /// Normalizes a stick axis to -1 to 1.
+    fn stick(&self, value: i32) -> f32 {
+        let center = (self.minimum as f32 + self.maximum as f32) / 2f32;
+        let half = (self.maximum as f32 - self.minimum as f32) / 2f32;
+
+        if half <= 0f32 || (value as f32 - center).abs() <= self.flat as f32 {
+            return 0f32;
+        }
+
+        return ((value as f32 - center) / half).clamp(-1f32, 1f32);
+    }
+
+    /// Normalizes a trigger axis to 0 to 1.
+    fn trigger(&self, value: i32) -> f32 {
+        let range = self.maximum as f32 - self.minimum as f32;
+
+        if range <= 0f32 {
+            return 0f32;
+        }
+
+        return ((value as f32 - self.minimum as f32) / range).clamp(0f32, 1f32);
+    }
+}
+
+struct EvdevDevice<R: Read> {
+    id: DeviceId,
+    kind: EvdevKind,
+    abs: HashMap<u16, AbsInfo>,
+    reader: R,
+    /// Bytes of an event that hasn't been fully read yet.
+    partial: Vec<u8>,
+    /// Events since the last `SYN_REPORT`, which are applied together.
+    packet: Vec<EvdevEvent>,
+    /// Set after `SYN_DROPPED`, until the next `SYN_REPORT`.
+    dropped: bool,
+    /// Set once the stream has ended, so the device is disconnected on the next poll.
+    ended: bool,
+}
+
+impl<R: Read> EvdevDevice<R> {
+    /// Reads every event that's available, returning `false` once the device is gone.
+    /// The end of a stream only counts as gone on the next read, so its last events get an update.
+    fn read(&mut self, state: &mut InputState) -> bool {
+        if self.ended {
+            return false;
+        }
+
+        let mut chunk = [0u8; EVENT_SIZE * 64];
+
+        loop {
+            let read = match self.reader.read(&mut chunk) {
+                Ok(0) => {
+                    self.ended = true;
+                    return true;
+                }
+                Ok(read) => read,
+                Err(error) if error.kind() == ErrorKind::WouldBlock => return true,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                // Reads fail with ENODEV once the device is unplugged.
+                Err(_) => return false,
+            };
+
+            let mut bytes = std::mem::take(&mut self.partial);
+            bytes.extend_from_slice(&chunk[..read]);
+
+            let mut events = bytes.chunks_exact(EVENT_SIZE);
+
+            for event in &mut events {
+                self.push(state, EvdevEvent::parse(event));
+            }
+
+            self.partial = events.remainder().to_vec();
+        }
+    }
+
+    fn push(&mut self, state: &mut InputState, event: EvdevEvent) {
+        if event.kind != EV_SYN {
+            if !self.dropped {
+                self.packet.push(event);
+            }
+
+            return;
+        }
+
+        match event.code {
+            SYN_REPORT if self.dropped => self.dropped = false,
+
+            SYN_REPORT => {
+                for event in self.packet.drain(..) {
+                    apply(self.id, self.kind, &self.abs, state, event);
+                }
+            }
+
+            // The kernel's buffer overflowed, so the events until the next report are incomplete.
+            SYN_DROPPED => {
+                self.packet.clear();
+                self.dropped = true;
+
+                resync(self.id, self.kind, state);
+            }
+
+            _ => {}
+        }
+    }
+}
+
+/// Releases every button and re-centers every axis, since their releases may have been dropped.
+/// Anything that's really still held reads as released until the device reports it again,
+/// which is better than a button that's stuck down.
+fn resync(id: DeviceId, kind: EvdevKind, state: &mut InputState) {
+    let Some((_, _, buttons, axes)) = state.raw_devices().find(|(it, _, _, _)| *it == id) else {
+        return;
+    };
+
+    let held = buttons.iter_ones().collect::<Vec<_>>();
+
+    let moved = axes
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| **value != 0f32)
+        .map(|(axis, _)| axis)
+        .collect::<Vec<_>>();
+
+    for button in held {
+        let Ok(button) = u8::try_from(button) else {
+            continue;
+        };
+
+        match kind {
+            EvdevKind::Keyboard => {
+                if let Ok(key) = KeyboardButton::try_from_primitive(button) {
+                    state.set_keyboard_button(&id, key, false);
+                }
+            }
+
+            EvdevKind::Mouse => {
+                if let Ok(button) = MouseButton::try_from_primitive(button) {
+                    state.set_mouse_button(&id, button, false);
+                }
+            }
+
+            EvdevKind::Gamepad => {
+                if let Ok(button) = GamepadButton::try_from_primitive(button) {
+                    state.set_gamepad_button(&id, button, false);
+                }
+            }
+        }
+    }
+
+    // Mouse axes are movement rather than a position, so there's nothing to re-center.
+    if kind != EvdevKind::Gamepad {
+        return;
+    }
+
+    for axis in moved {
+        if let Ok(axis) = GamepadAxis::try_from_primitive(axis as u8) {
+            state.set_gamepad_axis(&id, axis, 0f32);
+        }
+    }
+}
+
+fn apply(
+    id: DeviceId,
+    kind: EvdevKind,
+    abs: &HashMap<u16, AbsInfo>,
+    state: &mut InputState,
+    event: EvdevEvent,
+) {
+    // A value of 2 is a key repeat, which doesn't change whether the key is held.
+    let pressed = event.value != 0;
+
+    match (kind, event.kind) {
+        (EvdevKind::Keyboard, EV_KEY) => {
+            if let Some(key) = keyboard_button(event.code) {
+                state.set_keyboard_button(&id, key, pressed);
+            }
+        }
+
+        (EvdevKind::Mouse, EV_KEY) => {
+            if let Some(button) = mouse_button(event.code) {
+                state.set_mouse_button(&id, button, pressed);
+            }
+        }
+
+        (EvdevKind::Mouse, EV_REL) => {
+            let axis = match event.code {
+                REL_X => MouseAxis::DeltaX,
+                REL_Y => MouseAxis::DeltaY,
+                REL_HWHEEL => MouseAxis::WheelX,
+                REL_WHEEL => MouseAxis::WheelY,
+                _ => return,
+            };
+
+            state.set_mouse_axis(&id, axis, event.value as f32);
+        }
+
+        (EvdevKind::Gamepad, EV_KEY) => {
+            if let Some(button) = gamepad_button(event.code) {
+                state.set_gamepad_button(&id, button, pressed);
+            }
+        }
+
+        (EvdevKind::Gamepad, EV_ABS) => {
+            match event.code {
+                ABS_HAT0X => {
+                    state.set_gamepad_button(&id, GamepadButton::DpadLeft, event.value < 0);
+                    state.set_gamepad_button(&id, GamepadButton::DpadRight, event.value > 0);
+                    return;
+                }
+
+                ABS_HAT0Y => {
+                    state.set_gamepad_button(&id, GamepadButton::DpadUp, event.value < 0);
+                    state.set_gamepad_button(&id, GamepadButton::DpadDown, event.value > 0);
+                    return;
+                }
+
+                _ => {}
+            }
+
+            let Some(axis) = gamepad_axis(event.code) else {
+                return;
+            };
+
+            let Some(info) = abs.get(&event.code) else {
+                return;
+            };
+
+            let value = match axis {
+                GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => info.trigger(event.value),
+                _ => info.stick(event.value),
+            };
+
+            state.set_gamepad_axis(&id, axis, value);
+        }
+
+        _ => {}
+    }
+}
+
+/// Feeds `InputState` from Linux evdev devices, such as `/dev/input/event*`.
+///
+/// The platform layer opens each device with `O_NONBLOCK`, works out its kind and axis ranges
+/// with `EVIOCGBIT` and `EVIOCGABS`, and connects it here. Any `Read` works, so recorded
+/// event streams can be played back too.
+pub struct EvdevBackend<R: Read> {
+    devices: Vec<EvdevDevice<R>>,
+}
+
+impl<R: Read> EvdevBackend<R> {
+    pub fn new() -> Self {
+        return Self { devices: vec![] };
+    }
+
+    /// Connects a new device to the state, such as when it's plugged in.
+    pub fn connect(
+        &mut self,
+        state: &mut InputState,
+        kind: EvdevKind,
+        abs: HashMap<u16, AbsInfo>,
+        reader: R,
+    ) -> DeviceId {
+        let id = match kind {
+            EvdevKind::Keyboard => state.connect_keyboard(),
+            EvdevKind::Mouse => state.connect_mouse(),
+            EvdevKind::Gamepad => state.connect_gamepad(),
+        };
+
+        self.devices.push(EvdevDevice {
+            id,
+            kind,
+            abs,
+            reader,
+            partial: vec![],
+            packet: vec![],
+            dropped: false,
+            ended: false,
+        });
+
+        return id;
+    }
+
+    /// Disconnects a device, such as when udev reports it was removed.
+    pub fn disconnect(&mut self, state: &mut InputState, device: &DeviceId) -> bool {
+        let Some(index) = self.devices.iter().position(|it| it.id == *device) else {
+            return false;
+        };
+
+        self.devices.remove(index);
+
+        return state.disconnect_device(device);
+    }
+
+    /// Reads every available event from each device. Called once per frame, before updating managers.
+    /// Devices that have been unplugged, or whose stream ended on an earlier poll, are disconnected and returned.
+    pub fn poll(&mut self, state: &mut InputState) -> Vec<DeviceId> {
+        let mut removed = vec![];
+
+        self.devices.retain_mut(|device| {
+            if device.read(state) {
+                return true;
+            }
+
+            state.disconnect_device(&device.id);
+            removed.push(device.id);
+
+            return false;
+        });
+
+        return removed;
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = (DeviceId, EvdevKind)> {
+        return self.devices.iter().map(|it| (it.id, it.kind));
+    }
+}
+
+impl<R: Read> Default for EvdevBackend<R> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+fn keyboard_button(code: u16) -> Option<KeyboardButton> {
+    use KeyboardButton::*;
+
+    return Some(match code {
+        1 => Escape,
+        2 => Number1,
+        3 => Number2,
+        4 => Number3,
+        5 => Number4,
+        6 => Number5,
+        7 => Number6,
+        8 => Number7,
+        9 => Number8,
+        10 => Number9,
+        11 => Number0,
+        12 => Minus,
+        13 => Plus,
+        14 => Backspace,
+        15 => Tab,
+        16 => Q,
+        17 => W,
+        18 => E,
+        19 => R,
+        20 => T,
+        21 => Y,
+        22 => U,
+        23 => I,
+        24 => O,
+        25 => P,
+        26 => LeftBracket,
+        27 => RightBracket,
+        28 => Enter,
+        29 => LeftControl,
+        30 => A,
+        31 => S,
+        32 => D,
+        33 => F,
+        34 => G,
+        35 => H,
+        36 => J,
+        37 => K,
+        38 => L,
+        39 => Semicolon,
+        40 => Quote,
+        41 => Grave,
+        42 => LeftShift,
+        43 => BackSlash,
+        44 => Z,
+        45 => X,
+        46 => C,
+        47 => V,
+        48 => B,
+        49 => N,
+        50 => M,
+        51 => Comma,
+        52 => Period,
+        53 => Slash,
+        54 => RightShift,
+        55 => NumpadMultiply,
+        56 => LeftAlt,
+        57 => Space,
+        58 => CapsLock,
+        59 => F1,
+        60 => F2,
+        61 => F3,
+        62 => F4,
+        63 => F5,
+        64 => F6,
+        65 => F7,
+        66 => F8,
+        67 => F9,
+        68 => F10,
+        69 => NumLock,
+        70 => ScrollLock,
+        71 => Numpad7,
+        72 => Numpad8,
+        73 => Numpad9,
+        74 => NumpadMinus,
+        75 => Numpad4,
+        76 => Numpad5,
+        77 => Numpad6,
+        78 => NumpadPlus,
+        79 => Numpad1,
+        80 => Numpad2,
+        81 => Numpad3,
+        82 => Numpad0,
+        83 => NumpadDecimal,
+        87 => F11,
+        88 => F12,
+        96 => NumpadEnter,
+        97 => RightControl,
+        98 => NumpadDivide,
+        99 => PrintScreen,
+        100 => RightAlt,
+        102 => Home,
+        103 => Up,
+        104 => PageUp,
+        105 => Left,
+        106 => Right,
+        107 => End,
+        108 => Down,
+        109 => PageDown,
+        110 => Insert,
+        111 => Delete,
+        119 => Pause,
+        121 => NumpadPeriod,
+        125 => LeftMeta,
+        126 => RightMeta,
+        127 => Menu,
+        142 => Sleep,
+        183 => F13,
+        184 => F14,
+        185 => F15,
+        186 => F16,
+        187 => F17,
+        188 => F18,
+        189 => F19,
+        190 => F20,
+        191 => F21,
+        192 => F22,
+        193 => F23,
+        194 => F24,
+        0x163 => Clear,
+        _ => return None,
+    });
+}
+
+fn mouse_button(code: u16) -> Option<MouseButton> {
+    return Some(match code {
+        0x110 => MouseButton::Left,
+        0x111 => MouseButton::Right,
+        0x112 => MouseButton::Middle,
+        0x113 => MouseButton::Side1,
+        0x114 => MouseButton::Side2,
+        _ => return None,
+    });
+}
+
+fn gamepad_button(code: u16) -> Option<GamepadButton> {
+    return Some(match code {
+        0x130 => GamepadButton::South,
+        0x131 => GamepadButton::East,
+        0x133 => GamepadButton::North,
+        0x134 => GamepadButton::West,
+        0x136 => GamepadButton::LeftShoulder,
+        0x137 => GamepadButton::RightShoulder,
+        0x13a => GamepadButton::Back,
+        0x13b => GamepadButton::Start,
+        0x13c => GamepadButton::Guide,
+        0x13d => GamepadButton::LeftStick,
+        0x13e => GamepadButton::RightStick,
+        0x220 => GamepadButton::DpadUp,
+        0x221 => GamepadButton::DpadDown,
+        0x222 => GamepadButton::DpadLeft,
+        0x223 => GamepadButton::DpadRight,
+        _ => return None,
+    });
+}
+
+fn gamepad_axis(code: u16) -> Option<GamepadAxis> {
+    return Some(match code {
+        0x00 => GamepadAxis::LeftX,
+        0x01 => GamepadAxis::LeftY,
+        0x02 | 0x0a => GamepadAxis::LeftTrigger,
+        0x03 => GamepadAxis::RightX,
+        0x04 => GamepadAxis::RightY,
+        0x05 | 0x09 => GamepadAxis::RightTrigger,
+        _ => return None,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::VecDeque, io, rc::Rc};
+
+    use super::*;
+
+    /// A device that can be fed bytes while it's connected, and unplugged.
+    #[derive(Clone, Default)]
+    struct Feed(Rc<RefCell<(VecDeque<u8>, bool)>>);
+
+    impl Feed {
+        fn push(&self, events: &[EvdevEvent]) {
+            for event in events {
+                self.0.borrow_mut().0.extend(event.to_bytes());
+            }
+        }
+
+        fn unplug(&self) {
+            self.0.borrow_mut().1 = true;
+        }
+    }
+
+    impl Read for Feed {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let (bytes, unplugged) = &mut *self.0.borrow_mut();
+
+            if bytes.is_empty() {
+                if *unplugged {
+                    return Err(io::Error::other("No such device"));
+                }
+
+                return Err(ErrorKind::WouldBlock.into());
+            }
+
+            return bytes.read(buf);
+        }
+    }
+
+    fn read(state: &InputState, device: DeviceId) -> (Vec<bool>, Vec<f32>) {
+        let (_, _, buttons, axes) = state
+            .raw_devices()
+            .find(|(it, _, _, _)| *it == device)
+            .unwrap();
+
+        return (buttons.iter().map(|it| *it).collect(), axes.to_vec());
+    }
+
+    #[test]
+    fn applies_events_on_report() {
+        let mut state = InputState::new();
+        let mut backend = EvdevBackend::new();
+
+        let feed = Feed::default();
+
+        let abs = HashMap::from([
+            (
+                0x00,
+                AbsInfo {
+                    minimum: -32768,
+                    maximum: 32767,
+                    flat: 128,
+                },
+            ),
+            (
+                0x02,
+                AbsInfo {
+                    minimum: 0,
+                    maximum: 255,
+                    flat: 0,
+                },
+            ),
+        ]);
+
+        let gamepad = backend.connect(&mut state, EvdevKind::Gamepad, abs, feed.clone());
+
+        feed.push(&[
+            EvdevEvent::new(EV_KEY, 0x130, 1),
+            EvdevEvent::new(EV_ABS, 0x00, -32768),
+            EvdevEvent::new(EV_ABS, 0x02, 255),
+            EvdevEvent::new(EV_ABS, ABS_HAT0Y, -1),
+        ]);
+
+        // Nothing is applied until the report, and half an event waits for the rest.
+        let report = EvdevEvent::new(EV_SYN, SYN_REPORT, 0).to_bytes();
+        feed.0.borrow_mut().0.extend(&report[..4]);
+        backend.poll(&mut state);
+
+        assert!(!read(&state, gamepad).0[GamepadButton::South as usize]);
+
+        feed.0.borrow_mut().0.extend(&report[4..]);
+        backend.poll(&mut state);
+
+        let (buttons, axes) = read(&state, gamepad);
+
+        assert!(buttons[GamepadButton::South as usize]);
+        assert!(buttons[GamepadButton::DpadUp as usize]);
+        assert_eq!(axes[GamepadAxis::LeftX as usize], -1f32);
+        assert_eq!(axes[GamepadAxis::LeftTrigger as usize], 1f32);
+
+        // Events before a dropped report are thrown away, up to the next report,
+        // and everything is released in case a release was among them.
+        feed.push(&[
+            EvdevEvent::new(EV_KEY, 0x130, 0),
+            EvdevEvent::new(EV_SYN, SYN_DROPPED, 0),
+            EvdevEvent::new(EV_ABS, 0x00, 0),
+            EvdevEvent::new(EV_SYN, SYN_REPORT, 0),
+            EvdevEvent::new(EV_ABS, 0x00, 100),
+            EvdevEvent::new(EV_SYN, SYN_REPORT, 0),
+        ]);
+        backend.poll(&mut state);
+
+        let (buttons, axes) = read(&state, gamepad);
+
+        assert!(!buttons[GamepadButton::South as usize]);
+        assert!(!buttons[GamepadButton::DpadUp as usize]);
+        assert_eq!(axes[GamepadAxis::LeftX as usize], 0f32);
+        assert_eq!(axes[GamepadAxis::LeftTrigger as usize], 0f32);
+
+        // Anything pressed again after the drop is applied as usual.
+        feed.push(&[
+            EvdevEvent::new(EV_KEY, 0x130, 1),
+            EvdevEvent::new(EV_SYN, SYN_REPORT, 0),
+        ]);
+        backend.poll(&mut state);
+
+        assert!(read(&state, gamepad).0[GamepadButton::South as usize]);
+    }
+
+    #[test]
+    fn recorded_stream_ends_on_the_next_poll() {
+        let mut state = InputState::new();
+        let mut backend = EvdevBackend::new();
+
+        let bytes = [
+            EvdevEvent::new(EV_KEY, 0x130, 1),
+            EvdevEvent::new(EV_SYN, SYN_REPORT, 0),
+        ]
+        .iter()
+        .flat_map(EvdevEvent::to_bytes)
+        .collect::<Vec<_>>();
+
+        let gamepad = backend.connect(
+            &mut state,
+            EvdevKind::Gamepad,
+            HashMap::new(),
+            io::Cursor::new(bytes),
+        );
+
+        assert!(backend.poll(&mut state).is_empty());
+        assert!(read(&state, gamepad).0[GamepadButton::South as usize]);
+
+        assert_eq!(backend.poll(&mut state), [gamepad]);
+        assert_eq!(backend.devices().count(), 0);
+    }
+
+    #[test]
+    fn hotplug() {
+        let mut state = InputState::new();
+        let mut backend = EvdevBackend::new();
+
+        let keyboard_feed = Feed::default();
+        let mouse_feed = Feed::default();
+
+        let keyboard = backend.connect(
+            &mut state,
+            EvdevKind::Keyboard,
+            HashMap::new(),
+            keyboard_feed.clone(),
+        );
+        let mouse = backend.connect(
+            &mut state,
+            EvdevKind::Mouse,
+            HashMap::new(),
+            mouse_feed.clone(),
+        );
+
+        keyboard_feed.push(&[
+            EvdevEvent::new(EV_KEY, 17, 1),
+            EvdevEvent::new(EV_SYN, SYN_REPORT, 0),
+        ]);
+        mouse_feed.push(&[
+            EvdevEvent::new(EV_REL, REL_X, 3),
+            EvdevEvent::new(EV_REL, REL_X, 4),
+            EvdevEvent::new(EV_KEY, 0x110, 1),
+            EvdevEvent::new(EV_SYN, SYN_REPORT, 0),
+        ]);
+        mouse_feed.unplug();
+
+        assert_eq!(backend.poll(&mut state), [mouse]);
+
+        assert!(read(&state, keyboard).0[KeyboardButton::W as usize]);
+        assert_eq!(state.device_family(&mouse), None);
+        assert_eq!(
+            backend.devices().collect::<Vec<_>>(),
+            [(keyboard, EvdevKind::Keyboard)]
+        );
+
+        assert!(backend.disconnect(&mut state, &keyboard));
+        assert_eq!(state.device_family(&keyboard), None);
+    }
+}
@@ -0,0 +1,654 @@
+use std::{convert::identity, fmt::Debug};
+
+use num_enum::TryFromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    binding::{
+        AnalogBindingKind, BindingList, BoundAxis, BoundButton, DigitalBinding, VectorBindingKind,
+        axis::{GamepadAxis, KeyboardAxis, MouseAxis, TouchAxis, VirtualAxis},
+        button::{GamepadButton, KeyboardButton, MouseButton, TouchButton, VirtualButton},
+    },
+    layout::KeyboardLayout,
+    state::InputFamily,
+};
+
+/// Which labels a gamepad's buttons have, set per device.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, TryFromPrimitive)]
+pub enum ControllerStyle {
+    #[default]
+    Generic,
+    Xbox,
+    PlayStation,
+    Nintendo,
+}
+
+/// How to show a button or axis to the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct InputGlyph {
+    /// A stable identifier, such as `xbox_a` or `key_space`, for looking up an icon or a translated name.
+    pub id: &'static str,
+    /// The English name, for when there's no icon or translation.
+    pub name: &'static str,
+}
+
+pub trait Glyph: Copy {
+    fn glyph(self, style: ControllerStyle) -> InputGlyph;
+
+    /// A single glyph for a pair of axes shown together, such as both axes of a stick.
+    fn pair_glyph(_x: Self, _y: Self, _style: ControllerStyle) -> Option<InputGlyph> {
+        return None;
+    }
+}
+
+const fn glyph((id, name): (&'static str, &'static str)) -> InputGlyph {
+    return InputGlyph { id, name };
+}
+
+impl Glyph for KeyboardButton {
+    fn glyph(self, _style: ControllerStyle) -> InputGlyph {
+        return glyph(match self {
+            Self::A => ("key_a", "A"),
+            Self::B => ("key_b", "B"),
+            Self::C => ("key_c", "C"),
+            Self::D => ("key_d", "D"),
+            Self::E => ("key_e", "E"),
+            Self::F => ("key_f", "F"),
+            Self::G => ("key_g", "G"),
+            Self::H => ("key_h", "H"),
+            Self::I => ("key_i", "I"),
+            Self::J => ("key_j", "J"),
+            Self::K => ("key_k", "K"),
+            Self::L => ("key_l", "L"),
+            Self::M => ("key_m", "M"),
+            Self::N => ("key_n", "N"),
+            Self::O => ("key_o", "O"),
+            Self::P => ("key_p", "P"),
+            Self::Q => ("key_q", "Q"),
+            Self::R => ("key_r", "R"),
+            Self::S => ("key_s", "S"),
+            Self::T => ("key_t", "T"),
+            Self::U => ("key_u", "U"),
+            Self::V => ("key_v", "V"),
+            Self::W => ("key_w", "W"),
+            Self::X => ("key_x", "X"),
+            Self::Y => ("key_y", "Y"),
+            Self::Z => ("key_z", "Z"),
+            Self::Number0 => ("key_0", "0"),
+            Self::Number1 => ("key_1", "1"),
+            Self::Number2 => ("key_2", "2"),
+            Self::Number3 => ("key_3", "3"),
+            Self::Number4 => ("key_4", "4"),
+            Self::Number5 => ("key_5", "5"),
+            Self::Number6 => ("key_6", "6"),
+            Self::Number7 => ("key_7", "7"),
+            Self::Number8 => ("key_8", "8"),
+            Self::Number9 => ("key_9", "9"),
+            Self::Up => ("key_up", "Up Arrow"),
+            Self::Down => ("key_down", "Down Arrow"),
+            Self::Left => ("key_left", "Left Arrow"),
+            Self::Right => ("key_right", "Right Arrow"),
+            Self::Tilde => ("key_tilde", "~"),
+            Self::Grave => ("key_grave", "`"),
+            Self::Minus => ("key_minus", "-"),
+            Self::Plus => ("key_plus", "="),
+            Self::LeftBracket => ("key_left_bracket", "["),
+            Self::RightBracket => ("key_right_bracket", "]"),
+            Self::Semicolon => ("key_semicolon", ";"),
+            Self::Quote => ("key_quote", "'"),
+            Self::Comma => ("key_comma", ","),
+            Self::Period => ("key_period", "."),
+            Self::Slash => ("key_slash", "/"),
+            Self::BackSlash => ("key_backslash", "\\"),
+            Self::LeftShift => ("key_left_shift", "Left Shift"),
+            Self::RightShift => ("key_right_shift", "Right Shift"),
+            Self::LeftControl => ("key_left_control", "Left Control"),
+            Self::RightControl => ("key_right_control", "Right Control"),
+            Self::LeftAlt => ("key_left_alt", "Left Alt"),
+            Self::RightAlt => ("key_right_alt", "Right Alt"),
+            Self::LeftMeta => ("key_left_meta", "Left Meta"),
+            Self::RightMeta => ("key_right_meta", "Right Meta"),
+            Self::Menu => ("key_menu", "Menu"),
+            Self::Enter => ("key_enter", "Enter"),
+            Self::Escape => ("key_escape", "Escape"),
+            Self::Space => ("key_space", "Space"),
+            Self::Tab => ("key_tab", "Tab"),
+            Self::Backspace => ("key_backspace", "Backspace"),
+            Self::Insert => ("key_insert", "Insert"),
+            Self::Delete => ("key_delete", "Delete"),
+            Self::PageUp => ("key_page_up", "Page Up"),
+            Self::PageDown => ("key_page_down", "Page Down"),
+            Self::Home => ("key_home", "Home"),
+            Self::End => ("key_end", "End"),
+            Self::CapsLock => ("key_caps_lock", "Caps Lock"),
+            Self::ScrollLock => ("key_scroll_lock", "Scroll Lock"),
+            Self::PrintScreen => ("key_print_screen", "Print Screen"),
+            Self::Pause => ("key_pause", "Pause"),
+            Self::NumLock => ("key_num_lock", "Num Lock"),
+            Self::Clear => ("key_clear", "Clear"),
+            Self::Sleep => ("key_sleep", "Sleep"),
+            Self::Numpad0 => ("key_numpad_0", "Numpad 0"),
+            Self::Numpad1 => ("key_numpad_1", "Numpad 1"),
+            Self::Numpad2 => ("key_numpad_2", "Numpad 2"),
+            Self::Numpad3 => ("key_numpad_3", "Numpad 3"),
+            Self::Numpad4 => ("key_numpad_4", "Numpad 4"),
+            Self::Numpad5 => ("key_numpad_5", "Numpad 5"),
+            Self::Numpad6 => ("key_numpad_6", "Numpad 6"),
+            Self::Numpad7 => ("key_numpad_7", "Numpad 7"),
+            Self::Numpad8 => ("key_numpad_8", "Numpad 8"),
+            Self::Numpad9 => ("key_numpad_9", "Numpad 9"),
+            Self::NumpadDivide => ("key_numpad_divide", "Numpad /"),
+            Self::NumpadMultiply => ("key_numpad_multiply", "Numpad *"),
+            Self::NumpadMinus => ("key_numpad_minus", "Numpad -"),
+            Self::NumpadPlus => ("key_numpad_plus", "Numpad +"),
+            Self::NumpadDecimal => ("key_numpad_decimal", "Numpad Decimal"),
+            Self::NumpadPeriod => ("key_numpad_period", "Numpad ."),
+            Self::NumpadEnter => ("key_numpad_enter", "Numpad Enter"),
+            Self::F1 => ("key_f1", "F1"),
+            Self::F2 => ("key_f2", "F2"),
+            Self::F3 => ("key_f3", "F3"),
+            Self::F4 => ("key_f4", "F4"),
+            Self::F5 => ("key_f5", "F5"),
+            Self::F6 => ("key_f6", "F6"),
+            Self::F7 => ("key_f7", "F7"),
+            Self::F8 => ("key_f8", "F8"),
+            Self::F9 => ("key_f9", "F9"),
+            Self::F10 => ("key_f10", "F10"),
+            Self::F11 => ("key_f11", "F11"),
+            Self::F12 => ("key_f12", "F12"),
+            Self::F13 => ("key_f13", "F13"),
+            Self::F14 => ("key_f14", "F14"),
+            Self::F15 => ("key_f15", "F15"),
+            Self::F16 => ("key_f16", "F16"),
+            Self::F17 => ("key_f17", "F17"),
+            Self::F18 => ("key_f18", "F18"),
+            Self::F19 => ("key_f19", "F19"),
+            Self::F20 => ("key_f20", "F20"),
+            Self::F21 => ("key_f21", "F21"),
+            Self::F22 => ("key_f22", "F22"),
+            Self::F23 => ("key_f23", "F23"),
+            Self::F24 => ("key_f24", "F24"),
+        });
+    }
+}
+
+impl Glyph for MouseButton {
+    fn glyph(self, _style: ControllerStyle) -> InputGlyph {
+        return glyph(match self {
+            Self::Left => ("mouse_left", "Left Click"),
+            Self::Right => ("mouse_right", "Right Click"),
+            Self::Middle => ("mouse_middle", "Middle Click"),
+            Self::Side1 => ("mouse_side_1", "Mouse 4"),
+            Self::Side2 => ("mouse_side_2", "Mouse 5"),
+        });
+    }
+}
+
+impl Glyph for GamepadButton {
+    fn glyph(self, style: ControllerStyle) -> InputGlyph {
+        use ControllerStyle::*;
+
+        return glyph(match (style, self) {
+            (Xbox, Self::South) => ("xbox_a", "A"),
+            (Xbox, Self::East) => ("xbox_b", "B"),
+            (Xbox, Self::West) => ("xbox_x", "X"),
+            (Xbox, Self::North) => ("xbox_y", "Y"),
+            (Xbox, Self::Back) => ("xbox_view", "View"),
+            (Xbox, Self::Guide) => ("xbox_guide", "Xbox"),
+            (Xbox, Self::Start) => ("xbox_menu", "Menu"),
+            (Xbox, Self::LeftStick) => ("xbox_ls", "LS"),
+            (Xbox, Self::RightStick) => ("xbox_rs", "RS"),
+            (Xbox, Self::LeftShoulder) => ("xbox_lb", "LB"),
+            (Xbox, Self::RightShoulder) => ("xbox_rb", "RB"),
+            (Xbox, Self::Misc1) => ("xbox_share", "Share"),
+
+            (PlayStation, Self::South) => ("ps_cross", "Cross"),
+            (PlayStation, Self::East) => ("ps_circle", "Circle"),
+            (PlayStation, Self::West) => ("ps_square", "Square"),
+            (PlayStation, Self::North) => ("ps_triangle", "Triangle"),
+            (PlayStation, Self::Back) => ("ps_create", "Create"),
+            (PlayStation, Self::Guide) => ("ps_home", "PS"),
+            (PlayStation, Self::Start) => ("ps_options", "Options"),
+            (PlayStation, Self::LeftStick) => ("ps_l3", "L3"),
+            (PlayStation, Self::RightStick) => ("ps_r3", "R3"),
+            (PlayStation, Self::LeftShoulder) => ("ps_l1", "L1"),
+            (PlayStation, Self::RightShoulder) => ("ps_r1", "R1"),
+            (PlayStation, Self::Misc1) => ("ps_mute", "Mute"),
+            (PlayStation, Self::Touchpad) => ("ps_touchpad", "Touchpad"),
+
+            // Nintendo controllers swap the face buttons, so South is B.
+            (Nintendo, Self::South) => ("nintendo_b", "B"),
+            (Nintendo, Self::East) => ("nintendo_a", "A"),
+            (Nintendo, Self::West) => ("nintendo_y", "Y"),
+            (Nintendo, Self::North) => ("nintendo_x", "X"),
+            (Nintendo, Self::Back) => ("nintendo_minus", "−"),
+            (Nintendo, Self::Guide) => ("nintendo_home", "Home"),
+            (Nintendo, Self::Start) => ("nintendo_plus", "+"),
+            (Nintendo, Self::LeftStick) => ("nintendo_ls", "Left Stick"),
+            (Nintendo, Self::RightStick) => ("nintendo_rs", "Right Stick"),
+            (Nintendo, Self::LeftShoulder) => ("nintendo_l", "L"),
+            (Nintendo, Self::RightShoulder) => ("nintendo_r", "R"),
+            (Nintendo, Self::Misc1) => ("nintendo_capture", "Capture"),
+
+            (_, Self::South) => ("gamepad_south", "South"),
+            (_, Self::East) => ("gamepad_east", "East"),
+            (_, Self::West) => ("gamepad_west", "West"),
+            (_, Self::North) => ("gamepad_north", "North"),
+            (_, Self::Back) => ("gamepad_back", "Back"),
+            (_, Self::Guide) => ("gamepad_guide", "Guide"),
+            (_, Self::Start) => ("gamepad_start", "Start"),
+            (_, Self::LeftStick) => ("gamepad_left_stick", "Left Stick"),
+            (_, Self::RightStick) => ("gamepad_right_stick", "Right Stick"),
+            (_, Self::LeftShoulder) => ("gamepad_left_shoulder", "Left Shoulder"),
+            (_, Self::RightShoulder) => ("gamepad_right_shoulder", "Right Shoulder"),
+            (_, Self::DpadUp) => ("gamepad_dpad_up", "D-pad Up"),
+            (_, Self::DpadDown) => ("gamepad_dpad_down", "D-pad Down"),
+            (_, Self::DpadLeft) => ("gamepad_dpad_left", "D-pad Left"),
+            (_, Self::DpadRight) => ("gamepad_dpad_right", "D-pad Right"),
+            (_, Self::Misc1) => ("gamepad_misc_1", "Misc 1"),
+            (_, Self::RightPaddle1) => ("gamepad_paddle_1", "P1"),
+            (_, Self::LeftPaddle1) => ("gamepad_paddle_3", "P3"),
+            (_, Self::RightPaddle2) => ("gamepad_paddle_2", "P2"),
+            (_, Self::LeftPaddle2) => ("gamepad_paddle_4", "P4"),
+            (_, Self::Touchpad) => ("gamepad_touchpad", "Touchpad"),
+            (_, Self::Misc2) => ("gamepad_misc_2", "Misc 2"),
+            (_, Self::Misc3) => ("gamepad_misc_3", "Misc 3"),
+            (_, Self::Misc4) => ("gamepad_misc_4", "Misc 4"),
+            (_, Self::Misc5) => ("gamepad_misc_5", "Misc 5"),
+            (_, Self::Misc6) => ("gamepad_misc_6", "Misc 6"),
+        });
+    }
+}
+
+impl Glyph for TouchButton {
+    fn glyph(self, _style: ControllerStyle) -> InputGlyph {
+        return glyph(match self {
+            Self::Touch => ("touch", "Touch"),
+            Self::MultiTouch => ("touch_multi", "Two Finger Touch"),
+            Self::Tap => ("touch_tap", "Tap"),
+            Self::SwipeLeft => ("touch_swipe_left", "Swipe Left"),
+            Self::SwipeRight => ("touch_swipe_right", "Swipe Right"),
+            Self::SwipeUp => ("touch_swipe_up", "Swipe Up"),
+            Self::SwipeDown => ("touch_swipe_down", "Swipe Down"),
+        });
+    }
+}
+
+impl Glyph for KeyboardAxis {
+    fn glyph(self, _style: ControllerStyle) -> InputGlyph {
+        match self {}
+    }
+}
+
+impl Glyph for MouseAxis {
+    fn glyph(self, _style: ControllerStyle) -> InputGlyph {
+        return glyph(match self {
+            Self::DeltaX => ("mouse_move_x", "Mouse Left/Right"),
+            Self::DeltaY => ("mouse_move_y", "Mouse Up/Down"),
+            Self::WheelX => ("mouse_wheel_x", "Scroll Left/Right"),
+            Self::WheelY => ("mouse_wheel_y", "Scroll Wheel"),
+            Self::X | Self::NormalizedX => ("mouse_position_x", "Cursor X"),
+            Self::Y | Self::NormalizedY => ("mouse_position_y", "Cursor Y"),
+        });
+    }
+
+    fn pair_glyph(x: Self, y: Self, _style: ControllerStyle) -> Option<InputGlyph> {
+        return match (x, y) {
+            (Self::DeltaX, Self::DeltaY) => Some(glyph(("mouse_move", "Mouse"))),
+            (Self::WheelX, Self::WheelY) => Some(glyph(("mouse_wheel", "Scroll Wheel"))),
+            _ => None,
+        };
+    }
+}
+
+impl Glyph for GamepadAxis {
+    fn glyph(self, style: ControllerStyle) -> InputGlyph {
+        use ControllerStyle::*;
+
+        return glyph(match (style, self) {
+            (Xbox, Self::LeftTrigger) => ("xbox_lt", "LT"),
+            (Xbox, Self::RightTrigger) => ("xbox_rt", "RT"),
+            (PlayStation, Self::LeftTrigger) => ("ps_l2", "L2"),
+            (PlayStation, Self::RightTrigger) => ("ps_r2", "R2"),
+            (Nintendo, Self::LeftTrigger) => ("nintendo_zl", "ZL"),
+            (Nintendo, Self::RightTrigger) => ("nintendo_zr", "ZR"),
+
+            (_, Self::LeftX) => ("gamepad_left_stick_x", "Left Stick Left/Right"),
+            (_, Self::LeftY) => ("gamepad_left_stick_y", "Left Stick Up/Down"),
+            (_, Self::RightX) => ("gamepad_right_stick_x", "Right Stick Left/Right"),
+            (_, Self::RightY) => ("gamepad_right_stick_y", "Right Stick Up/Down"),
+            (_, Self::LeftTrigger) => ("gamepad_left_trigger", "Left Trigger"),
+            (_, Self::RightTrigger) => ("gamepad_right_trigger", "Right Trigger"),
+            (_, Self::GyroX) => ("gamepad_gyro_pitch", "Tilt Up/Down"),
+            (_, Self::GyroY) => ("gamepad_gyro_yaw", "Turn Left/Right"),
+            (_, Self::GyroZ) => ("gamepad_gyro_roll", "Roll"),
+            (_, Self::AccelX) => ("gamepad_accel_x", "Accelerometer X"),
+            (_, Self::AccelY) => ("gamepad_accel_y", "Accelerometer Y"),
+            (_, Self::AccelZ) => ("gamepad_accel_z", "Accelerometer Z"),
+        });
+    }
+
+    fn pair_glyph(x: Self, y: Self, _style: ControllerStyle) -> Option<InputGlyph> {
+        return match (x, y) {
+            (Self::LeftX, Self::LeftY) => Some(glyph(("gamepad_left_stick", "Left Stick"))),
+            (Self::RightX, Self::RightY) => Some(glyph(("gamepad_right_stick", "Right Stick"))),
+            (Self::GyroY, Self::GyroX) => Some(glyph(("gamepad_gyro", "Gyro"))),
+            _ => None,
+        };
+    }
+}
+
+impl Glyph for TouchAxis {
+    fn glyph(self, _style: ControllerStyle) -> InputGlyph {
+        return glyph(match self {
+            Self::X => ("touch_x", "Touch X"),
+            Self::Y => ("touch_y", "Touch Y"),
+            Self::Pressure => ("touch_pressure", "Touch Pressure"),
+            Self::Pinch => ("touch_pinch", "Pinch"),
+            Self::PanX => ("touch_pan_x", "Pan Left/Right"),
+            Self::PanY => ("touch_pan_y", "Pan Up/Down"),
+        });
+    }
+
+    fn pair_glyph(x: Self, y: Self, _style: ControllerStyle) -> Option<InputGlyph> {
+        return match (x, y) {
+            (Self::X, Self::Y) => Some(glyph(("touch", "Touch"))),
+            (Self::PanX, Self::PanY) => Some(glyph(("touch_pan", "Pan"))),
+            _ => None,
+        };
+    }
+}
+
+/// Virtual devices are usually drawn by the game itself, so their inputs only get a generic glyph.
+impl Glyph for VirtualButton {
+    fn glyph(self, _style: ControllerStyle) -> InputGlyph {
+        return glyph(("virtual_button", "Button"));
+    }
+}
+
+impl Glyph for VirtualAxis {
+    fn glyph(self, _style: ControllerStyle) -> InputGlyph {
+        return glyph(("virtual_axis", "Axis"));
+    }
+}
+
+fn button_glyphs<TButton, TAxis>(
+    button: &BoundButton<TButton, TAxis>,
+    style: ControllerStyle,
+    printed: impl Fn(TButton) -> TButton,
+) -> Vec<InputGlyph>
+where
+    TButton: Debug + Copy + PartialEq + Serialize + Glyph,
+    TAxis: Debug + Copy + PartialEq + Serialize + Glyph,
+{
+    return match button {
+        BoundButton::Analog { axis, .. } => vec![axis.glyph(style)],
+        BoundButton::Digital(button) => vec![printed(*button).glyph(style)],
+        BoundButton::Chord { modifiers, button } => modifiers
+            .iter()
+            .chain([button])
+            .map(|it| printed(*it).glyph(style))
+            .collect(),
+    };
+}
+
+fn axis_glyphs<TButton, TAxis>(
+    axis: &BoundAxis<TButton, TAxis>,
+    style: ControllerStyle,
+    printed: impl Fn(TButton) -> TButton,
+    glyphs: &mut Vec<InputGlyph>,
+) where
+    TButton: Debug + Copy + PartialEq + Serialize + Glyph,
+    TAxis: Debug + Copy + PartialEq + Serialize + Glyph,
+{
+    match axis {
+        BoundAxis::Analog(axis) => glyphs.push(axis.glyph(style)),
+        BoundAxis::Digital(button) => glyphs.push(printed(*button).glyph(style)),
+        BoundAxis::MultiDigital { negative, positive } => {
+            glyphs.push(printed(*negative).glyph(style));
+            glyphs.push(printed(*positive).glyph(style));
+        }
+    }
+}
+
+fn vector_glyphs<TButton, TAxis>(
+    x: &BoundAxis<TButton, TAxis>,
+    y: &BoundAxis<TButton, TAxis>,
+    style: ControllerStyle,
+    printed: impl Fn(TButton) -> TButton + Copy,
+) -> Vec<InputGlyph>
+where
+    TButton: Debug + Copy + PartialEq + Serialize + Glyph,
+    TAxis: Debug + Copy + PartialEq + Serialize + Glyph,
+{
+    if let (BoundAxis::Analog(x), BoundAxis::Analog(y)) = (x, y)
+        && let Some(glyph) = TAxis::pair_glyph(*x, *y, style)
+    {
+        return vec![glyph];
+    }
+
+    let mut glyphs = vec![];
+    axis_glyphs(x, style, printed, &mut glyphs);
+    axis_glyphs(y, style, printed, &mut glyphs);
+
+    return glyphs;
+}
+
+impl DigitalBinding {
+    /// The glyphs to show for this binding, or `None` if it's for a different device family.
+    /// Physical keyboard bindings show the key printed at their position in `layout`.
+    pub fn glyphs(
+        &self,
+        family: InputFamily,
+        style: ControllerStyle,
+        layout: &KeyboardLayout,
+    ) -> Option<Vec<InputGlyph>> {
+        return match self {
+            Self::Keyboard(button) if family == InputFamily::Keyboard => {
+                Some(button_glyphs(button, style, |it| layout.logical(it)))
+            }
+            Self::LogicalKeyboard(button) if family == InputFamily::Keyboard => {
+                Some(button_glyphs(button, style, identity))
+            }
+            Self::Mouse(button) if family == InputFamily::Mouse => {
+                Some(button_glyphs(button, style, identity))
+            }
+            Self::Gamepad(button) if family == InputFamily::Gamepad => {
+                Some(button_glyphs(button, style, identity))
+            }
+            Self::Touch(button) if family == InputFamily::Touch => {
+                Some(button_glyphs(button, style, identity))
+            }
+            Self::Virtual(button) if family == InputFamily::Virtual => {
+                Some(button_glyphs(button, style, identity))
+            }
+            _ => None,
+        };
+    }
+}
+
+impl AnalogBindingKind {
+    /// The glyphs to show for this binding, or `None` if it's for a different device family.
+    /// Physical keyboard bindings show the keys printed at their positions in `layout`.
+    pub fn glyphs(
+        &self,
+        family: InputFamily,
+        style: ControllerStyle,
+        layout: &KeyboardLayout,
+    ) -> Option<Vec<InputGlyph>> {
+        let mut glyphs = vec![];
+
+        match self {
+            Self::Keyboard(axis) if family == InputFamily::Keyboard => {
+                axis_glyphs(axis, style, |it| layout.logical(it), &mut glyphs)
+            }
+            Self::LogicalKeyboard(axis) if family == InputFamily::Keyboard => {
+                axis_glyphs(axis, style, identity, &mut glyphs)
+            }
+            Self::Mouse(axis) if family == InputFamily::Mouse => {
+                axis_glyphs(axis, style, identity, &mut glyphs)
+            }
+            Self::Gamepad(axis) if family == InputFamily::Gamepad => {
+                axis_glyphs(axis, style, identity, &mut glyphs)
+            }
+            Self::Touch(axis) if family == InputFamily::Touch => {
+                axis_glyphs(axis, style, identity, &mut glyphs)
+            }
+            Self::Virtual(axis) if family == InputFamily::Virtual => {
+                axis_glyphs(axis, style, identity, &mut glyphs)
+            }
+            _ => return None,
+        }
+
+        return Some(glyphs);
+    }
+}
+
+impl VectorBindingKind {
+    /// The glyphs to show for this binding, or `None` if it's for a different device family.
+    /// Both axes of a stick are shown as the stick itself.
+    /// Physical keyboard bindings show the keys printed at their positions in `layout`.
+    pub fn glyphs(
+        &self,
+        family: InputFamily,
+        style: ControllerStyle,
+        layout: &KeyboardLayout,
+    ) -> Option<Vec<InputGlyph>> {
+        return match self {
+            Self::Keyboard { x, y } if family == InputFamily::Keyboard => {
+                Some(vector_glyphs(x, y, style, |it| layout.logical(it)))
+            }
+            Self::LogicalKeyboard { x, y } if family == InputFamily::Keyboard => {
+                Some(vector_glyphs(x, y, style, identity))
+            }
+            Self::Mouse { x, y } if family == InputFamily::Mouse => {
+                Some(vector_glyphs(x, y, style, identity))
+            }
+            Self::Gamepad { x, y } if family == InputFamily::Gamepad => {
+                Some(vector_glyphs(x, y, style, identity))
+            }
+            Self::Touch { x, y } if family == InputFamily::Touch => {
+                Some(vector_glyphs(x, y, style, identity))
+            }
+            Self::Virtual { x, y } if family == InputFamily::Virtual => {
+                Some(vector_glyphs(x, y, style, identity))
+            }
+            _ => None,
+        };
+    }
+}
+
+impl BindingList {
+    /// The glyphs of each binding for the given device family, in order.
+    /// A binding with several glyphs, such as a chord or WASD, has them in the order they're shown.
+    pub fn glyphs(
+        &self,
+        family: InputFamily,
+        style: ControllerStyle,
+        layout: &KeyboardLayout,
+    ) -> Vec<Vec<InputGlyph>> {
+        return match self {
+            Self::Digital(bindings) => bindings
+                .iter()
+                .filter_map(|it| it.glyphs(family, style, layout))
+                .collect(),
+            Self::Analog(bindings) => bindings
+                .iter()
+                .filter_map(|it| it.axis.glyphs(family, style, layout))
+                .collect(),
+            Self::Vector(bindings) => bindings
+                .iter()
+                .filter_map(|it| it.axes.glyphs(family, style, layout))
+                .collect(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{VectorBinding, map::BindingMap},
+        manager::InputManager,
+        state::InputState,
+    };
+    use pawkit_interner::InternString;
+
+    fn ids(glyphs: Vec<Vec<InputGlyph>>) -> Vec<Vec<&'static str>> {
+        return glyphs
+            .into_iter()
+            .map(|it| it.into_iter().map(|it| it.id).collect())
+            .collect();
+    }
+
+    #[test]
+    fn gamepad_labels_follow_style() {
+        assert_eq!(GamepadButton::South.glyph(ControllerStyle::Xbox).name, "A");
+        assert_eq!(
+            GamepadButton::South
+                .glyph(ControllerStyle::PlayStation)
+                .name,
+            "Cross"
+        );
+        assert_eq!(
+            GamepadButton::South.glyph(ControllerStyle::Nintendo).name,
+            "B"
+        );
+        assert_eq!(
+            GamepadButton::DpadUp.glyph(ControllerStyle::Xbox).id,
+            "gamepad_dpad_up"
+        );
+    }
+
+    #[test]
+    fn action_glyphs_use_last_device() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("jump"),
+            BindingList::Digital(vec![
+                DigitalBinding::Keyboard(BoundButton::Digital(KeyboardButton::Space)),
+                DigitalBinding::Gamepad(BoundButton::Digital(GamepadButton::South)),
+            ]),
+        );
+        bindings.register_binding(
+            InternString::new("move"),
+            BindingList::Vector(vec![VectorBinding {
+                axes: VectorBindingKind::Gamepad {
+                    x: BoundAxis::Analog(GamepadAxis::LeftX),
+                    y: BoundAxis::Analog(GamepadAxis::LeftY),
+                },
+                deadzone: 0.1,
+                scale: (1.0, 1.0),
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+                ratchet: None,
+            }]),
+        );
+
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+        let gamepad = state.connect_gamepad();
+
+        state.set_controller_style(&gamepad, ControllerStyle::PlayStation);
+
+        let mut manager = InputManager::new(bindings);
+        manager.connect_device(keyboard);
+        manager.connect_device(gamepad);
+
+        let jump = InternString::new("jump");
+
+        assert_eq!(ids(manager.action_glyphs(&state, &jump)), [["key_space"]]);
+
+        state.set_gamepad_button(&gamepad, GamepadButton::South, true);
+        manager.update(&state, 0.1);
+
+        assert_eq!(ids(manager.action_glyphs(&state, &jump)), [["ps_cross"]]);
+        assert_eq!(
+            ids(manager.action_glyphs(&state, &InternString::new("move"))),
+            [["gamepad_left_stick"]]
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+
+/// How strongly each motor should be running, from 0 to 1.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct HapticOutput {
+    pub low_frequency: f32,
+    pub high_frequency: f32,
+    pub left_trigger: f32,
+    pub right_trigger: f32,
+}
+
+impl HapticOutput {
+    fn max(self, other: Self) -> Self {
+        return Self {
+            low_frequency: self.low_frequency.max(other.low_frequency),
+            high_frequency: self.high_frequency.max(other.high_frequency),
+            left_trigger: self.left_trigger.max(other.left_trigger),
+            right_trigger: self.right_trigger.max(other.right_trigger),
+        };
+    }
+}
+
+/// Fades an effect in over `attack`, keeps it at full strength for `hold`, then fades it out over `release`.
+/// All times are in seconds.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HapticEnvelope {
+    #[serde(default)]
+    pub attack: f32,
+    pub hold: f32,
+    #[serde(default)]
+    pub release: f32,
+}
+
+impl HapticEnvelope {
+    /// Full strength for the whole duration, without fading.
+    pub fn constant(duration: f32) -> Self {
+        return Self {
+            attack: 0f32,
+            hold: duration,
+            release: 0f32,
+        };
+    }
+
+    pub fn duration(&self) -> f32 {
+        return self.attack + self.hold + self.release;
+    }
+
+    pub fn amplitude(&self, time: f32) -> f32 {
+        if time < 0f32 {
+            return 0f32;
+        }
+
+        if time < self.attack {
+            return time / self.attack;
+        }
+
+        let time = time - self.attack;
+
+        if time < self.hold {
+            return 1f32;
+        }
+
+        let time = time - self.hold;
+
+        if time < self.release {
+            return 1f32 - time / self.release;
+        }
+
+        return 0f32;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum HapticEffect {
+    /// Dual-motor rumble.
+    Rumble {
+        low_frequency: f32,
+        high_frequency: f32,
+        envelope: HapticEnvelope,
+    },
+    /// Rumble in the triggers, on controllers that have trigger motors.
+    TriggerRumble {
+        left: f32,
+        right: f32,
+        envelope: HapticEnvelope,
+    },
+    /// A timed sequence of effects. Overlapping steps use the strongest value for each motor.
+    Pattern { steps: Vec<HapticStep> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HapticStep {
+    /// Seconds from the start of the pattern.
+    pub start: f32,
+    pub effect: HapticEffect,
+}
+
+impl HapticEffect {
+    pub fn load(s: &str) -> serde_json::Result<Self> {
+        return serde_json::from_str(s);
+    }
+
+    pub fn duration(&self) -> f32 {
+        return match self {
+            Self::Rumble { envelope, .. } | Self::TriggerRumble { envelope, .. } => {
+                envelope.duration()
+            }
+            Self::Pattern { steps } => steps
+                .iter()
+                .map(|it| it.start + it.effect.duration())
+                .fold(0f32, f32::max),
+        };
+    }
+
+    /// The motor strengths at the given number of seconds into the effect.
+    pub fn sample(&self, time: f32) -> HapticOutput {
+        match self {
+            Self::Rumble {
+                low_frequency,
+                high_frequency,
+                envelope,
+            } => {
+                let amplitude = envelope.amplitude(time);
+
+                return HapticOutput {
+                    low_frequency: low_frequency * amplitude,
+                    high_frequency: high_frequency * amplitude,
+                    ..Default::default()
+                };
+            }
+
+            Self::TriggerRumble {
+                left,
+                right,
+                envelope,
+            } => {
+                let amplitude = envelope.amplitude(time);
+
+                return HapticOutput {
+                    left_trigger: left * amplitude,
+                    right_trigger: right * amplitude,
+                    ..Default::default()
+                };
+            }
+
+            Self::Pattern { steps } => {
+                return steps
+                    .iter()
+                    .map(|it| it.effect.sample(time - it.start))
+                    .fold(HapticOutput::default(), HapticOutput::max);
+            }
+        }
+    }
+}
+
+/// The effects playing on a single device.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HapticQueue {
+    effects: Vec<(f32, HapticEffect)>,
+    /// Whether the motors were running as of the last drain, so they get turned off once.
+    active: bool,
+}
+
+impl HapticQueue {
+    pub(crate) fn push(&mut self, effect: HapticEffect) {
+        self.effects.push((0f32, effect));
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.effects.clear();
+    }
+
+    pub(crate) fn is_idle(&self) -> bool {
+        return self.effects.is_empty() && !self.active;
+    }
+
+    pub(crate) fn drain(&mut self, delta_time: f32) -> Option<HapticOutput> {
+        if self.effects.is_empty() {
+            if !self.active {
+                return None;
+            }
+
+            self.active = false;
+
+            return Some(HapticOutput::default());
+        }
+
+        let output = self
+            .effects
+            .iter()
+            .map(|(time, effect)| effect.sample(*time))
+            .fold(HapticOutput::default(), HapticOutput::max);
+
+        for (time, _) in &mut self.effects {
+            *time += delta_time;
+        }
+
+        self.effects
+            .retain(|(time, effect)| *time < effect.duration());
+
+        self.active = true;
+
+        return Some(output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{
+            BindingList, BoundButton, DigitalBinding, button::GamepadButton, map::BindingMap,
+        },
+        manager::InputManager,
+        state::InputState,
+    };
+    use pawkit_interner::InternString;
+
+    #[test]
+    fn envelope_shape() {
+        let envelope = HapticEnvelope {
+            attack: 0.1,
+            hold: 0.2,
+            release: 0.1,
+        };
+
+        assert_eq!(envelope.amplitude(0.05), 0.5);
+        assert_eq!(envelope.amplitude(0.2), 1.0);
+        assert!((envelope.amplitude(0.35) - 0.5).abs() < 1e-5);
+        assert_eq!(envelope.amplitude(0.5), 0.0);
+    }
+
+    #[test]
+    fn drain_turns_motors_off_once() {
+        let mut state = InputState::new();
+        let gamepad = state.connect_gamepad();
+
+        state.play_haptic(
+            &gamepad,
+            HapticEffect::Pattern {
+                steps: vec![
+                    HapticStep {
+                        start: 0.0,
+                        effect: HapticEffect::Rumble {
+                            low_frequency: 1.0,
+                            high_frequency: 0.0,
+                            envelope: HapticEnvelope::constant(0.1),
+                        },
+                    },
+                    HapticStep {
+                        start: 0.1,
+                        effect: HapticEffect::TriggerRumble {
+                            left: 0.0,
+                            right: 0.5,
+                            envelope: HapticEnvelope::constant(0.1),
+                        },
+                    },
+                ],
+            },
+        );
+
+        let first = state.drain_haptics(&gamepad, 0.1).unwrap();
+        assert_eq!(first.low_frequency, 1.0);
+        assert_eq!(first.right_trigger, 0.0);
+
+        let second = state.drain_haptics(&gamepad, 0.1).unwrap();
+        assert_eq!(second.low_frequency, 0.0);
+        assert_eq!(second.right_trigger, 0.5);
+
+        assert_eq!(
+            state.drain_haptics(&gamepad, 0.1),
+            Some(HapticOutput::default())
+        );
+        assert_eq!(state.drain_haptics(&gamepad, 0.1), None);
+    }
+
+    #[test]
+    fn manager_follows_the_active_device() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("shoot"),
+            BindingList::Digital(vec![DigitalBinding::Gamepad(BoundButton::Digital(
+                GamepadButton::RightShoulder,
+            ))]),
+        );
+
+        let mut state = InputState::new();
+        let first = state.connect_gamepad();
+        let second = state.connect_gamepad();
+        let keyboard = state.connect_keyboard();
+
+        let mut manager = InputManager::new(bindings);
+        manager.connect_device(first);
+        manager.connect_device(second);
+        manager.connect_device(keyboard);
+
+        let effect = HapticEffect::Rumble {
+            low_frequency: 0.5,
+            high_frequency: 0.5,
+            envelope: HapticEnvelope::constant(0.2),
+        };
+
+        let shoot = InternString::new("shoot");
+
+        assert!(!manager.play_haptic(&mut state, &shoot, effect.clone()));
+
+        state.set_gamepad_button(&second, GamepadButton::RightShoulder, true);
+        manager.update(&state, 0.1);
+
+        assert!(manager.play_haptic(&mut state, &shoot, effect));
+        assert_eq!(state.drain_haptics(&first, 0.1), None);
+        assert!(state.drain_haptics(&second, 0.1).is_some());
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::{HashMap, VecDeque};
+
+use pawkit_interner::InternString;
+use serde::{Deserialize, Serialize};
+
+use crate::{DeviceId, context::ContextState, length_squared, state::InputFamily};
+
+/// What an action read from the devices on one tick, before interactions and deltas are worked out.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ActionValue {
+    Digital(bool),
+    Analog(f32),
+    Vector([f32; 2]),
+}
+
+impl ActionValue {
+    fn same_kind(&self, other: &Self) -> bool {
+        return std::mem::discriminant(self) == std::mem::discriminant(other);
+    }
+
+    /// How far the value is from rest, with a pressed button counting as 1.
+    pub(crate) fn magnitude(&self) -> f32 {
+        match self {
+            Self::Digital(pressed) => return if *pressed { 1f32 } else { 0f32 },
+            Self::Analog(value) => return value.abs(),
+            Self::Vector(value) => return length_squared(*value).sqrt(),
+        }
+    }
+}
+
+/// The value of every action on one tick, by frame index.
+#[derive(Clone)]
+pub(crate) struct TickInputs {
+    pub(crate) base: Vec<ActionValue>,
+    pub(crate) contexts: HashMap<InternString, Vec<ActionValue>>,
+}
+
+impl TickInputs {
+    /// Replaces an action's value in the base bindings or the given context.
+    /// Returns false if there's no such action, or it's a different kind.
+    pub(crate) fn set(
+        &mut self,
+        context: Option<&InternString>,
+        index: usize,
+        value: ActionValue,
+    ) -> bool {
+        let values = match context {
+            Some(name) => self.contexts.get_mut(name),
+            None => Some(&mut self.base),
+        };
+
+        let Some(slot) = values.and_then(|it| it.get_mut(index)) else {
+            return false;
+        };
+
+        if !slot.same_kind(&value) {
+            return false;
+        }
+
+        *slot = value;
+
+        return true;
+    }
+
+    pub(crate) fn get(&self, context: Option<&InternString>, index: usize) -> Option<ActionValue> {
+        let values = match context {
+            Some(name) => self.contexts.get(name)?,
+            None => &self.base,
+        };
+
+        return values.get(index).copied();
+    }
+}
+
+/// The frames of every context at one point in time, and the device the player was using.
+#[derive(Clone)]
+pub(crate) struct ManagerState {
+    pub(crate) base: ContextState,
+    pub(crate) contexts: HashMap<InternString, ContextState>,
+    pub(crate) last_device: Option<DeviceId>,
+    pub(crate) last_family: Option<InputFamily>,
+}
+
+pub(crate) struct TickRecord {
+    pub(crate) inputs: TickInputs,
+    pub(crate) delta_time: f32,
+    /// The frames from just before this tick was applied, and which devices drove each action on it.
+    pub(crate) before: ManagerState,
+}
+
+/// The last few ticks of input, so they can be rolled back and replayed when a late input arrives.
+pub(crate) struct InputHistory {
+    length: usize,
+    records: VecDeque<TickRecord>,
+    /// The tick of the oldest record.
+    first: u64,
+}
+
+impl InputHistory {
+    pub(crate) fn new(length: usize, first: u64) -> Self {
+        return Self {
+            length,
+            records: VecDeque::with_capacity(length),
+            first,
+        };
+    }
+
+    pub(crate) fn set_length(&mut self, length: usize) {
+        self.length = length;
+        self.trim();
+    }
+
+    /// The tick after the newest record.
+    pub(crate) fn end(&self) -> u64 {
+        return self.first + self.records.len() as u64;
+    }
+
+    pub(crate) fn start(&self) -> u64 {
+        return self.first;
+    }
+
+    /// Drops every record, so the next one is for the given tick.
+    pub(crate) fn clear(&mut self, tick: u64) {
+        self.records.clear();
+        self.first = tick;
+    }
+
+    pub(crate) fn push(&mut self, record: TickRecord) {
+        self.records.push_back(record);
+        self.trim();
+    }
+
+    pub(crate) fn get(&self, tick: u64) -> Option<&TickRecord> {
+        let index = tick.checked_sub(self.first)?;
+
+        return self.records.get(index as usize);
+    }
+
+    pub(crate) fn get_mut(&mut self, tick: u64) -> Option<&mut TickRecord> {
+        let index = tick.checked_sub(self.first)?;
+
+        return self.records.get_mut(index as usize);
+    }
+
+    fn trim(&mut self) {
+        while self.records.len() > self.length {
+            self.records.pop_front();
+            self.first += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aggregation::DeviceAggregation,
+        binding::{
+            AnalogBinding, AnalogBindingKind, BindingList, BoundAxis, BoundButton, DigitalBinding,
+            VectorBinding, VectorBindingKind, axis::GamepadAxis, button::KeyboardButton,
+            map::BindingMap,
+        },
+        manager::{InputFrame, InputManager},
+        state::InputState,
+    };
+
+    fn bindings() -> BindingMap {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("jump"),
+            BindingList::Digital(vec![DigitalBinding::Keyboard(BoundButton::Digital(
+                KeyboardButton::Space,
+            ))]),
+        );
+
+        bindings.register_binding(
+            InternString::new("throttle"),
+            BindingList::Analog(vec![AnalogBinding {
+                axis: AnalogBindingKind::Keyboard(BoundAxis::Digital(KeyboardButton::W)),
+                deadzone: 0f32,
+                scale: 1f32,
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+            }]),
+        );
+
+        return bindings;
+    }
+
+    fn frame(manager: &InputManager, name: &str) -> InputFrame {
+        return manager.get_binding(&InternString::new(name)).unwrap();
+    }
+
+    #[test]
+    fn late_input_replays_from_its_tick() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(keyboard);
+        manager.set_history_length(8);
+
+        let jump = InternString::new("jump");
+        let throttle = InternString::new("throttle");
+
+        for _ in 0..4 {
+            manager.update(&state, 0.1);
+        }
+
+        assert_eq!(manager.tick(), 4);
+        assert_eq!(manager.history_range(), 0..4);
+        assert_eq!(
+            manager.tick_input(1, &jump),
+            Some(ActionValue::Digital(false))
+        );
+
+        // A remote player pressed jump and pushed the throttle on tick 1, but we only hear about it now.
+        assert!(manager.set_tick_input(1, &jump, ActionValue::Digital(true)));
+        assert!(manager.set_tick_input(1, &throttle, ActionValue::Analog(1f32)));
+        assert!(manager.set_tick_input(2, &throttle, ActionValue::Analog(1f32)));
+        assert!(!manager.set_tick_input(1, &jump, ActionValue::Analog(1f32)));
+        assert!(!manager.set_tick_input(9, &jump, ActionValue::Digital(true)));
+
+        assert!(manager.rewind(1));
+        assert_eq!(manager.tick(), 1);
+
+        assert!(manager.replay_tick());
+
+        let InputFrame::Digital(jumped) = frame(&manager, "jump") else {
+            panic!();
+        };
+        let InputFrame::Analog(pushed) = frame(&manager, "throttle") else {
+            panic!();
+        };
+
+        assert!(jumped.just_pressed);
+        assert_eq!(pushed.value, 1f32);
+        assert_eq!(pushed.delta, 1f32);
+
+        assert!(manager.replay_tick());
+
+        let InputFrame::Digital(jumped) = frame(&manager, "jump") else {
+            panic!();
+        };
+        let InputFrame::Analog(pushed) = frame(&manager, "throttle") else {
+            panic!();
+        };
+
+        assert!(jumped.just_released);
+        assert_eq!(pushed.delta, 0f32);
+
+        // Updating while behind catches up on the rest before applying the new tick.
+        manager.update(&state, 0.1);
+
+        assert_eq!(manager.tick(), 5);
+
+        let InputFrame::Analog(pushed) = frame(&manager, "throttle") else {
+            panic!();
+        };
+
+        assert_eq!(pushed.value, 0f32);
+        assert_eq!(pushed.delta, 0f32);
+
+        // The later ticks were saved again with the new inputs, so rolling back to them still works.
+        assert!(manager.rewind(3));
+        assert!(manager.replay_tick());
+
+        let InputFrame::Analog(pushed) = frame(&manager, "throttle") else {
+            panic!();
+        };
+
+        assert_eq!(pushed.delta, -1f32);
+    }
+
+    #[test]
+    fn history_keeps_the_newest_ticks() {
+        let state = InputState::new();
+
+        let mut manager = InputManager::new(bindings());
+        manager.set_history_length(3);
+
+        for _ in 0..5 {
+            manager.update(&state, 0.1);
+        }
+
+        assert_eq!(manager.history_range(), 2..5);
+        assert!(!manager.rewind(1));
+        assert!(manager.rewind(2));
+        assert!(manager.replay_tick());
+        assert_eq!(manager.tick(), 3);
+
+        manager.set_history_length(0);
+
+        assert_eq!(manager.tick(), 5);
+        assert_eq!(manager.history_range(), 5..5);
+    }
+
+    #[test]
+    fn rewind_restores_the_most_recent_device() {
+        let mut state = InputState::new();
+        let first = state.connect_gamepad();
+        let second = state.connect_gamepad();
+
+        let name = InternString::new("move");
+
+        let mut bindings = BindingMap::new();
+        bindings.register_binding(
+            name.clone(),
+            BindingList::Vector(vec![VectorBinding {
+                axes: VectorBindingKind::Gamepad {
+                    x: BoundAxis::Analog(GamepadAxis::LeftX),
+                    y: BoundAxis::Analog(GamepadAxis::LeftY),
+                },
+                deadzone: 0f32,
+                scale: (1f32, 1f32),
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+                ratchet: None,
+            }]),
+        );
+
+        let mut manager = InputManager::new(bindings);
+        manager.connect_device(first);
+        manager.connect_device(second);
+        manager.set_history_length(8);
+        manager
+            .set_aggregation(&name, DeviceAggregation::MostRecent)
+            .unwrap();
+
+        let moved = |manager: &InputManager| {
+            let Some(InputFrame::Vector(frame)) = manager.get_binding(&name) else {
+                panic!("move is not a vector binding");
+            };
+
+            return frame.value[0];
+        };
+
+        // The first gamepad pushes on tick 1 and the second on tick 2.
+        // The first lets go on tick 4 and pushes again on tick 5, so it's the most recent again.
+        let first_x = [0f32, 0.6, 0.6, 0.6, 0f32, 0.9];
+        let second_x = [0f32, 0f32, 0.8, 0.8, 0.8, 0.8];
+
+        for (first_x, second_x) in first_x.into_iter().zip(second_x) {
+            state.set_gamepad_axis(&first, GamepadAxis::LeftX, first_x);
+            state.set_gamepad_axis(&second, GamepadAxis::LeftX, second_x);
+            manager.update(&state, 0.1);
+        }
+
+        assert_eq!(moved(&manager), 0.9);
+        assert_eq!(manager.last_device(), Some(first));
+
+        // On tick 3 the second gamepad started pushing later, so it won.
+        assert!(manager.rewind(3));
+        assert_eq!(manager.last_device(), Some(second));
+
+        assert!(manager.replay_tick());
+        assert_eq!(moved(&manager), 0.8);
+        assert_eq!(manager.last_device(), Some(second));
+
+        while manager.replay_tick() {}
+
+        assert_eq!(moved(&manager), 0.9);
+        assert_eq!(manager.last_device(), Some(first));
+    }
+}
@@ -0,0 +1,289 @@
+use serde::{Deserialize, Serialize};
+
+use crate::manager::DigitalInputFrame;
+
+/// Time based interactions for a digital action. All durations are in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct DigitalInteractions {
+    /// Sets `held` while the action has been pressed for at least this long,
+    /// and `long_pressed` on the frame the duration is first reached.
+    pub hold: Option<f32>,
+    /// Sets `tapped` when the action is released within this duration.
+    pub tap: Option<f32>,
+    /// Sets `double_tapped` when the action is pressed twice within this window.
+    pub double_tap: Option<f32>,
+    /// Sets `repeated` on press, and then periodically while the action stays pressed.
+    pub repeat: Option<RepeatInteraction>,
+    /// Keeps `buffered` set after a press until the window passes,
+    /// or the press is consumed with `InputManager::consume`.
+    pub buffer: Option<InputBuffer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RepeatInteraction {
+    /// The time between the initial press and the first repeat.
+    pub delay: f32,
+    /// The number of repeats per second after the delay.
+    pub rate: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputBuffer {
+    Frames(u32),
+    Milliseconds(f32),
+}
+
+impl InputBuffer {
+    fn contains(self, frames: u32, time: f32) -> bool {
+        match self {
+            Self::Frames(window) => return frames <= window,
+            Self::Milliseconds(window) => return time * 1000f32 <= window,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct InteractionTimers {
+    duration: f32,
+    since_last_press: Option<f32>,
+    next_repeat: f32,
+    buffer_frames: u32,
+    buffer_time: f32,
+}
+
+impl DigitalInteractions {
+    pub(crate) fn update(
+        &self,
+        frame: &mut DigitalInputFrame,
+        timers: &mut InteractionTimers,
+        pressed: bool,
+        delta_time: f32,
+    ) {
+        let was_pressed = frame.pressed;
+        let previous_duration = timers.duration;
+
+        frame.pressed = pressed;
+        frame.just_pressed = !was_pressed && pressed;
+        frame.just_released = was_pressed && !pressed;
+
+        timers.duration = if frame.just_pressed {
+            0f32
+        } else if pressed {
+            previous_duration + delta_time
+        } else {
+            0f32
+        };
+
+        frame.held = false;
+        frame.long_pressed = false;
+
+        if let Some(hold) = self.hold {
+            frame.held = pressed && timers.duration >= hold;
+            frame.long_pressed = frame.held && !(was_pressed && previous_duration >= hold);
+        }
+
+        frame.tapped = false;
+
+        if let Some(tap) = self.tap {
+            frame.tapped = frame.just_released && previous_duration + delta_time <= tap;
+        }
+
+        frame.double_tapped = false;
+
+        if let Some(window) = self.double_tap {
+            let since_last_press = timers.since_last_press.map(|it| it + delta_time);
+
+            timers.since_last_press = since_last_press;
+
+            if frame.just_pressed {
+                if since_last_press.is_some_and(|it| it <= window) {
+                    frame.double_tapped = true;
+                    timers.since_last_press = None;
+                } else {
+                    timers.since_last_press = Some(0f32);
+                }
+            }
+        }
+
+        frame.repeated = false;
+
+        if let Some(repeat) = self.repeat {
+            if frame.just_pressed {
+                frame.repeated = true;
+                timers.next_repeat = repeat.delay;
+            } else if pressed {
+                timers.next_repeat -= delta_time;
+
+                if timers.next_repeat <= 0f32 {
+                    frame.repeated = true;
+
+                    if repeat.rate > 0f32 {
+                        let period = 1f32 / repeat.rate;
+
+                        timers.next_repeat += period;
+
+                        if timers.next_repeat <= 0f32 {
+                            timers.next_repeat = period;
+                        }
+                    } else {
+                        timers.next_repeat = f32::INFINITY;
+                    }
+                }
+            }
+        }
+
+        let Some(buffer) = self.buffer else {
+            frame.buffered = false;
+            return;
+        };
+
+        if frame.just_pressed {
+            frame.buffered = true;
+            timers.buffer_frames = 0;
+            timers.buffer_time = 0f32;
+        } else if frame.buffered {
+            timers.buffer_frames += 1;
+            timers.buffer_time += delta_time;
+            frame.buffered = buffer.contains(timers.buffer_frames, timers.buffer_time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Tester {
+        interactions: DigitalInteractions,
+        frame: DigitalInputFrame,
+        timers: InteractionTimers,
+    }
+
+    impl Tester {
+        fn new(interactions: DigitalInteractions) -> Self {
+            return Self {
+                interactions,
+                frame: DigitalInputFrame {
+                    pressed: false,
+                    just_pressed: false,
+                    just_released: false,
+                    held: false,
+                    long_pressed: false,
+                    tapped: false,
+                    double_tapped: false,
+                    repeated: false,
+                    buffered: false,
+                },
+                timers: InteractionTimers::default(),
+            };
+        }
+
+        fn step(&mut self, pressed: bool, delta_time: f32) -> DigitalInputFrame {
+            self.interactions
+                .update(&mut self.frame, &mut self.timers, pressed, delta_time);
+
+            return self.frame;
+        }
+    }
+
+    #[test]
+    fn hold_and_long_press() {
+        let mut tester = Tester::new(DigitalInteractions {
+            hold: Some(0.5),
+            ..Default::default()
+        });
+
+        assert!(!tester.step(true, 0.25).held);
+        assert!(!tester.step(true, 0.25).held);
+
+        let frame = tester.step(true, 0.25);
+        assert!(frame.held && frame.long_pressed);
+
+        let frame = tester.step(true, 0.25);
+        assert!(frame.held && !frame.long_pressed);
+
+        assert!(!tester.step(false, 0.25).held);
+    }
+
+    #[test]
+    fn tap_only_when_released_quickly() {
+        let mut tester = Tester::new(DigitalInteractions {
+            tap: Some(0.2),
+            ..Default::default()
+        });
+
+        tester.step(true, 0.1);
+        assert!(tester.step(false, 0.1).tapped);
+
+        tester.step(true, 0.1);
+        tester.step(true, 0.1);
+        tester.step(true, 0.1);
+        assert!(!tester.step(false, 0.1).tapped);
+    }
+
+    #[test]
+    fn double_tap_within_window() {
+        let mut tester = Tester::new(DigitalInteractions {
+            double_tap: Some(0.3),
+            ..Default::default()
+        });
+
+        assert!(!tester.step(true, 0.1).double_tapped);
+        tester.step(false, 0.1);
+        assert!(tester.step(true, 0.1).double_tapped);
+        tester.step(false, 0.1);
+        assert!(!tester.step(true, 0.1).double_tapped);
+        tester.step(false, 0.5);
+        assert!(!tester.step(true, 0.5).double_tapped);
+    }
+
+    #[test]
+    fn repeat_after_delay() {
+        let mut tester = Tester::new(DigitalInteractions {
+            repeat: Some(RepeatInteraction {
+                delay: 0.5,
+                rate: 4.0,
+            }),
+            ..Default::default()
+        });
+
+        let repeats = (0..10)
+            .map(|_| tester.step(true, 0.125).repeated)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            repeats,
+            [
+                true, false, false, false, true, false, true, false, true, false
+            ]
+        );
+    }
+
+    #[test]
+    fn buffer_frames() {
+        let mut tester = Tester::new(DigitalInteractions {
+            buffer: Some(InputBuffer::Frames(3)),
+            ..Default::default()
+        });
+
+        let buffered = [true, false, false, false, false]
+            .into_iter()
+            .map(|it| tester.step(it, 0.016).buffered)
+            .collect::<Vec<_>>();
+
+        assert_eq!(buffered, [true, true, true, true, false]);
+    }
+
+    #[test]
+    fn buffer_milliseconds() {
+        let mut tester = Tester::new(DigitalInteractions {
+            buffer: Some(InputBuffer::Milliseconds(100.0)),
+            ..Default::default()
+        });
+
+        assert!(tester.step(true, 0.05).buffered);
+        assert!(tester.step(true, 0.05).buffered);
+        assert!(tester.step(false, 0.05).buffered);
+        assert!(!tester.step(false, 0.05).buffered);
+    }
+}
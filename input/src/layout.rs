@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+
+use bitvec::{array::BitArray, slice::BitSlice};
+use num_enum::TryFromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::binding::button::KeyboardButton;
+
+/// Which key is printed on each physical key, so logical bindings can follow the printed letter.
+/// Keys that aren't listed print what they would on a US QWERTY keyboard.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KeyboardLayout {
+    keys: BTreeMap<KeyboardButton, KeyboardButton>,
+}
+
+impl KeyboardLayout {
+    /// US QWERTY, where every key prints what's at its position.
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// French AZERTY.
+    /// The keys at Period and Slash print `:` and `!`, which have no `KeyboardButton`, so they keep their names.
+    pub fn azerty() -> Self {
+        use KeyboardButton::*;
+
+        return Self::from_pairs(&[
+            (Q, A),
+            (A, Q),
+            (W, Z),
+            (Z, W),
+            (Semicolon, M),
+            (M, Comma),
+            (Comma, Semicolon),
+        ]);
+    }
+
+    /// Dvorak.
+    pub fn dvorak() -> Self {
+        use KeyboardButton::*;
+
+        return Self::from_pairs(&[
+            (Minus, LeftBracket),
+            (Plus, RightBracket),
+            (Q, Quote),
+            (W, Comma),
+            (E, Period),
+            (R, P),
+            (T, Y),
+            (Y, F),
+            (U, G),
+            (I, C),
+            (O, R),
+            (P, L),
+            (LeftBracket, Slash),
+            (RightBracket, Plus),
+            (S, O),
+            (D, E),
+            (F, U),
+            (G, I),
+            (H, D),
+            (J, H),
+            (K, T),
+            (L, N),
+            (Semicolon, S),
+            (Quote, Minus),
+            (Z, Semicolon),
+            (X, Q),
+            (C, J),
+            (V, K),
+            (B, X),
+            (N, B),
+            (Comma, W),
+            (Period, V),
+            (Slash, Z),
+        ]);
+    }
+
+    fn from_pairs(pairs: &[(KeyboardButton, KeyboardButton)]) -> Self {
+        let mut layout = Self::new();
+
+        for (physical, logical) in pairs {
+            layout.set(*physical, *logical);
+        }
+
+        return layout;
+    }
+
+    pub fn set(&mut self, physical: KeyboardButton, logical: KeyboardButton) {
+        if physical == logical {
+            self.keys.remove(&physical);
+            return;
+        }
+
+        self.keys.insert(physical, logical);
+    }
+
+    /// The key printed on a physical key.
+    pub fn logical(&self, physical: KeyboardButton) -> KeyboardButton {
+        return self.keys.get(&physical).copied().unwrap_or(physical);
+    }
+
+    /// The physical key that a key is printed on, if any key has it.
+    pub fn physical(&self, logical: KeyboardButton) -> Option<KeyboardButton> {
+        if let Some((physical, _)) = self.keys.iter().find(|(_, it)| **it == logical) {
+            return Some(*physical);
+        }
+
+        // Without a remapping, a key prints itself unless its position was given to something else.
+        if self.keys.contains_key(&logical) {
+            return None;
+        }
+
+        return Some(logical);
+    }
+
+    /// Every key that prints something other than what's at its position on US QWERTY.
+    pub fn iter(&self) -> impl Iterator<Item = (KeyboardButton, KeyboardButton)> {
+        return self
+            .keys
+            .iter()
+            .map(|(physical, logical)| (*physical, *logical));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.keys.is_empty();
+    }
+
+    /// The keys that are held, by what's printed on them.
+    pub(crate) fn logical_keys(&self, physical: &BitSlice<u8>) -> BitArray<[u8; 15]> {
+        let mut logical = BitArray::new([0; 15]);
+
+        for key in physical.iter_ones() {
+            let Ok(key) = KeyboardButton::try_from_primitive(key as u8) else {
+                continue;
+            };
+
+            logical.set(self.logical(key) as usize, true);
+        }
+
+        return logical;
+    }
+
+    /// Whether the physical key prints `logical`.
+    pub(crate) fn prints(&self, physical: usize, logical: usize) -> bool {
+        let Ok(physical) = KeyboardButton::try_from_primitive(physical as u8) else {
+            return false;
+        };
+
+        return self.logical(physical) as usize == logical;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{
+            AnalogBinding, AnalogBindingKind, BindingList, BoundAxis, BoundButton, DigitalBinding,
+            map::BindingMap,
+        },
+        manager::{InputFrame, InputManager},
+        recording::{InputRecording, InputReplay},
+        state::InputState,
+    };
+    use pawkit_interner::InternString;
+
+    fn bindings() -> BindingMap {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("forward"),
+            BindingList::Digital(vec![DigitalBinding::Keyboard(BoundButton::Digital(
+                KeyboardButton::W,
+            ))]),
+        );
+
+        bindings.register_binding(
+            InternString::new("inventory"),
+            BindingList::Digital(vec![DigitalBinding::LogicalKeyboard(BoundButton::Digital(
+                KeyboardButton::I,
+            ))]),
+        );
+
+        bindings.register_binding(
+            InternString::new("accelerate"),
+            BindingList::Analog(vec![AnalogBinding {
+                axis: AnalogBindingKind::LogicalKeyboard(BoundAxis::Digital(KeyboardButton::A)),
+                deadzone: 0f32,
+                scale: 1f32,
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+            }]),
+        );
+
+        return bindings;
+    }
+
+    fn frame(manager: &InputManager, name: &str) -> InputFrame {
+        return manager.get_binding(&InternString::new(name)).unwrap();
+    }
+
+    #[test]
+    fn logical_bindings_follow_layout() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+        state.set_keyboard_layout(&keyboard, KeyboardLayout::dvorak());
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(keyboard);
+
+        // The key at W's position prints a comma on Dvorak, but forward follows the position.
+        state.set_keyboard_button(&keyboard, KeyboardButton::W, true);
+        manager.update(&state, 0.1);
+
+        let InputFrame::Digital(forward) = frame(&manager, "forward") else {
+            panic!("forward is not a digital binding");
+        };
+
+        assert!(forward.pressed);
+
+        // I is printed on the key at G's position.
+        state.set_keyboard_button(&keyboard, KeyboardButton::I, true);
+        manager.update(&state, 0.1);
+
+        let InputFrame::Digital(inventory) = frame(&manager, "inventory") else {
+            panic!("inventory is not a digital binding");
+        };
+
+        assert!(!inventory.pressed);
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::G, true);
+        manager.update(&state, 0.1);
+
+        let InputFrame::Digital(inventory) = frame(&manager, "inventory") else {
+            panic!("inventory is not a digital binding");
+        };
+
+        assert!(inventory.pressed);
+
+        // On AZERTY, A is printed on the key at Q's position.
+        state.set_keyboard_layout(&keyboard, KeyboardLayout::azerty());
+        state.set_keyboard_button(&keyboard, KeyboardButton::Q, true);
+        manager.update(&state, 0.1);
+
+        let InputFrame::Analog(accelerate) = frame(&manager, "accelerate") else {
+            panic!("accelerate is not an analog binding");
+        };
+
+        assert_eq!(accelerate.value, 1f32);
+    }
+
+    #[test]
+    fn physical_glyphs_follow_layout() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+        state.set_keyboard_layout(&keyboard, KeyboardLayout::azerty());
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(keyboard);
+
+        let glyph = |name: &str| {
+            return manager.action_glyphs(&state, &InternString::new(name))[0][0].id;
+        };
+
+        // Forward is bound to the key at W's position, which prints Z on AZERTY.
+        assert_eq!(glyph("forward"), "key_z");
+        assert_eq!(glyph("inventory"), "key_i");
+    }
+
+    #[test]
+    fn azerty_prints_each_key_once() {
+        let layout = KeyboardLayout::azerty();
+        let mut printed = BTreeMap::new();
+
+        for key in (0..=u8::MAX).filter_map(|it| KeyboardButton::try_from_primitive(it).ok()) {
+            assert_eq!(printed.insert(layout.logical(key), key), None);
+        }
+    }
+
+    #[test]
+    fn logical_bindings_round_trip() {
+        let saved = bindings().save();
+
+        assert!(saved.contains("\"LogicalKeyboard\""));
+        assert_eq!(BindingMap::load(&saved).unwrap(), bindings());
+
+        let mut state = InputState::new();
+        state.start_recording();
+
+        let keyboard = state.connect_keyboard();
+        state.set_keyboard_layout(&keyboard, KeyboardLayout::azerty());
+
+        let recording = InputRecording::load(&state.stop_recording().unwrap().save()).unwrap();
+
+        let mut replayed = InputState::new();
+        InputReplay::new(recording).step(&mut replayed);
+
+        assert_eq!(
+            replayed.keyboard_layout(&keyboard),
+            Some(&KeyboardLayout::azerty())
+        );
+    }
+}
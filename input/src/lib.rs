@@ -7,9 +7,27 @@ use pawkit_crockford::Ulid;
 use pawkit_interner::InternString;
 use serde::{Deserialize, Serialize};
 
+pub mod aggregation;
 pub mod binding;
+pub mod capture;
+pub mod context;
+#[cfg(feature = "evdev")]
+pub mod evdev;
+pub mod glyph;
+pub mod haptics;
+pub mod history;
+pub mod interaction;
+pub mod layout;
 pub mod manager;
+pub mod mapping;
+pub mod motion;
+pub mod players;
+pub mod recording;
+pub mod snapshot;
 pub mod state;
+pub mod text;
+pub mod touch;
+pub mod virtual_device;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
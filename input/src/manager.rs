@@ -1,15 +1,26 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+    ops::Range,
+};
 
 use pawkit_interner::InternString;
 
 use crate::{
     DeviceId,
+    aggregation::DeviceAggregation,
     binding::{
         AnalogBinding, BindingKind, BindingList, DigitalBinding, VectorBinding,
         map::{BindingMap, BindingMapModificaitonError},
     },
-    length_squared,
-    state::InputState,
+    capture::CapturedBinding,
+    context::{ActionContext, ContextOptions, InputConsumption},
+    glyph::InputGlyph,
+    haptics::HapticEffect,
+    history::{ActionValue, InputHistory, ManagerState, TickInputs, TickRecord},
+    interaction::DigitalInteractions,
+    layout::KeyboardLayout,
+    state::{InputFamily, InputState, MouseReads},
 };
 
 #[repr(C)]
@@ -18,6 +29,12 @@ pub struct DigitalInputFrame {
     pub pressed: bool,
     pub just_pressed: bool,
     pub just_released: bool,
+    pub held: bool,
+    pub long_pressed: bool,
+    pub tapped: bool,
+    pub double_tapped: bool,
+    pub repeated: bool,
+    pub buffered: bool,
 }
 
 #[repr(C)]
@@ -37,9 +54,9 @@ pub struct VectorInputFrame {
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub union RawInputFrame {
-    digital: DigitalInputFrame,
-    analog: AnalogInputFrame,
-    vector: VectorInputFrame,
+    pub(crate) digital: DigitalInputFrame,
+    pub(crate) analog: AnalogInputFrame,
+    pub(crate) vector: VectorInputFrame,
 }
 
 #[repr(C, u8)]
@@ -50,58 +67,49 @@ pub enum InputFrame {
     Vector(VectorInputFrame),
 }
 
+/// The player started using a different device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceChange {
+    pub device: DeviceId,
+    pub family: InputFamily,
+    pub previous: Option<DeviceId>,
+    pub previous_family: Option<InputFamily>,
+}
+
 pub struct InputManager {
-    bindings: BindingMap,
+    base: ActionContext,
     devices: Vec<DeviceId>,
-    frame_indices: im::HashMap<InternString, usize>,
-    frames: Box<[RawInputFrame]>,
+    contexts: HashMap<InternString, ActionContext>,
+    /// Active contexts in the order they were pushed.
+    stack: Vec<InternString>,
+    /// Active contexts in the order they see input, highest priority first.
+    order: Vec<InternString>,
+    last_device: Option<DeviceId>,
+    last_family: Option<InputFamily>,
+    /// How strongly another device has to drive an action to take over from the last one.
+    switch_threshold: f32,
+    device_changes: VecDeque<DeviceChange>,
+    mouse_reads: MouseReads,
+    /// How many ticks have been applied.
+    tick: u64,
+    history: Option<InputHistory>,
 }
 
 impl InputManager {
     pub fn new(bindings: BindingMap) -> Self {
-        let mut frame_indices = HashMap::new();
-        let mut frames = vec![];
-
-        for (key, value) in &bindings {
-            let index = frames.len();
-            frame_indices.insert(key.clone(), index);
-
-            match value {
-                BindingList::Digital(_) => {
-                    frames.push(RawInputFrame {
-                        digital: DigitalInputFrame {
-                            pressed: false,
-                            just_pressed: false,
-                            just_released: false,
-                        },
-                    });
-                }
-
-                BindingList::Analog(_) => {
-                    frames.push(RawInputFrame {
-                        analog: AnalogInputFrame {
-                            value: 0f32,
-                            delta: 0f32,
-                        },
-                    });
-                }
-
-                BindingList::Vector(_) => {
-                    frames.push(RawInputFrame {
-                        vector: VectorInputFrame {
-                            value: [0f32; 2],
-                            delta: [0f32; 2],
-                        },
-                    });
-                }
-            }
-        }
-
         return Self {
-            bindings,
+            base: ActionContext::new(bindings, ContextOptions::default()),
             devices: vec![],
-            frame_indices: frame_indices.into(),
-            frames: frames.into(),
+            contexts: HashMap::new(),
+            stack: vec![],
+            order: vec![],
+            last_device: None,
+            last_family: None,
+            switch_threshold: 0.5,
+            device_changes: VecDeque::new(),
+            mouse_reads: MouseReads::default(),
+            tick: 0,
+            history: None,
         };
     }
 
@@ -115,103 +123,761 @@ impl InputManager {
 
     pub fn disconnect_device(&mut self, device: DeviceId) {
         self.devices.retain(|it| *it != device);
+
+        self.base.clear_source(device);
+
+        for context in self.contexts.values_mut() {
+            context.clear_source(device);
+        }
+
+        if self.last_device == Some(device) {
+            self.last_device = None;
+            self.last_family = None;
+        }
+    }
+
+    pub fn devices(&self) -> &[DeviceId] {
+        return &self.devices;
+    }
+
+    /// The bindings the manager was created with, such as for checking conflicts before rebinding.
+    pub fn bindings(&self) -> &BindingMap {
+        return &self.base.bindings;
+    }
+
+    /// Every action and its kind, including the ones in contexts.
+    /// The base bindings come first, then each context's by the context's name.
+    pub(crate) fn actions(&self) -> Vec<(InternString, BindingKind)> {
+        let mut contexts = self.contexts.iter().collect::<Vec<_>>();
+        contexts.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+        return [&self.base]
+            .into_iter()
+            .chain(contexts.into_iter().map(|(_, context)| context))
+            .flat_map(|context| {
+                (&context.bindings).into_iter().filter_map(|(name, _)| {
+                    Some((name.clone(), context.bindings.get_binding_kind(name)?))
+                })
+            })
+            .collect();
+    }
+
+    /// Registers a named context, such as a menu or vehicle, with its own bindings.
+    /// It doesn't see any input until it's pushed. Returns false if the name is already taken.
+    pub fn add_context(
+        &mut self,
+        name: InternString,
+        bindings: BindingMap,
+        options: ContextOptions,
+    ) -> bool {
+        if self.contexts.contains_key(&name) {
+            return false;
+        }
+
+        self.contexts
+            .insert(name, ActionContext::new(bindings, options));
+
+        return true;
+    }
+
+    pub fn remove_context(&mut self, name: &InternString) -> bool {
+        if self.contexts.remove(name).is_none() {
+            return false;
+        }
+
+        self.stack.retain(|it| it != name);
+        self.sort_contexts();
+
+        return true;
+    }
+
+    /// Activates a context, so it sees input before every active context with the same or lower priority.
+    /// Returns false if the context doesn't exist or is already active.
+    pub fn push_context(&mut self, name: &InternString) -> bool {
+        if !self.contexts.contains_key(name) || self.stack.contains(name) {
+            return false;
+        }
+
+        self.stack.push(name.clone());
+        self.sort_contexts();
+
+        return true;
+    }
+
+    /// Deactivates the most recently pushed context.
+    /// Its actions are released on the next update, rather than keeping their last values.
+    pub fn pop_context(&mut self) -> Option<InternString> {
+        let name = self.stack.pop()?;
+
+        self.sort_contexts();
+
+        return Some(name);
+    }
+
+    /// Deactivates a context, wherever it is in the stack.
+    pub fn deactivate_context(&mut self, name: &InternString) -> bool {
+        let Some(index) = self.stack.iter().position(|it| it == name) else {
+            return false;
+        };
+
+        self.stack.remove(index);
+        self.sort_contexts();
+
+        return true;
+    }
+
+    pub fn is_context_active(&self, name: &InternString) -> bool {
+        return self.stack.contains(name);
+    }
+
+    /// The active contexts in the order they were pushed.
+    pub fn active_contexts(&self) -> &[InternString] {
+        return &self.stack;
+    }
+
+    fn sort_contexts(&mut self) {
+        self.order = self.stack.iter().rev().cloned().collect();
+
+        // The sort is stable, so contexts with the same priority stay newest first.
+        self.order
+            .sort_by_key(|it| Reverse(self.contexts[it].options.priority));
+    }
+
+    /// The context that owns an action, checking active contexts from the highest priority down,
+    /// then the bindings the manager was created with, then inactive contexts.
+    fn find_context(&self, name: &InternString) -> Option<&ActionContext> {
+        return self
+            .order
+            .iter()
+            .map(|it| &self.contexts[it])
+            .chain([&self.base])
+            .chain(
+                self.contexts
+                    .iter()
+                    .filter(|(it, _)| !self.stack.contains(it))
+                    .map(|(_, it)| it),
+            )
+            .find(|it| it.contains(name));
+    }
+
+    fn find_context_mut(&mut self, name: &InternString) -> Option<&mut ActionContext> {
+        let active = self
+            .order
+            .iter()
+            .find(|it| self.contexts[*it].contains(name))
+            .cloned();
+
+        if active.is_none() && self.base.contains(name) {
+            return Some(&mut self.base);
+        }
+
+        let context = active.or_else(|| {
+            self.contexts
+                .iter()
+                .find(|(_, it)| it.contains(name))
+                .map(|(it, _)| it.clone())
+        })?;
+
+        return self.contexts.get_mut(&context);
+    }
+
+    pub fn set_interactions(
+        &mut self,
+        name: &InternString,
+        interactions: DigitalInteractions,
+    ) -> Result<(), BindingMapModificaitonError> {
+        return self.base.set_interactions(name, interactions);
+    }
+
+    pub fn set_context_interactions(
+        &mut self,
+        context: &InternString,
+        name: &InternString,
+        interactions: DigitalInteractions,
+    ) -> Result<(), BindingMapModificaitonError> {
+        let Some(context) = self.contexts.get_mut(context) else {
+            return Err(BindingMapModificaitonError::NotFound);
+        };
+
+        return context.set_interactions(name, interactions);
+    }
+
+    /// Sets how an action combines the values of several devices.
+    pub fn set_aggregation(
+        &mut self,
+        name: &InternString,
+        aggregation: DeviceAggregation,
+    ) -> Result<(), BindingMapModificaitonError> {
+        return self.base.set_aggregation(name, aggregation);
+    }
+
+    pub fn set_context_aggregation(
+        &mut self,
+        context: &InternString,
+        name: &InternString,
+        aggregation: DeviceAggregation,
+    ) -> Result<(), BindingMapModificaitonError> {
+        let Some(context) = self.contexts.get_mut(context) else {
+            return Err(BindingMapModificaitonError::NotFound);
+        };
+
+        return context.set_aggregation(name, aggregation);
+    }
+
+    /// Adds an action to the bindings the manager was created with, such as for a mod or a newly loaded level.
+    /// Returns false if the action already exists.
+    pub fn register_action(&mut self, name: InternString, bindings: BindingList) -> bool {
+        return self.register_action_in(None, name, bindings);
+    }
+
+    /// Replaces every binding of an action, keeping its frame unless it changes kind.
+    /// Returns false if the action doesn't exist.
+    pub fn replace_action(&mut self, name: InternString, bindings: BindingList) -> bool {
+        return self.replace_action_in(None, name, bindings);
+    }
+
+    /// Removes an action and its frame. Returns false if the action doesn't exist.
+    pub fn unregister_action(&mut self, name: &InternString) -> bool {
+        return self.unregister_action_in(None, name);
+    }
+
+    pub fn register_context_action(
+        &mut self,
+        context: &InternString,
+        name: InternString,
+        bindings: BindingList,
+    ) -> bool {
+        return self.register_action_in(Some(context), name, bindings);
+    }
+
+    pub fn replace_context_action(
+        &mut self,
+        context: &InternString,
+        name: InternString,
+        bindings: BindingList,
+    ) -> bool {
+        return self.replace_action_in(Some(context), name, bindings);
+    }
+
+    pub fn unregister_context_action(
+        &mut self,
+        context: &InternString,
+        name: &InternString,
+    ) -> bool {
+        return self.unregister_action_in(Some(context), name);
+    }
+
+    fn action_context(&self, context: Option<&InternString>) -> Option<&ActionContext> {
+        match context {
+            Some(name) => return self.contexts.get(name),
+            None => return Some(&self.base),
+        }
+    }
+
+    fn action_context_mut(&mut self, context: Option<&InternString>) -> Option<&mut ActionContext> {
+        match context {
+            Some(name) => return self.contexts.get_mut(name),
+            None => return Some(&mut self.base),
+        }
+    }
+
+    fn register_action_in(
+        &mut self,
+        context: Option<&InternString>,
+        name: InternString,
+        bindings: BindingList,
+    ) -> bool {
+        if self
+            .action_context(context)
+            .is_none_or(|it| it.contains(&name))
+        {
+            return false;
+        }
+
+        self.forget_history();
+
+        let Some(context) = self.action_context_mut(context) else {
+            return false;
+        };
+
+        context.register(name, bindings);
+
+        return true;
+    }
+
+    fn replace_action_in(
+        &mut self,
+        context: Option<&InternString>,
+        name: InternString,
+        bindings: BindingList,
+    ) -> bool {
+        let Some(kind) = self
+            .action_context(context)
+            .and_then(|it| it.bindings.get_binding_kind(&name))
+        else {
+            return false;
+        };
+
+        // Only a change of kind moves the action to a new frame.
+        if kind != bindings.kind() {
+            self.forget_history();
+        }
+
+        let Some(context) = self.action_context_mut(context) else {
+            return false;
+        };
+
+        context.register(name, bindings);
+
+        return true;
+    }
+
+    fn unregister_action_in(
+        &mut self,
+        context: Option<&InternString>,
+        name: &InternString,
+    ) -> bool {
+        if !self
+            .action_context(context)
+            .is_some_and(|it| it.contains(name))
+        {
+            return false;
+        }
+
+        self.forget_history();
+
+        let Some(context) = self.action_context_mut(context) else {
+            return false;
+        };
+
+        return context.unregister(name);
+    }
+
+    /// Frames saved before the actions change can't be restored onto the new ones,
+    /// so any rewound ticks are replayed and the history starts over.
+    fn forget_history(&mut self) {
+        while self.replay_tick() {}
+
+        if let Some(history) = &mut self.history {
+            history.clear(self.tick);
+        }
+    }
+
+    /// Applies the next tick of input.
+    /// If the manager was rewound, the remaining ticks in the history are replayed first.
+    pub fn update(&mut self, state: &InputState, delta_time: f32) {
+        while self.replay_tick() {}
+
+        let inputs = self.sample(state);
+
+        if self.history.is_some() {
+            let before = self.save();
+
+            if let Some(history) = &mut self.history {
+                history.push(TickRecord {
+                    inputs: inputs.clone(),
+                    delta_time,
+                    before,
+                });
+            }
+        }
+
+        self.advance(&inputs, delta_time);
     }
 
-    pub fn update(&mut self, state: &InputState) {
-        for (name, index) in &self.frame_indices {
-            let frame = &mut self.frames[*index];
+    /// Reads every action from the devices, without touching the frames.
+    fn sample(&mut self, state: &InputState) -> TickInputs {
+        let mut activity = vec![0f32; self.devices.len()];
+        let mut consumed = vec![];
+        let mut blocked = false;
+        let mut contexts = HashMap::new();
 
-            let Some(bindings) = self.bindings.get_bindings(name) else {
+        for name in &self.order {
+            let Some(context) = self.contexts.get_mut(name) else {
                 continue;
             };
 
-            match bindings {
-                BindingList::Digital(bindings) => {
-                    // SAFETY: We constructed the raw frame with the same type that the given name is
-                    let frame = unsafe { &mut frame.digital };
+            let devices = if blocked { &[][..] } else { &self.devices };
 
-                    let mut pressed = false;
+            contexts.insert(
+                name.clone(),
+                context.sample(state, devices, &consumed, &self.mouse_reads, &mut activity),
+            );
 
-                    for device in &self.devices {
-                        let Some(true) = state.get_digital(&device, bindings) else {
-                            continue;
-                        };
+            match context.options.consumption {
+                InputConsumption::Passthrough => {}
+                InputConsumption::Bound => context.inputs(&mut consumed),
+                InputConsumption::All => blocked = true,
+            }
+        }
 
-                        pressed = true;
-                        break;
-                    }
+        let devices = if blocked { &[][..] } else { &self.devices };
 
-                    let was_pressed = frame.pressed;
+        let base = self
+            .base
+            .sample(state, devices, &consumed, &self.mouse_reads, &mut activity);
 
-                    frame.pressed = pressed;
-                    frame.just_pressed = !was_pressed && pressed;
-                    frame.just_released = was_pressed && !pressed;
-                }
+        self.mouse_reads.advance(state);
 
-                BindingList::Analog(bindings) => {
-                    // SAFETY: We constructed the raw frame with the same type that the given name is
-                    let frame = unsafe { &mut frame.analog };
+        // Inactive contexts still update without any input, so their actions get released.
+        for (name, context) in &self.contexts {
+            if self.stack.contains(name) {
+                continue;
+            }
 
-                    let mut value = 0f32;
+            contexts.insert(name.clone(), context.released_values());
+        }
 
-                    for device in &self.devices {
-                        let Some(analog) = state.get_analog(&device, bindings) else {
-                            continue;
-                        };
+        self.update_last_device(state, &activity);
 
-                        value = value.max(analog);
-                    }
+        return TickInputs { base, contexts };
+    }
 
-                    let old_value = frame.value;
+    /// Stick with the current device while it's still in use, so the choice doesn't depend on action order.
+    /// Other devices only take over past the switch threshold, so a resting stick's noise doesn't steal it.
+    fn update_last_device(&mut self, state: &InputState, activity: &[f32]) {
+        let current = self
+            .last_device
+            .and_then(|device| self.devices.iter().position(|it| *it == device));
 
-                    frame.value = value;
-                    frame.delta = value - old_value;
-                }
+        // Another device has to pass the threshold, and drive something harder than the last device,
+        // so a drifting stick or a resting mouse can't hold on to it.
+        let held = current.map(|i| activity[i]).unwrap_or(0f32);
 
-                BindingList::Vector(bindings) => {
-                    // SAFETY: We constructed the raw frame with the same type that the given name is
-                    let frame = unsafe { &mut frame.vector };
+        let strongest = activity
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != current)
+            .filter(|(_, it)| **it >= self.switch_threshold && **it > held)
+            .max_by(|a, b| a.1.total_cmp(b.1));
 
-                    let mut value = [0f32; 2];
-                    let mut value_len_sqr = 0f32;
+        let Some((i, _)) = strongest else {
+            return;
+        };
 
-                    for device in &self.devices {
-                        let Some(analog) = state.get_vector(&device, bindings) else {
-                            continue;
-                        };
+        let device = self.devices[i];
 
-                        let analog_len_sqr = length_squared(analog);
+        let Some(family) = state.device_family(&device) else {
+            return;
+        };
 
-                        if analog_len_sqr > value_len_sqr {
-                            value = analog;
-                            value_len_sqr = analog_len_sqr;
-                        }
-                    }
+        self.device_changes.push_back(DeviceChange {
+            device,
+            family,
+            previous: self.last_device,
+            previous_family: self.last_family,
+        });
 
-                    let old_value = frame.value;
+        self.last_device = Some(device);
+        self.last_family = Some(family);
+    }
 
-                    frame.value = value;
-                    frame.delta = [old_value[0] - value[0], old_value[1] - value[1]];
-                }
-            }
+    /// The device that most recently drove an action, which prompts and glyphs should follow.
+    pub fn last_device(&self) -> Option<DeviceId> {
+        return self.last_device;
+    }
+
+    pub fn last_family(&self) -> Option<InputFamily> {
+        return self.last_family;
+    }
+
+    /// How strongly, from 0 to 1, a device has to drive an action to become the last device.
+    /// It also has to drive an action harder than the last device currently does. Defaults to 0.5.
+    pub fn set_device_switch_threshold(&mut self, threshold: f32) {
+        self.switch_threshold = threshold.clamp(0f32, 1f32);
+    }
+
+    /// Takes the oldest change of the last device.
+    pub fn pop_device_change(&mut self) -> Option<DeviceChange> {
+        return self.device_changes.pop_front();
+    }
+
+    /// Takes every change of the last device since the last drain.
+    pub fn drain_device_changes(&mut self) -> Vec<DeviceChange> {
+        return self.device_changes.drain(..).collect();
+    }
+
+    fn advance(&mut self, inputs: &TickInputs, delta_time: f32) {
+        self.base.advance(&inputs.base, delta_time);
+
+        for (name, values) in &inputs.contexts {
+            let Some(context) = self.contexts.get_mut(name) else {
+                continue;
+            };
+
+            context.advance(values, delta_time);
         }
+
+        self.tick += 1;
     }
 
-    pub unsafe fn get_binding_raw(&self, name: &InternString) -> Option<RawInputFrame> {
-        let index = self.frame_indices.get(name)?;
+    fn save(&self) -> ManagerState {
+        return ManagerState {
+            base: self.base.save(),
+            contexts: self
+                .contexts
+                .iter()
+                .map(|(name, context)| (name.clone(), context.save()))
+                .collect(),
+            last_device: self.last_device,
+            last_family: self.last_family,
+        };
+    }
+
+    fn restore(&mut self, state: &ManagerState) {
+        self.base.restore(&state.base, &self.devices);
+
+        for (name, context) in &state.contexts {
+            let Some(current) = self.contexts.get_mut(name) else {
+                continue;
+            };
+
+            current.restore(context, &self.devices);
+        }
+
+        self.restore_last_device(state);
+    }
+
+    /// Puts back which devices drove each action, and the last device, without touching the frames.
+    fn restore_sources(&mut self, state: &ManagerState) {
+        self.base.restore_sources(&state.base, &self.devices);
+
+        for (name, context) in &state.contexts {
+            let Some(current) = self.contexts.get_mut(name) else {
+                continue;
+            };
+
+            current.restore_sources(context, &self.devices);
+        }
+
+        self.restore_last_device(state);
+    }
+
+    fn restore_last_device(&mut self, state: &ManagerState) {
+        let Some(device) = state.last_device.filter(|it| self.devices.contains(it)) else {
+            self.last_device = None;
+            self.last_family = None;
+            return;
+        };
+
+        self.last_device = Some(device);
+        self.last_family = state.last_family;
+    }
+
+    /// Keeps the inputs of the last `length` ticks, so they can be corrected and replayed for rollback netcode.
+    /// A length of 0 turns the history off. Any rewound ticks are replayed first.
+    pub fn set_history_length(&mut self, length: usize) {
+        while self.replay_tick() {}
+
+        if length == 0 {
+            self.history = None;
+            return;
+        }
+
+        match &mut self.history {
+            Some(history) => history.set_length(length),
+            None => self.history = Some(InputHistory::new(length, self.tick)),
+        }
+    }
+
+    /// How many ticks have been applied, which is also the number of the next tick.
+    pub fn tick(&self) -> u64 {
+        return self.tick;
+    }
+
+    /// The ticks that are still in the history.
+    pub fn history_range(&self) -> Range<u64> {
+        let Some(history) = &self.history else {
+            return self.tick..self.tick;
+        };
+
+        return history.start()..history.end();
+    }
+
+    /// Every context that has an action, with the same precedence as `get_binding`.
+    fn contexts_with(&self, name: &InternString) -> Vec<(Option<InternString>, usize)> {
+        return self
+            .order
+            .iter()
+            .map(|it| (Some(it.clone()), &self.contexts[it]))
+            .chain([(None, &self.base)])
+            .chain(
+                self.contexts
+                    .iter()
+                    .filter(|(it, _)| !self.stack.contains(it))
+                    .map(|(it, context)| (Some(it.clone()), context)),
+            )
+            .filter_map(|(key, context)| Some((key, context.index_of(name)?)))
+            .collect();
+    }
 
-        return Some(self.frames[*index]);
+    /// What an action read on a tick in the history.
+    pub fn tick_input(&self, tick: u64, name: &InternString) -> Option<ActionValue> {
+        let record = self.history.as_ref()?.get(tick)?;
+
+        return self
+            .contexts_with(name)
+            .into_iter()
+            .find_map(|(context, index)| record.inputs.get(context.as_ref(), index));
+    }
+
+    /// Overwrites what an action read on a tick in the history, in every context that has it,
+    /// such as when a remote player's input arrives late. Rewind to the tick to replay it.
+    /// Returns false if the tick isn't in the history, or the action doesn't exist or is a different kind.
+    pub fn set_tick_input(&mut self, tick: u64, name: &InternString, value: ActionValue) -> bool {
+        let contexts = self.contexts_with(name);
+
+        let Some(record) = self.history.as_mut().and_then(|it| it.get_mut(tick)) else {
+            return false;
+        };
+
+        let mut changed = false;
+
+        for (context, index) in contexts {
+            changed |= record.inputs.set(context.as_ref(), index, value);
+        }
+
+        return changed;
     }
 
+    /// Puts every frame back to how it was just before the given tick,
+    /// and which devices drove each action, and the last device, to how they were on it.
+    /// The ticks after it are replayed by `replay_tick`, or by the next `update`.
+    /// Returns false if the tick isn't in the history.
+    pub fn rewind(&mut self, tick: u64) -> bool {
+        let Some(before) = self
+            .history
+            .as_ref()
+            .and_then(|it| it.get(tick))
+            .map(|it| it.before.clone())
+        else {
+            return false;
+        };
+
+        self.restore(&before);
+        self.tick = tick;
+
+        return true;
+    }
+
+    /// Applies the next tick from the history after a rewind, so the game can re-run its logic for each tick.
+    /// Returns false once the manager has caught up.
+    pub fn replay_tick(&mut self) -> bool {
+        let Some(sampled) = self
+            .history
+            .as_ref()
+            .and_then(|it| it.get(self.tick))
+            .map(|it| it.before.clone())
+        else {
+            return false;
+        };
+
+        // Replays don't read the devices, so which devices drove each action comes from when the tick was sampled.
+        self.restore_sources(&sampled);
+
+        let before = self.save();
+
+        let Some(record) = self.history.as_mut().and_then(|it| it.get_mut(self.tick)) else {
+            return false;
+        };
+
+        // The frames before this tick may have changed, so a later rewind starts from the corrected ones.
+        record.before = before;
+
+        let inputs = record.inputs.clone();
+        let delta_time = record.delta_time;
+
+        self.advance(&inputs, delta_time);
+
+        return true;
+    }
+
+    /// Plays a haptic effect on the device that last drove an action,
+    /// or the device the player last used if the action hasn't been used yet.
+    /// Returns false if that device can't play haptics, such as a keyboard.
+    pub fn play_haptic(
+        &self,
+        state: &mut InputState,
+        name: &InternString,
+        effect: HapticEffect,
+    ) -> bool {
+        let source = self
+            .find_context(name)
+            .and_then(|context| context.source(name));
+
+        let Some(device) = source.or(self.last_device) else {
+            return false;
+        };
+
+        return state.play_haptic(&device, effect);
+    }
+
+    /// The glyphs of each of an action's bindings, for the family and controller style of the device the player last used,
+    /// or the first connected device if none has been used yet.
+    pub fn action_glyphs(&self, state: &InputState, name: &InternString) -> Vec<Vec<InputGlyph>> {
+        let Some(device) = self.last_device.or(self.devices.first().copied()) else {
+            return vec![];
+        };
+
+        let (Some(family), Some(style)) = (
+            state.device_family(&device),
+            state.controller_style(&device),
+        ) else {
+            return vec![];
+        };
+
+        let Some(bindings) = self
+            .find_context(name)
+            .and_then(|it| it.bindings.get_bindings(name))
+        else {
+            return vec![];
+        };
+
+        let default = KeyboardLayout::new();
+        let layout = state.keyboard_layout(&device).unwrap_or(&default);
+
+        return bindings.glyphs(family, style, layout);
+    }
+
+    /// Uses up a buffered press, returning whether there was one.
+    pub fn consume(&mut self, name: &InternString) -> bool {
+        let Some(context) = self.find_context_mut(name) else {
+            return false;
+        };
+
+        return context.consume(name);
+    }
+
+    /// Looks the action up in the highest priority active context that has it,
+    /// then the bindings the manager was created with, then inactive contexts.
+    pub unsafe fn get_binding_raw(&self, name: &InternString) -> Option<RawInputFrame> {
+        return self.find_context(name)?.get_binding_raw(name);
+    }
+
+    /// Looks the action up in the highest priority active context that has it,
+    /// then the bindings the manager was created with, then inactive contexts.
     pub fn get_binding(&self, name: &InternString) -> Option<InputFrame> {
-        let index = self.frame_indices.get(name)?;
+        return self.find_context(name)?.get_binding(name);
+    }
 
-        let frame = &self.frames[*index];
+    /// Gets an action from a specific context, whether or not it's active.
+    pub fn get_context_binding(
+        &self,
+        context: &InternString,
+        name: &InternString,
+    ) -> Option<InputFrame> {
+        return self.contexts.get(context)?.get_binding(name);
+    }
 
-        match self.bindings.get_binding_kind(name)? {
-            BindingKind::Digital => return Some(InputFrame::Digital(unsafe { frame.digital })),
-            BindingKind::Analog => return Some(InputFrame::Analog(unsafe { frame.analog })),
-            BindingKind::Vector => return Some(InputFrame::Vector(unsafe { frame.vector })),
+    pub fn add_captured_binding(
+        &mut self,
+        name: InternString,
+        value: CapturedBinding,
+    ) -> Result<(), BindingMapModificaitonError> {
+        match value {
+            CapturedBinding::Digital(value) => return self.add_digital_binding(name, value),
+            CapturedBinding::Analog(value) => return self.add_analog_binding(name, value),
+            CapturedBinding::Vector(value) => return self.add_vector_binding(name, value),
         }
     }
 
@@ -220,7 +886,7 @@ impl InputManager {
         name: InternString,
         value: DigitalBinding,
     ) -> Result<(), BindingMapModificaitonError> {
-        return self.bindings.add_digital_binding(name, value);
+        return self.base.bindings.add_digital_binding(name, value);
     }
 
     pub fn remove_digital_binding(
@@ -228,7 +894,7 @@ impl InputManager {
         name: InternString,
         value: DigitalBinding,
     ) -> Result<(), BindingMapModificaitonError> {
-        return self.bindings.remove_digital_binding(name, value);
+        return self.base.bindings.remove_digital_binding(name, value);
     }
 
     pub fn add_analog_binding(
@@ -236,7 +902,7 @@ impl InputManager {
         name: InternString,
         value: AnalogBinding,
     ) -> Result<(), BindingMapModificaitonError> {
-        return self.bindings.add_analog_binding(name, value);
+        return self.base.bindings.add_analog_binding(name, value);
     }
 
     pub fn remove_analog_binding(
@@ -244,7 +910,7 @@ impl InputManager {
         name: InternString,
         value: AnalogBinding,
     ) -> Result<(), BindingMapModificaitonError> {
-        return self.bindings.remove_analog_binding(name, value);
+        return self.base.bindings.remove_analog_binding(name, value);
     }
 
     pub fn add_vector_binding(
@@ -252,7 +918,7 @@ impl InputManager {
         name: InternString,
         value: VectorBinding,
     ) -> Result<(), BindingMapModificaitonError> {
-        return self.bindings.add_vector_binding(name, value);
+        return self.base.bindings.add_vector_binding(name, value);
     }
 
     pub fn remove_vector_binding(
@@ -260,6 +926,198 @@ impl InputManager {
         name: InternString,
         value: VectorBinding,
     ) -> Result<(), BindingMapModificaitonError> {
-        return self.bindings.remove_vector_binding(name, value);
+        return self.base.bindings.remove_vector_binding(name, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{
+            AnalogBindingKind, BindingList, BoundAxis, BoundButton, ChordModifiers,
+            axis::GamepadAxis, button::KeyboardButton,
+        },
+        interaction::InputBuffer,
+    };
+
+    fn key(button: KeyboardButton) -> DigitalBinding {
+        return DigitalBinding::Keyboard(BoundButton::Digital(button));
+    }
+
+    fn chord(modifiers: &[KeyboardButton], button: KeyboardButton) -> DigitalBinding {
+        return DigitalBinding::Keyboard(BoundButton::Chord {
+            modifiers: ChordModifiers::new(modifiers).unwrap(),
+            button,
+        });
+    }
+
+    fn pressed(manager: &InputManager, name: &str) -> bool {
+        let Some(InputFrame::Digital(frame)) = manager.get_binding(&InternString::new(name)) else {
+            panic!("{} is not a digital binding", name);
+        };
+
+        return frame.pressed;
+    }
+
+    #[test]
+    fn chord_suppresses_plain_binding() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("z"),
+            BindingList::Digital(vec![key(KeyboardButton::Z)]),
+        );
+        bindings.register_binding(
+            InternString::new("undo"),
+            BindingList::Digital(vec![chord(
+                &[KeyboardButton::LeftControl],
+                KeyboardButton::Z,
+            )]),
+        );
+
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        let mut manager = InputManager::new(bindings);
+        manager.connect_device(keyboard);
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Z, true);
+        manager.update(&state, 0.1);
+
+        assert!(pressed(&manager, "z"));
+        assert!(!pressed(&manager, "undo"));
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::LeftControl, true);
+        manager.update(&state, 0.1);
+
+        assert!(!pressed(&manager, "z"));
+        assert!(pressed(&manager, "undo"));
+    }
+
+    #[test]
+    fn consume_uses_buffered_press_once() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("jump"),
+            BindingList::Digital(vec![key(KeyboardButton::Space)]),
+        );
+
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        let mut manager = InputManager::new(bindings);
+        manager.connect_device(keyboard);
+        manager
+            .set_interactions(
+                &InternString::new("jump"),
+                DigitalInteractions {
+                    buffer: Some(InputBuffer::Frames(3)),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        manager.update(&state, 0.016);
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, false);
+        manager.update(&state, 0.016);
+        manager.update(&state, 0.016);
+
+        assert!(manager.consume(&InternString::new("jump")));
+        assert!(!manager.consume(&InternString::new("jump")));
+
+        manager.update(&state, 0.016);
+
+        assert!(!manager.consume(&InternString::new("jump")));
+    }
+
+    #[test]
+    fn small_stick_movement_does_not_switch_device() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("jump"),
+            BindingList::Digital(vec![key(KeyboardButton::Space)]),
+        );
+        bindings.register_binding(
+            InternString::new("throttle"),
+            BindingList::Analog(vec![AnalogBinding {
+                axis: AnalogBindingKind::Gamepad(BoundAxis::Analog(GamepadAxis::LeftY)),
+                deadzone: 0.05,
+                scale: 1f32,
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+            }]),
+        );
+
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+        let gamepad = state.connect_gamepad();
+
+        let mut manager = InputManager::new(bindings);
+        manager.connect_device(keyboard);
+        manager.connect_device(gamepad);
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        manager.update(&state, 0.1);
+
+        assert_eq!(manager.last_device(), Some(keyboard));
+        assert_eq!(
+            manager.pop_device_change(),
+            Some(DeviceChange {
+                device: keyboard,
+                family: InputFamily::Keyboard,
+                previous: None,
+                previous_family: None,
+            })
+        );
+
+        // A drifting stick drives the action a little, but not enough to take over.
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, false);
+        state.set_gamepad_axis(&gamepad, GamepadAxis::LeftY, 0.2);
+        manager.update(&state, 0.1);
+
+        assert_eq!(manager.last_family(), Some(InputFamily::Keyboard));
+        assert_eq!(manager.pop_device_change(), None);
+
+        state.set_gamepad_axis(&gamepad, GamepadAxis::LeftY, 0.9);
+        manager.update(&state, 0.1);
+
+        assert_eq!(manager.last_device(), Some(gamepad));
+        assert_eq!(
+            manager.drain_device_changes(),
+            [DeviceChange {
+                device: gamepad,
+                family: InputFamily::Gamepad,
+                previous: Some(keyboard),
+                previous_family: Some(InputFamily::Keyboard),
+            }]
+        );
+
+        // Once it has taken over, the gamepad keeps its place until something else passes the threshold.
+        state.set_gamepad_axis(&gamepad, GamepadAxis::LeftY, 0.2);
+        manager.update(&state, 0.1);
+
+        assert_eq!(manager.last_family(), Some(InputFamily::Gamepad));
+        assert!(manager.drain_device_changes().is_empty());
+
+        // A stick left drifting doesn't stop the keyboard from taking over.
+        state.set_gamepad_axis(&gamepad, GamepadAxis::LeftY, 0.1);
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        manager.update(&state, 0.1);
+
+        assert_eq!(manager.last_device(), Some(keyboard));
+        assert_eq!(
+            manager.drain_device_changes(),
+            [DeviceChange {
+                device: keyboard,
+                family: InputFamily::Keyboard,
+                previous: Some(gamepad),
+                previous_family: Some(InputFamily::Gamepad),
+            }]
+        );
     }
 }
@@ -0,0 +1,570 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    io::{self, Read},
+    str::FromStr,
+};
+
+use pawkit_fs::{Vfs, VfsError};
+use thiserror::Error;
+
+use crate::{
+    DeviceId,
+    binding::{axis::GamepadAxis, button::GamepadButton},
+    state::InputState,
+};
+
+/// Used for devices that aren't in the database, matching the usual XInput layout.
+const FALLBACK: &str = "00000000000000000000000000000000,Generic Gamepad,a:b0,b:b1,x:b2,y:b3,back:b6,guide:b8,start:b7,leftstick:b9,rightstick:b10,leftshoulder:b4,rightshoulder:b5,dpup:h0.1,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,leftx:a0,lefty:a1,rightx:a3,righty:a4,lefttrigger:a2,righttrigger:a5,";
+
+/// How far a raw axis has to move for a button mapped to it to be pressed.
+const BUTTON_THRESHOLD: f32 = 0.5;
+
+#[derive(Error, Debug)]
+pub enum MappingError {
+    #[error("VFS error: {0}")]
+    VfsError(#[from] VfsError),
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("Malformed mapping: {0}")]
+    Malformed(Box<str>),
+}
+
+/// The GUID SDL gives a joystick, made from its bus, vendor, product and version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GamepadGuid(pub [u8; 16]);
+
+impl GamepadGuid {
+    /// Newer versions of SDL put a checksum of the device name in bytes 2 and 3,
+    /// which older mapping files don't have.
+    fn without_crc(self) -> Self {
+        let mut bytes = self.0;
+        bytes[2] = 0;
+        bytes[3] = 0;
+
+        return Self(bytes);
+    }
+}
+
+impl FromStr for GamepadGuid {
+    type Err = MappingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || MappingError::Malformed(s.into());
+
+        if s.len() != 32 || !s.is_ascii() {
+            return Err(malformed());
+        }
+
+        let mut bytes = [0u8; 16];
+
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).map_err(|_| malformed())?;
+        }
+
+        return Ok(Self(bytes));
+    }
+}
+
+impl Display for GamepadGuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// Which part of an axis a mapping uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AxisRange {
+    #[default]
+    Full,
+    /// Only the half from the center to the positive end, written `+` in the mapping.
+    Positive,
+    /// Only the half from the center to the negative end, written `-` in the mapping.
+    Negative,
+}
+
+impl AxisRange {
+    fn parse(s: &str) -> (Self, &str) {
+        if let Some(rest) = s.strip_prefix('+') {
+            return (Self::Positive, rest);
+        }
+
+        if let Some(rest) = s.strip_prefix('-') {
+            return (Self::Negative, rest);
+        }
+
+        return (Self::Full, s);
+    }
+
+    /// The values at the start and end of the range.
+    fn bounds(self, trigger: bool) -> (f32, f32) {
+        return match self {
+            Self::Full if trigger => (0f32, 1f32),
+            Self::Full => (-1f32, 1f32),
+            Self::Positive => (0f32, 1f32),
+            Self::Negative => (0f32, -1f32),
+        };
+    }
+}
+
+/// A raw input on the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappingSource {
+    Button(u32),
+    /// A hat switch, and the direction bit that triggers this mapping.
+    Hat(u32, u8),
+    Axis {
+        axis: u32,
+        range: AxisRange,
+        inverted: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappingTarget {
+    Button(GamepadButton),
+    Axis(GamepadAxis, AxisRange),
+}
+
+impl MappingSource {
+    fn parse(s: &str) -> Option<Self> {
+        let (range, s) = AxisRange::parse(s);
+
+        let (s, inverted) = match s.strip_suffix('~') {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+
+        if let Some(axis) = s.strip_prefix('a') {
+            return Some(Self::Axis {
+                axis: axis.parse().ok()?,
+                range,
+                inverted,
+            });
+        }
+
+        if range != AxisRange::Full || inverted {
+            return None;
+        }
+
+        if let Some(button) = s.strip_prefix('b') {
+            return Some(Self::Button(button.parse().ok()?));
+        }
+
+        let (hat, mask) = s.strip_prefix('h')?.split_once('.')?;
+
+        return Some(Self::Hat(hat.parse().ok()?, mask.parse().ok()?));
+    }
+}
+
+impl MappingTarget {
+    fn parse(s: &str) -> Option<Self> {
+        let (range, s) = AxisRange::parse(s);
+
+        let axis = match s {
+            "leftx" => Some(GamepadAxis::LeftX),
+            "lefty" => Some(GamepadAxis::LeftY),
+            "rightx" => Some(GamepadAxis::RightX),
+            "righty" => Some(GamepadAxis::RightY),
+            "lefttrigger" => Some(GamepadAxis::LeftTrigger),
+            "righttrigger" => Some(GamepadAxis::RightTrigger),
+            _ => None,
+        };
+
+        if let Some(axis) = axis {
+            return Some(Self::Axis(axis, range));
+        }
+
+        if range != AxisRange::Full {
+            return None;
+        }
+
+        let button = match s {
+            "a" => GamepadButton::South,
+            "b" => GamepadButton::East,
+            "x" => GamepadButton::West,
+            "y" => GamepadButton::North,
+            "back" => GamepadButton::Back,
+            "guide" => GamepadButton::Guide,
+            "start" => GamepadButton::Start,
+            "leftstick" => GamepadButton::LeftStick,
+            "rightstick" => GamepadButton::RightStick,
+            "leftshoulder" => GamepadButton::LeftShoulder,
+            "rightshoulder" => GamepadButton::RightShoulder,
+            "dpup" => GamepadButton::DpadUp,
+            "dpdown" => GamepadButton::DpadDown,
+            "dpleft" => GamepadButton::DpadLeft,
+            "dpright" => GamepadButton::DpadRight,
+            "misc1" => GamepadButton::Misc1,
+            "paddle1" => GamepadButton::RightPaddle1,
+            "paddle2" => GamepadButton::LeftPaddle1,
+            "paddle3" => GamepadButton::RightPaddle2,
+            "paddle4" => GamepadButton::LeftPaddle2,
+            "touchpad" => GamepadButton::Touchpad,
+            "misc2" => GamepadButton::Misc2,
+            "misc3" => GamepadButton::Misc3,
+            "misc4" => GamepadButton::Misc4,
+            "misc5" => GamepadButton::Misc5,
+            "misc6" => GamepadButton::Misc6,
+            _ => return None,
+        };
+
+        return Some(Self::Button(button));
+    }
+
+    /// Sets the target from a value between 0 and 1 along its source.
+    fn apply(self, state: &mut InputState, device: &DeviceId, value: f32) {
+        match self {
+            Self::Button(button) => {
+                state.set_gamepad_button(device, button, value > BUTTON_THRESHOLD);
+            }
+
+            Self::Axis(axis, range) => {
+                let trigger = matches!(axis, GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger);
+                let (start, end) = range.bounds(trigger);
+
+                state.set_gamepad_axis(device, axis, start + (end - start) * value);
+            }
+        }
+    }
+}
+
+/// How the raw buttons, hats and axes of one kind of device turn into gamepad inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadMapping {
+    pub guid: GamepadGuid,
+    pub name: Box<str>,
+    /// The platform the mapping is for, or `None` if it's for every platform.
+    pub platform: Option<Box<str>>,
+    pub elements: Vec<(MappingSource, MappingTarget)>,
+}
+
+impl GamepadMapping {
+    /// Parses a single line of a `gamecontrollerdb.txt` file.
+    /// Elements that PawKit doesn't have a button or axis for are skipped.
+    pub fn parse(line: &str) -> Result<Self, MappingError> {
+        let malformed = || MappingError::Malformed(line.into());
+
+        let mut fields = line.trim().split(',');
+
+        let guid = fields.next().ok_or_else(malformed)?.parse()?;
+        let name = fields.next().ok_or_else(malformed)?.into();
+
+        let mut platform = None;
+        let mut elements = vec![];
+
+        for field in fields {
+            if field.is_empty() {
+                continue;
+            }
+
+            let (target, source) = field.split_once(':').ok_or_else(malformed)?;
+
+            if target == "platform" {
+                platform = Some(source.into());
+                continue;
+            }
+
+            let (Some(target), Some(source)) =
+                (MappingTarget::parse(target), MappingSource::parse(source))
+            else {
+                continue;
+            };
+
+            elements.push((source, target));
+        }
+
+        return Ok(Self {
+            guid,
+            name,
+            platform,
+            elements,
+        });
+    }
+
+    /// The mapping used for devices that aren't in the database.
+    pub fn fallback() -> Self {
+        return Self::parse(FALLBACK).expect("The fallback mapping should parse");
+    }
+
+    /// Updates the gamepad from a raw button.
+    pub fn set_button(&self, state: &mut InputState, device: &DeviceId, button: u32, value: bool) {
+        for (source, target) in &self.elements {
+            let MappingSource::Button(it) = *source else {
+                continue;
+            };
+
+            if it == button {
+                target.apply(state, device, if value { 1f32 } else { 0f32 });
+            }
+        }
+    }
+
+    /// Updates the gamepad from a raw hat, where `value` has a bit set for each held direction:
+    /// 1 for up, 2 for right, 4 for down and 8 for left.
+    pub fn set_hat(&self, state: &mut InputState, device: &DeviceId, hat: u32, value: u8) {
+        for (source, target) in &self.elements {
+            let MappingSource::Hat(it, mask) = *source else {
+                continue;
+            };
+
+            if it == hat {
+                target.apply(state, device, if value & mask != 0 { 1f32 } else { 0f32 });
+            }
+        }
+    }
+
+    /// Updates the gamepad from a raw axis, where `value` is between -1 and 1.
+    pub fn set_axis(&self, state: &mut InputState, device: &DeviceId, axis: u32, value: f32) {
+        for (source, target) in &self.elements {
+            let MappingSource::Axis {
+                axis: it,
+                range,
+                inverted,
+            } = *source
+            else {
+                continue;
+            };
+
+            if it != axis {
+                continue;
+            }
+
+            let value = if inverted { -value } else { value };
+
+            // Where the value is along the part of the axis that's mapped, from 0 to 1.
+            // A trigger reported as a full axis rests at -1, so it's mapped from end to end.
+            let (start, end) = range.bounds(false);
+            let value = ((value - start) / (end - start)).clamp(0f32, 1f32);
+
+            target.apply(state, device, value);
+        }
+    }
+}
+
+/// A set of `GamepadMapping`s, such as one loaded from SDL's `gamecontrollerdb.txt`.
+#[derive(Debug, Clone)]
+pub struct GamepadMappings {
+    mappings: HashMap<GamepadGuid, GamepadMapping>,
+    fallback: GamepadMapping,
+}
+
+impl GamepadMappings {
+    pub fn new() -> Self {
+        return Self {
+            mappings: HashMap::new(),
+            fallback: GamepadMapping::fallback(),
+        };
+    }
+
+    /// The name SDL uses for the platform this was built for.
+    pub fn current_platform() -> &'static str {
+        if cfg!(target_os = "windows") {
+            return "Windows";
+        }
+
+        if cfg!(target_os = "macos") {
+            return "Mac OS X";
+        }
+
+        if cfg!(target_os = "android") {
+            return "Android";
+        }
+
+        if cfg!(target_os = "ios") {
+            return "iOS";
+        }
+
+        return "Linux";
+    }
+
+    /// Adds every mapping in a `gamecontrollerdb.txt` file for the current platform.
+    /// Comments, blank lines and malformed lines are skipped, and the number of mappings added is returned.
+    pub fn parse(&mut self, text: &str) -> usize {
+        return self.parse_for_platform(text, Self::current_platform());
+    }
+
+    pub fn parse_for_platform(&mut self, text: &str, platform: &str) -> usize {
+        let mut added = 0;
+
+        for line in text.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Ok(mapping) = GamepadMapping::parse(line) else {
+                continue;
+            };
+
+            if mapping.platform.as_deref().is_some_and(|it| it != platform) {
+                continue;
+            }
+
+            self.insert(mapping);
+            added += 1;
+        }
+
+        return added;
+    }
+
+    /// Reads a mapping file from any reader, such as a `VfsBuffer`.
+    pub fn read<R: Read>(&mut self, reader: &mut R) -> Result<usize, MappingError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+
+        return Ok(self.parse(&text));
+    }
+
+    pub fn load(&mut self, vfs: &Vfs, path: &str) -> Result<usize, MappingError> {
+        return self.read(&mut vfs.open(path)?);
+    }
+
+    /// Adds a mapping, replacing any with the same GUID.
+    pub fn insert(&mut self, mapping: GamepadMapping) {
+        self.mappings.insert(mapping.guid, mapping);
+    }
+
+    /// The mapping for a device, if it's in the database.
+    pub fn get(&self, guid: GamepadGuid) -> Option<&GamepadMapping> {
+        if let Some(mapping) = self.mappings.get(&guid) {
+            return Some(mapping);
+        }
+
+        let guid = guid.without_crc();
+
+        return self
+            .mappings
+            .values()
+            .find(|it| it.guid.without_crc() == guid);
+    }
+
+    /// The mapping for a device, or a generic one if it isn't in the database.
+    pub fn get_or_fallback(&self, guid: GamepadGuid) -> &GamepadMapping {
+        return self.get(guid).unwrap_or(&self.fallback);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.mappings.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.mappings.is_empty();
+    }
+}
+
+impl Default for GamepadMappings {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DB: &str = "\
+# Game Controller DB
+03000000de280000ff11000001000000,Steam Virtual Gamepad,a:b0,b:b1,x:b2,y:b3,back:b6,start:b7,leftshoulder:b4,rightshoulder:b5,dpup:h0.1,dpdown:h0.4,leftx:a0,lefty:a1,lefttrigger:+a2,righttrigger:-a2,platform:Linux,
+03000000790000000600000000000000,Retro Pad,a:b1,b:b2,leftx:a0,lefty:a1~,dpleft:-a3,dpright:+a3,lefttrigger:b6,platform:Linux,
+03000000790000000600000000000000,Retro Pad,a:b9,platform:Windows,
+not a mapping
+";
+
+    fn connect() -> (InputState, DeviceId) {
+        let mut state = InputState::new();
+        let gamepad = state.connect_gamepad();
+
+        return (state, gamepad);
+    }
+
+    fn read(state: &InputState, gamepad: DeviceId) -> (Vec<bool>, Vec<f32>) {
+        let (_, _, buttons, axes) = state
+            .raw_devices()
+            .find(|(device, _, _, _)| *device == gamepad)
+            .unwrap();
+
+        return (buttons.iter().map(|it| *it).collect(), axes.to_vec());
+    }
+
+    fn guid(s: &str) -> GamepadGuid {
+        return s.parse().unwrap();
+    }
+
+    #[test]
+    fn parses_database_for_platform() {
+        let mut mappings = GamepadMappings::new();
+
+        assert_eq!(mappings.parse_for_platform(DB, "Linux"), 2);
+
+        let retro = mappings
+            .get(guid("03000000790000000600000000000000"))
+            .unwrap();
+
+        assert_eq!(&*retro.name, "Retro Pad");
+        assert!(retro.elements.contains(&(
+            MappingSource::Button(1),
+            MappingTarget::Button(GamepadButton::South)
+        )));
+
+        // Newer GUIDs have a name checksum, which the database doesn't.
+        assert!(
+            mappings
+                .get(guid("0300abcd790000000600000000000000"))
+                .is_some()
+        );
+
+        let unknown = guid("05000000000000000000000000000000");
+
+        assert!(mappings.get(unknown).is_none());
+        assert_eq!(
+            mappings.get_or_fallback(unknown),
+            &GamepadMapping::fallback()
+        );
+    }
+
+    #[test]
+    fn maps_raw_inputs() {
+        let mut mappings = GamepadMappings::new();
+        mappings.parse_for_platform(DB, "Linux");
+
+        let steam = mappings.get_or_fallback(guid("03000000de280000ff11000001000000"));
+        let retro = mappings.get_or_fallback(guid("03000000790000000600000000000000"));
+
+        let (mut state, gamepad) = connect();
+
+        steam.set_button(&mut state, &gamepad, 0, true);
+        steam.set_hat(&mut state, &gamepad, 0, 1 | 2);
+
+        // One axis split into both triggers, like some older drivers report them.
+        steam.set_axis(&mut state, &gamepad, 2, -0.5);
+
+        let (buttons, axes) = read(&state, gamepad);
+
+        assert!(buttons[GamepadButton::South as usize]);
+        assert!(buttons[GamepadButton::DpadUp as usize]);
+        assert!(!buttons[GamepadButton::DpadDown as usize]);
+
+        assert_eq!(axes[GamepadAxis::LeftTrigger as usize], 0f32);
+        assert_eq!(axes[GamepadAxis::RightTrigger as usize], 0.5);
+
+        let (mut state, gamepad) = connect();
+
+        retro.set_axis(&mut state, &gamepad, 1, 0.25);
+        retro.set_axis(&mut state, &gamepad, 3, 0.75);
+        retro.set_button(&mut state, &gamepad, 6, true);
+
+        let (buttons, axes) = read(&state, gamepad);
+
+        assert_eq!(axes[GamepadAxis::LeftY as usize], -0.25);
+        assert_eq!(axes[GamepadAxis::LeftTrigger as usize], 1f32);
+
+        assert!(buttons[GamepadButton::DpadRight as usize]);
+        assert!(!buttons[GamepadButton::DpadLeft as usize]);
+    }
+}
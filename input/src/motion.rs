@@ -0,0 +1,253 @@
+use crate::binding::axis::GamepadAxis;
+
+/// A single reading from a gamepad's motion sensors, in the units SDL reports them in.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionSample {
+    /// When the sample was taken, in microseconds. Only the difference between samples matters.
+    pub timestamp: u64,
+    /// Angular velocity around the x (pitch), y (yaw) and z (roll) axes, in radians per second.
+    pub gyro: [f32; 3],
+    /// Acceleration along each axis, in meters per second squared.
+    pub accel: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GyroCalibration {
+    /// Subtracted from every gyro sample.
+    pub offset: [f32; 3],
+    /// While the gyro reads slower than this, the controller is assumed to be resting,
+    /// and the offset slowly follows the readings to compensate for drift. Zero disables it.
+    pub rest_threshold: f32,
+    /// How much of the remaining drift is absorbed into the offset per second of rest.
+    pub drift_rate: f32,
+}
+
+impl Default for GyroCalibration {
+    fn default() -> Self {
+        return Self {
+            offset: [0f32; 3],
+            rest_threshold: 0f32,
+            drift_rate: 0.5,
+        };
+    }
+}
+
+const GYRO: [GamepadAxis; 3] = [GamepadAxis::GyroX, GamepadAxis::GyroY, GamepadAxis::GyroZ];
+const ACCEL: [GamepadAxis; 3] = [
+    GamepadAxis::AccelX,
+    GamepadAxis::AccelY,
+    GamepadAxis::AccelZ,
+];
+
+/// Integrates gyro samples into the rotation since the last frame, and keeps the calibration.
+pub(crate) struct MotionTracker {
+    pub(crate) calibration: GyroCalibration,
+    last_sample: Option<MotionSample>,
+    calibrating: Option<([f64; 3], u32)>,
+}
+
+impl MotionTracker {
+    pub(crate) fn new() -> Self {
+        return Self {
+            calibration: GyroCalibration::default(),
+            last_sample: None,
+            calibrating: None,
+        };
+    }
+
+    pub(crate) fn last_sample(&self) -> Option<MotionSample> {
+        return self.last_sample;
+    }
+
+    pub(crate) fn is_calibrating(&self) -> bool {
+        return self.calibrating.is_some();
+    }
+
+    /// Starts averaging the gyro samples. The controller should be resting on a flat surface until it ends.
+    pub(crate) fn begin_calibration(&mut self) {
+        self.calibrating = Some(([0f64; 3], 0));
+    }
+
+    /// Uses the average of the samples since the calibration began as the new offset.
+    pub(crate) fn end_calibration(&mut self) -> Option<GyroCalibration> {
+        let (sum, count) = self.calibrating.take()?;
+
+        if count == 0 {
+            return None;
+        }
+
+        self.calibration.offset = sum.map(|it| (it / count as f64) as f32);
+
+        return Some(self.calibration);
+    }
+
+    pub(crate) fn push_sample(&mut self, sample: MotionSample, axes: &mut [f32]) {
+        if let Some((sum, count)) = &mut self.calibrating {
+            for (sum, value) in sum.iter_mut().zip(sample.gyro) {
+                *sum += value as f64;
+            }
+
+            *count += 1;
+        }
+
+        // The first sample only marks the time, since there's nothing to measure it from.
+        let delta_time = match self.last_sample {
+            Some(last) => sample.timestamp.saturating_sub(last.timestamp) as f32 / 1_000_000f32,
+            None => 0f32,
+        };
+
+        let offset = self.calibration.offset;
+        let gyro = [0, 1, 2].map(|i| sample.gyro[i] - offset[i]);
+
+        let rest_threshold = self.calibration.rest_threshold;
+
+        if rest_threshold > 0f32
+            && gyro.iter().map(|it| it * it).sum::<f32>() < rest_threshold * rest_threshold
+        {
+            let amount = (self.calibration.drift_rate * delta_time).min(1f32);
+
+            for (offset, value) in self.calibration.offset.iter_mut().zip(gyro) {
+                *offset += value * amount;
+            }
+        }
+
+        for (axis, value) in GYRO.into_iter().zip(gyro) {
+            axes[axis as usize] += value * delta_time;
+        }
+
+        for (axis, value) in ACCEL.into_iter().zip(sample.accel) {
+            axes[axis as usize] = value;
+        }
+
+        self.last_sample = Some(sample);
+    }
+
+    /// Clears the rotation, so the next frame measures from zero.
+    pub(crate) fn end_frame(&self, axes: &mut [f32]) {
+        for axis in GYRO {
+            axes[axis as usize] = 0f32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{
+            BindingList, BoundAxis, BoundButton, DigitalBinding, VectorBinding, VectorBindingKind,
+            button::GamepadButton, map::BindingMap,
+        },
+        manager::{InputFrame, InputManager},
+        state::InputState,
+    };
+    use pawkit_interner::InternString;
+
+    fn sample(timestamp: u64, gyro: [f32; 3]) -> MotionSample {
+        return MotionSample {
+            timestamp,
+            gyro,
+            accel: [0.0, -9.8, 0.0],
+        };
+    }
+
+    #[test]
+    fn integrates_samples_per_frame() {
+        let mut state = InputState::new();
+        let gamepad = state.connect_gamepad();
+
+        state.set_gamepad_motion(&gamepad, sample(0, [1.0, 0.0, 0.0]));
+        state.set_gamepad_motion(&gamepad, sample(10_000, [1.0, 0.0, 0.0]));
+        state.set_gamepad_motion(&gamepad, sample(20_000, [1.0, 2.0, 0.0]));
+
+        let axes = state.raw_devices().next().unwrap().3.to_vec();
+
+        assert!((axes[GamepadAxis::GyroX as usize] - 0.02).abs() < 1e-6);
+        assert!((axes[GamepadAxis::GyroY as usize] - 0.02).abs() < 1e-6);
+        assert_eq!(axes[GamepadAxis::AccelY as usize], -9.8);
+
+        state.next_frame();
+
+        let axes = state.raw_devices().next().unwrap().3.to_vec();
+
+        assert_eq!(axes[GamepadAxis::GyroX as usize], 0.0);
+        assert_eq!(axes[GamepadAxis::AccelY as usize], -9.8);
+    }
+
+    #[test]
+    fn calibration_removes_offset() {
+        let mut state = InputState::new();
+        let gamepad = state.connect_gamepad();
+
+        state.begin_gyro_calibration(&gamepad);
+
+        for i in 0..4 {
+            let noise = if i % 2 == 0 { 0.01 } else { -0.01 };
+            state.set_gamepad_motion(&gamepad, sample(i * 1000, [0.1 + noise, -0.2, 0.0]));
+        }
+
+        let calibration = state.end_gyro_calibration(&gamepad).unwrap();
+
+        assert!((calibration.offset[0] - 0.1).abs() < 1e-6);
+        assert!((calibration.offset[1] + 0.2).abs() < 1e-6);
+
+        state.next_frame();
+        state.set_gamepad_motion(&gamepad, sample(100_000, [0.1, -0.2, 0.0]));
+
+        let axes = state.raw_devices().next().unwrap().3.to_vec();
+
+        assert!(axes[GamepadAxis::GyroX as usize].abs() < 1e-6);
+        assert!(axes[GamepadAxis::GyroY as usize].abs() < 1e-6);
+    }
+
+    #[test]
+    fn ratchet_only_aims_while_held() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("aim"),
+            BindingList::Vector(vec![VectorBinding {
+                axes: VectorBindingKind::Gamepad {
+                    x: BoundAxis::Analog(GamepadAxis::GyroY),
+                    y: BoundAxis::Analog(GamepadAxis::GyroX),
+                },
+                deadzone: 0.0,
+                scale: (1.0, 1.0),
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+                ratchet: Some(DigitalBinding::Gamepad(BoundButton::Digital(
+                    GamepadButton::RightShoulder,
+                ))),
+            }]),
+        );
+
+        let mut state = InputState::new();
+        let gamepad = state.connect_gamepad();
+
+        let mut manager = InputManager::new(bindings);
+        manager.connect_device(gamepad);
+
+        let aim = |manager: &InputManager| {
+            let Some(InputFrame::Vector(frame)) = manager.get_binding(&InternString::new("aim"))
+            else {
+                panic!("expected a vector frame");
+            };
+
+            return frame.value;
+        };
+
+        state.set_gamepad_motion(&gamepad, sample(0, [0.0, 5.0, 0.0]));
+        state.set_gamepad_motion(&gamepad, sample(10_000, [0.0, 5.0, 0.0]));
+        manager.update(&state, 0.01);
+
+        assert_eq!(aim(&manager), [0.0, 0.0]);
+
+        state.set_gamepad_button(&gamepad, GamepadButton::RightShoulder, true);
+        manager.update(&state, 0.01);
+
+        assert!((aim(&manager)[0] - 0.05).abs() < 1e-6);
+    }
+}
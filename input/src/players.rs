@@ -0,0 +1,369 @@
+use std::collections::HashSet;
+
+use crate::{
+    DeviceId,
+    binding::{DigitalBinding, map::BindingMap},
+    manager::InputManager,
+    state::{InputFamily, InputState, MouseReads},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotStatus {
+    Empty,
+    Joined,
+    /// Every device of the player disconnected.
+    /// The slot stays reserved until a device of the same family joins again, or the player leaves.
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerEvent {
+    Joined { player: usize, device: DeviceId },
+    Disconnected { player: usize },
+    Reconnected { player: usize, device: DeviceId },
+    Left { player: usize },
+}
+
+struct PlayerSlot {
+    manager: InputManager,
+    devices: Vec<(DeviceId, InputFamily)>,
+    status: SlotStatus,
+    lost_family: Option<InputFamily>,
+}
+
+impl PlayerSlot {
+    fn has_family(&self, family: InputFamily) -> bool {
+        return self.devices.iter().any(|it| it.1 == family);
+    }
+
+    fn add_device(&mut self, device: DeviceId, family: InputFamily) {
+        self.devices.push((device, family));
+        self.manager.connect_device(device);
+    }
+}
+
+/// Owns one `InputManager` per local player, and hands devices out to them as players join.
+///
+/// A device joins when one of the join bindings is pressed on it.
+/// Keyboards and mice are kept together, so a player with a keyboard
+/// also gets the first free mouse, and the other way around.
+pub struct PlayerSlots {
+    slots: Box<[PlayerSlot]>,
+    join: Vec<DigitalBinding>,
+    join_held: HashSet<DeviceId>,
+}
+
+impl PlayerSlots {
+    pub fn new(bindings: BindingMap, players: usize, join: Vec<DigitalBinding>) -> Self {
+        let slots = (0..players)
+            .map(|_| PlayerSlot {
+                manager: InputManager::new(bindings.clone()),
+                devices: vec![],
+                status: SlotStatus::Empty,
+                lost_family: None,
+            })
+            .collect();
+
+        return Self {
+            slots,
+            join,
+            join_held: HashSet::new(),
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.slots.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.slots.is_empty();
+    }
+
+    pub fn player(&self, player: usize) -> Option<&InputManager> {
+        return self.slots.get(player).map(|it| &it.manager);
+    }
+
+    pub fn player_mut(&mut self, player: usize) -> Option<&mut InputManager> {
+        return self.slots.get_mut(player).map(|it| &mut it.manager);
+    }
+
+    pub fn status(&self, player: usize) -> Option<SlotStatus> {
+        return self.slots.get(player).map(|it| it.status);
+    }
+
+    /// The player that a device belongs to.
+    pub fn player_of(&self, device: DeviceId) -> Option<usize> {
+        return self
+            .slots
+            .iter()
+            .position(|slot| slot.devices.iter().any(|it| it.0 == device));
+    }
+
+    /// The devices that belong to a player.
+    pub fn devices(&self, player: usize) -> Option<&[DeviceId]> {
+        return self.slots.get(player).map(|it| it.manager.devices());
+    }
+
+    /// Puts a device in the next free slot, as if the join binding was pressed on it.
+    /// Returns `None` if the device already belongs to a player, isn't connected, or every slot is taken.
+    pub fn join(&mut self, state: &InputState, device: DeviceId) -> Option<PlayerEvent> {
+        if self.player_of(device).is_some() {
+            return None;
+        }
+
+        let family = state.device_family(&device)?;
+
+        let is_pointer = |it: InputFamily| it == InputFamily::Keyboard || it == InputFamily::Mouse;
+
+        let reconnect = self.slots.iter().position(|slot| {
+            slot.status == SlotStatus::Disconnected
+                && slot
+                    .lost_family
+                    .is_some_and(|it| it == family || (is_pointer(it) && is_pointer(family)))
+        });
+
+        if let Some(player) = reconnect {
+            let slot = &mut self.slots[player];
+
+            slot.add_device(device, family);
+            slot.status = SlotStatus::Joined;
+            slot.lost_family = None;
+
+            return Some(PlayerEvent::Reconnected { player, device });
+        }
+
+        let player = self
+            .slots
+            .iter()
+            .position(|slot| slot.status == SlotStatus::Empty)?;
+
+        let slot = &mut self.slots[player];
+
+        slot.add_device(device, family);
+        slot.status = SlotStatus::Joined;
+
+        return Some(PlayerEvent::Joined { player, device });
+    }
+
+    /// Frees a player's slot, and releases their devices.
+    pub fn leave(&mut self, player: usize) -> Option<PlayerEvent> {
+        let slot = self.slots.get_mut(player)?;
+
+        if slot.status == SlotStatus::Empty {
+            return None;
+        }
+
+        for (device, _) in slot.devices.drain(..) {
+            slot.manager.disconnect_device(device);
+        }
+
+        slot.status = SlotStatus::Empty;
+        slot.lost_family = None;
+
+        return Some(PlayerEvent::Left { player });
+    }
+
+    /// Swaps two players, along with their devices and input state.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        if a >= self.slots.len() || b >= self.slots.len() {
+            return;
+        }
+
+        self.slots.swap(a, b);
+    }
+
+    /// Handles disconnects and joins, then updates every player's `InputManager`.
+    pub fn update(&mut self, state: &InputState, delta_time: f32) -> Vec<PlayerEvent> {
+        let mut events = vec![];
+
+        for (player, slot) in self.slots.iter_mut().enumerate() {
+            let mut lost = None;
+
+            slot.devices.retain(|(device, family)| {
+                if state.device_family(device).is_some() {
+                    return true;
+                }
+
+                slot.manager.disconnect_device(*device);
+                lost = Some(*family);
+
+                return false;
+            });
+
+            if slot.status == SlotStatus::Joined && slot.devices.is_empty() {
+                slot.status = SlotStatus::Disconnected;
+                slot.lost_family = lost;
+
+                events.push(PlayerEvent::Disconnected { player });
+            }
+        }
+
+        let mut devices = state
+            .raw_devices()
+            .map(|(device, family, _, _)| (device, family))
+            .collect::<Vec<_>>();
+        devices.sort();
+
+        self.join_held
+            .retain(|it| devices.iter().any(|(d, _)| d == it));
+
+        for (device, _) in &devices {
+            let held = state.get_digital(device, &self.join, &[], &[], &MouseReads::default())
+                == Some(true);
+
+            if !held {
+                self.join_held.remove(device);
+                continue;
+            }
+
+            if !self.join_held.insert(*device) {
+                continue;
+            }
+
+            if let Some(event) = self.join(state, *device) {
+                events.push(event);
+            }
+        }
+
+        let mut assigned = self
+            .slots
+            .iter()
+            .flat_map(|slot| slot.devices.iter().map(|it| it.0))
+            .collect::<HashSet<_>>();
+
+        for slot in &mut self.slots {
+            if slot.status != SlotStatus::Joined {
+                continue;
+            }
+
+            for (has, needs) in [
+                (InputFamily::Keyboard, InputFamily::Mouse),
+                (InputFamily::Mouse, InputFamily::Keyboard),
+            ] {
+                if !slot.has_family(has) || slot.has_family(needs) {
+                    continue;
+                }
+
+                let free = devices
+                    .iter()
+                    .find(|(device, family)| *family == needs && !assigned.contains(device));
+
+                let Some((device, family)) = free else {
+                    continue;
+                };
+
+                slot.add_device(*device, *family);
+                assigned.insert(*device);
+            }
+        }
+
+        for slot in &mut self.slots {
+            slot.manager.update(state, delta_time);
+        }
+
+        return events;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::{
+        BindingList, BoundButton,
+        button::{GamepadButton, KeyboardButton},
+    };
+
+    fn slots() -> PlayerSlots {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            pawkit_interner::InternString::new("jump"),
+            BindingList::Digital(vec![DigitalBinding::Gamepad(BoundButton::Digital(
+                GamepadButton::South,
+            ))]),
+        );
+
+        return PlayerSlots::new(
+            bindings,
+            2,
+            vec![
+                DigitalBinding::Gamepad(BoundButton::Digital(GamepadButton::Start)),
+                DigitalBinding::Keyboard(BoundButton::Digital(KeyboardButton::Enter)),
+            ],
+        );
+    }
+
+    #[test]
+    fn join_on_press() {
+        let mut state = InputState::new();
+        let mut slots = slots();
+
+        let first = state.connect_gamepad();
+        let second = state.connect_gamepad();
+
+        assert!(slots.update(&state, 0.1).is_empty());
+
+        state.set_gamepad_button(&second, GamepadButton::Start, true);
+
+        assert_eq!(
+            slots.update(&state, 0.1),
+            [PlayerEvent::Joined {
+                player: 0,
+                device: second
+            }]
+        );
+        assert!(slots.update(&state, 0.1).is_empty());
+
+        state.set_gamepad_button(&first, GamepadButton::Start, true);
+        slots.update(&state, 0.1);
+
+        assert_eq!(slots.player_of(second), Some(0));
+        assert_eq!(slots.player_of(first), Some(1));
+    }
+
+    #[test]
+    fn reconnect_keeps_slot() {
+        let mut state = InputState::new();
+        let mut slots = slots();
+
+        let gamepad = state.connect_gamepad();
+        slots.join(&state, gamepad);
+
+        state.disconnect_device(&gamepad);
+
+        assert_eq!(
+            slots.update(&state, 0.1),
+            [PlayerEvent::Disconnected { player: 0 }]
+        );
+
+        let keyboard = state.connect_keyboard();
+        slots.join(&state, keyboard);
+
+        assert_eq!(slots.player_of(keyboard), Some(1));
+
+        let gamepad = state.connect_gamepad();
+
+        assert_eq!(
+            slots.join(&state, gamepad),
+            Some(PlayerEvent::Reconnected {
+                player: 0,
+                device: gamepad
+            })
+        );
+    }
+
+    #[test]
+    fn keyboard_and_mouse_stay_together() {
+        let mut state = InputState::new();
+        let mut slots = slots();
+
+        let keyboard = state.connect_keyboard();
+        let mouse = state.connect_mouse();
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Enter, true);
+        slots.update(&state, 0.1);
+
+        assert_eq!(slots.player_of(keyboard), Some(0));
+        assert_eq!(slots.player_of(mouse), Some(0));
+    }
+}
@@ -0,0 +1,742 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use num_enum::TryFromPrimitive;
+use pawkit_crockford::Ulid;
+use pawkit_fs::VfsBuffer;
+use pawkit_interner::InternString;
+use thiserror::Error;
+
+use crate::{
+    DeviceId,
+    binding::{
+        axis::{GamepadAxis, MouseAxis, VirtualAxis},
+        button::{GamepadButton, KeyboardButton, MouseButton, VirtualButton},
+    },
+    history::ActionValue,
+    motion::{GyroCalibration, MotionSample},
+    state::InputState,
+    touch::{TouchContact, TouchSettings},
+    virtual_device::VirtualLayout,
+};
+
+const MAGIC: &[u8; 4] = b"PKIR";
+const VERSION: u8 = 1;
+
+#[derive(Error, Debug)]
+pub enum RecordingError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("Not an input recording")]
+    InvalidHeader,
+    #[error("Unsupported recording version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Malformed recording")]
+    Malformed,
+}
+
+/// A single change made to an `InputState`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    ConnectKeyboard(DeviceId),
+    ConnectMouse(DeviceId),
+    ConnectGamepad(DeviceId),
+    Disconnect(DeviceId),
+    KeyboardButton(DeviceId, KeyboardButton, bool),
+    MouseButton(DeviceId, MouseButton, bool),
+    GamepadButton(DeviceId, GamepadButton, bool),
+    MouseAxis(DeviceId, MouseAxis, f32),
+    GamepadAxis(DeviceId, GamepadAxis, f32),
+    ConnectTouch(DeviceId),
+    TouchContact(DeviceId, TouchContact),
+    TouchRelease(DeviceId, u64),
+    TouchSettings(DeviceId, TouchSettings),
+    GamepadMotion(DeviceId, MotionSample),
+    GyroCalibration(DeviceId, GyroCalibration),
+    BeginGyroCalibration(DeviceId),
+    EndGyroCalibration(DeviceId),
+    TextInputActive(DeviceId, bool),
+    MouseViewport(DeviceId, [f32; 2]),
+    /// The physical key, then the key printed on it.
+    KeyboardLayoutKey(DeviceId, KeyboardButton, KeyboardButton),
+    ConnectVirtual(DeviceId, VirtualLayout),
+    VirtualButton(DeviceId, VirtualButton, bool),
+    VirtualAxis(DeviceId, VirtualAxis, f32),
+    SetVirtualAction(DeviceId, InternString, ActionValue),
+    ClearVirtualAction(DeviceId, InternString),
+}
+
+impl InputEvent {
+    pub fn device(&self) -> DeviceId {
+        return match self {
+            Self::ConnectKeyboard(device)
+            | Self::ConnectMouse(device)
+            | Self::ConnectGamepad(device)
+            | Self::Disconnect(device)
+            | Self::KeyboardButton(device, _, _)
+            | Self::MouseButton(device, _, _)
+            | Self::GamepadButton(device, _, _)
+            | Self::MouseAxis(device, _, _)
+            | Self::GamepadAxis(device, _, _)
+            | Self::ConnectTouch(device)
+            | Self::TouchContact(device, _)
+            | Self::TouchRelease(device, _)
+            | Self::TouchSettings(device, _)
+            | Self::GamepadMotion(device, _)
+            | Self::GyroCalibration(device, _)
+            | Self::BeginGyroCalibration(device)
+            | Self::EndGyroCalibration(device)
+            | Self::TextInputActive(device, _)
+            | Self::MouseViewport(device, _)
+            | Self::KeyboardLayoutKey(device, _, _)
+            | Self::ConnectVirtual(device, _)
+            | Self::VirtualButton(device, _, _)
+            | Self::VirtualAxis(device, _, _)
+            | Self::SetVirtualAction(device, _, _)
+            | Self::ClearVirtualAction(device, _) => *device,
+        };
+    }
+
+    pub fn apply(&self, state: &mut InputState) {
+        match *self {
+            Self::ConnectKeyboard(device) => state.connect_keyboard_with_id(device),
+            Self::ConnectMouse(device) => state.connect_mouse_with_id(device),
+            Self::ConnectGamepad(device) => state.connect_gamepad_with_id(device),
+            Self::Disconnect(device) => {
+                state.disconnect_device(&device);
+            }
+            Self::KeyboardButton(device, button, value) => {
+                state.set_keyboard_button(&device, button, value);
+            }
+            Self::MouseButton(device, button, value) => {
+                state.set_mouse_button(&device, button, value);
+            }
+            Self::GamepadButton(device, button, value) => {
+                state.set_gamepad_button(&device, button, value);
+            }
+            Self::MouseAxis(device, axis, value) => {
+                state.set_mouse_axis(&device, axis, value);
+            }
+            Self::GamepadAxis(device, axis, value) => {
+                state.set_gamepad_axis(&device, axis, value);
+            }
+            Self::ConnectTouch(device) => state.connect_touch_with_id(device),
+            Self::TouchContact(device, contact) => {
+                state.set_touch_contact(&device, contact);
+            }
+            Self::TouchRelease(device, id) => {
+                state.remove_touch_contact(&device, id);
+            }
+            Self::TouchSettings(device, settings) => {
+                state.set_touch_settings(&device, settings);
+            }
+            Self::GamepadMotion(device, sample) => {
+                state.set_gamepad_motion(&device, sample);
+            }
+            Self::GyroCalibration(device, calibration) => {
+                state.set_gyro_calibration(&device, calibration);
+            }
+            Self::BeginGyroCalibration(device) => {
+                state.begin_gyro_calibration(&device);
+            }
+            Self::EndGyroCalibration(device) => {
+                state.end_gyro_calibration(&device);
+            }
+            Self::TextInputActive(device, active) => {
+                state.set_text_input_active(&device, active);
+            }
+            Self::MouseViewport(device, size) => {
+                state.set_mouse_viewport(&device, size);
+            }
+            Self::KeyboardLayoutKey(device, physical, logical) => {
+                state.set_keyboard_key(&device, physical, logical);
+            }
+            Self::ConnectVirtual(device, layout) => state.connect_virtual_with_id(device, layout),
+            Self::VirtualButton(device, button, value) => {
+                state.set_virtual_button(&device, button, value);
+            }
+            Self::VirtualAxis(device, axis, value) => {
+                state.set_virtual_axis(&device, axis, value);
+            }
+            Self::SetVirtualAction(device, ref name, value) => {
+                state.set_virtual_action(&device, name.clone(), value);
+            }
+            Self::ClearVirtualAction(device, ref name) => {
+                state.clear_virtual_action(&device, name);
+            }
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        return match self {
+            Self::ConnectKeyboard(_) => 0,
+            Self::ConnectMouse(_) => 1,
+            Self::ConnectGamepad(_) => 2,
+            Self::Disconnect(_) => 3,
+            Self::KeyboardButton(_, _, false) => 4,
+            Self::KeyboardButton(_, _, true) => 5,
+            Self::MouseButton(_, _, false) => 6,
+            Self::MouseButton(_, _, true) => 7,
+            Self::GamepadButton(_, _, false) => 8,
+            Self::GamepadButton(_, _, true) => 9,
+            Self::MouseAxis(_, _, _) => 10,
+            Self::GamepadAxis(_, _, _) => 11,
+            Self::ConnectTouch(_) => 12,
+            Self::TouchContact(_, _) => 13,
+            Self::TouchRelease(_, _) => 14,
+            Self::TouchSettings(_, _) => 15,
+            Self::GamepadMotion(_, _) => 16,
+            Self::GyroCalibration(_, _) => 17,
+            Self::BeginGyroCalibration(_) => 18,
+            Self::EndGyroCalibration(_) => 19,
+            Self::TextInputActive(_, false) => 20,
+            Self::TextInputActive(_, true) => 21,
+            Self::MouseViewport(_, _) => 22,
+            Self::KeyboardLayoutKey(_, _, _) => 23,
+            Self::ConnectVirtual(_, _) => 24,
+            Self::VirtualButton(_, _, false) => 25,
+            Self::VirtualButton(_, _, true) => 26,
+            Self::VirtualAxis(_, _, _) => 27,
+            Self::SetVirtualAction(_, _, _) => 28,
+            Self::ClearVirtualAction(_, _) => 29,
+        };
+    }
+}
+
+/// Every change made to an `InputState` while it was recording, tagged with the frame it happened on.
+///
+/// The binary format is a header followed by one entry per event:
+/// a varint frame delta, a tag byte, then the device and payload.
+/// Devices are written in full when they connect, and by their connection index afterwards.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InputRecording {
+    events: Vec<(u64, InputEvent)>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        return Self { events: vec![] };
+    }
+
+    pub(crate) fn push(&mut self, frame: u64, event: InputEvent) {
+        self.events.push((frame, event));
+    }
+
+    pub fn events(&self) -> &[(u64, InputEvent)] {
+        return &self.events;
+    }
+
+    /// The number of frames the recording spans.
+    pub fn frame_count(&self) -> u64 {
+        return self.events.last().map(|it| it.0 + 1).unwrap_or(0);
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        return writer.write_all(&self.save());
+    }
+
+    pub fn save(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+
+        let mut devices = HashMap::<DeviceId, u64>::new();
+        let mut last_frame = 0;
+
+        for (frame, event) in &self.events {
+            let device = event.device();
+
+            let index = match event {
+                InputEvent::ConnectKeyboard(_)
+                | InputEvent::ConnectMouse(_)
+                | InputEvent::ConnectGamepad(_)
+                | InputEvent::ConnectTouch(_)
+                | InputEvent::ConnectVirtual(_, _) => None,
+
+                // Events on devices that were never connected are no-ops, so they're dropped.
+                _ => match devices.get(&device) {
+                    Some(index) => Some(*index),
+                    None => continue,
+                },
+            };
+
+            write_varint(&mut bytes, frame - last_frame);
+            last_frame = *frame;
+
+            bytes.push(event.tag());
+
+            let Some(index) = index else {
+                bytes.extend_from_slice(&<[u8; 16]>::from(device.0));
+
+                if let InputEvent::ConnectVirtual(_, layout) = event {
+                    bytes.push(layout.buttons);
+                    bytes.push(layout.axes);
+                }
+
+                let index = devices.len() as u64;
+                devices.insert(device, index);
+                continue;
+            };
+
+            write_varint(&mut bytes, index);
+
+            match *event {
+                InputEvent::KeyboardButton(_, button, _) => bytes.push(button as u8),
+                InputEvent::MouseButton(_, button, _) => bytes.push(button as u8),
+                InputEvent::GamepadButton(_, button, _) => bytes.push(button as u8),
+                InputEvent::VirtualButton(_, button, _) => bytes.push(button.0),
+                InputEvent::MouseAxis(_, axis, value) => {
+                    bytes.push(axis as u8);
+                    bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                }
+                InputEvent::GamepadAxis(_, axis, value) => {
+                    bytes.push(axis as u8);
+                    bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                }
+                InputEvent::VirtualAxis(_, axis, value) => {
+                    bytes.push(axis.0);
+                    bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                }
+                InputEvent::TouchContact(_, contact) => {
+                    write_varint(&mut bytes, contact.id);
+
+                    for value in [contact.position[0], contact.position[1], contact.pressure] {
+                        bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                    }
+                }
+                InputEvent::TouchRelease(_, id) => write_varint(&mut bytes, id),
+                InputEvent::TouchSettings(_, settings) => {
+                    for value in [settings.tap_distance, settings.swipe_distance] {
+                        bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                    }
+
+                    write_varint(&mut bytes, settings.max_tap_frames as u64);
+                }
+                InputEvent::GamepadMotion(_, sample) => {
+                    write_varint(&mut bytes, sample.timestamp);
+
+                    for value in sample.gyro.into_iter().chain(sample.accel) {
+                        bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                    }
+                }
+                InputEvent::KeyboardLayoutKey(_, physical, logical) => {
+                    bytes.push(physical as u8);
+                    bytes.push(logical as u8);
+                }
+                InputEvent::MouseViewport(_, size) => {
+                    for value in size {
+                        bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                    }
+                }
+                InputEvent::SetVirtualAction(_, ref name, value) => {
+                    write_string(&mut bytes, name);
+
+                    match value {
+                        ActionValue::Digital(pressed) => bytes.extend([0, pressed as u8]),
+                        ActionValue::Analog(value) => {
+                            bytes.push(1);
+                            bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                        }
+                        ActionValue::Vector(value) => {
+                            bytes.push(2);
+
+                            for value in value {
+                                bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                            }
+                        }
+                    }
+                }
+                InputEvent::ClearVirtualAction(_, ref name) => write_string(&mut bytes, name),
+                InputEvent::GyroCalibration(_, calibration) => {
+                    for value in calibration
+                        .offset
+                        .into_iter()
+                        .chain([calibration.rest_threshold, calibration.drift_rate])
+                    {
+                        bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        return bytes;
+    }
+
+    pub fn to_buffer(&self) -> VfsBuffer {
+        return VfsBuffer::from(self.save());
+    }
+
+    /// Reads a recording from any reader, such as a `VfsBuffer`.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, RecordingError> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+
+        return Self::load(&bytes);
+    }
+
+    pub fn load(bytes: &[u8]) -> Result<Self, RecordingError> {
+        let Some(bytes) = bytes.strip_prefix(MAGIC) else {
+            return Err(RecordingError::InvalidHeader);
+        };
+
+        let Some((&version, bytes)) = bytes.split_first() else {
+            return Err(RecordingError::InvalidHeader);
+        };
+
+        if version != VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+
+        let mut reader = ByteReader { bytes };
+        let mut devices = vec![];
+        let mut events = vec![];
+        let mut frame = 0u64;
+
+        while !reader.bytes.is_empty() {
+            frame = frame
+                .checked_add(reader.varint()?)
+                .ok_or(RecordingError::Malformed)?;
+
+            let tag = reader.byte()?;
+
+            if matches!(tag, 0..=2 | 12 | 24) {
+                let device = DeviceId(Ulid::from(reader.array::<16>()?));
+                devices.push(device);
+
+                let event = match tag {
+                    0 => InputEvent::ConnectKeyboard(device),
+                    1 => InputEvent::ConnectMouse(device),
+                    2 => InputEvent::ConnectGamepad(device),
+                    12 => InputEvent::ConnectTouch(device),
+                    _ => InputEvent::ConnectVirtual(
+                        device,
+                        VirtualLayout {
+                            buttons: reader.byte()?,
+                            axes: reader.byte()?,
+                        },
+                    ),
+                };
+
+                events.push((frame, event));
+                continue;
+            }
+
+            let device = *devices
+                .get(reader.varint()? as usize)
+                .ok_or(RecordingError::Malformed)?;
+
+            let event = match tag {
+                3 => InputEvent::Disconnect(device),
+                4 | 5 => InputEvent::KeyboardButton(device, reader.primitive()?, tag == 5),
+                6 | 7 => InputEvent::MouseButton(device, reader.primitive()?, tag == 7),
+                8 | 9 => InputEvent::GamepadButton(device, reader.primitive()?, tag == 9),
+                10 => InputEvent::MouseAxis(device, reader.primitive()?, reader.float()?),
+                11 => InputEvent::GamepadAxis(device, reader.primitive()?, reader.float()?),
+                13 => InputEvent::TouchContact(
+                    device,
+                    TouchContact {
+                        id: reader.varint()?,
+                        position: [reader.float()?, reader.float()?],
+                        pressure: reader.float()?,
+                    },
+                ),
+                14 => InputEvent::TouchRelease(device, reader.varint()?),
+                15 => InputEvent::TouchSettings(
+                    device,
+                    TouchSettings {
+                        tap_distance: reader.float()?,
+                        swipe_distance: reader.float()?,
+                        max_tap_frames: reader.varint()? as u32,
+                    },
+                ),
+                16 => InputEvent::GamepadMotion(
+                    device,
+                    MotionSample {
+                        timestamp: reader.varint()?,
+                        gyro: [reader.float()?, reader.float()?, reader.float()?],
+                        accel: [reader.float()?, reader.float()?, reader.float()?],
+                    },
+                ),
+                17 => InputEvent::GyroCalibration(
+                    device,
+                    GyroCalibration {
+                        offset: [reader.float()?, reader.float()?, reader.float()?],
+                        rest_threshold: reader.float()?,
+                        drift_rate: reader.float()?,
+                    },
+                ),
+                18 => InputEvent::BeginGyroCalibration(device),
+                19 => InputEvent::EndGyroCalibration(device),
+                20 | 21 => InputEvent::TextInputActive(device, tag == 21),
+                22 => InputEvent::MouseViewport(device, [reader.float()?, reader.float()?]),
+                23 => {
+                    InputEvent::KeyboardLayoutKey(device, reader.primitive()?, reader.primitive()?)
+                }
+                25 | 26 => {
+                    InputEvent::VirtualButton(device, VirtualButton(reader.byte()?), tag == 26)
+                }
+                27 => InputEvent::VirtualAxis(device, VirtualAxis(reader.byte()?), reader.float()?),
+                28 => {
+                    let name = reader.string()?;
+
+                    let value = match reader.byte()? {
+                        0 => ActionValue::Digital(reader.byte()? != 0),
+                        1 => ActionValue::Analog(reader.float()?),
+                        2 => ActionValue::Vector([reader.float()?, reader.float()?]),
+                        _ => return Err(RecordingError::Malformed),
+                    };
+
+                    InputEvent::SetVirtualAction(device, name, value)
+                }
+                29 => InputEvent::ClearVirtualAction(device, reader.string()?),
+                _ => return Err(RecordingError::Malformed),
+            };
+
+            events.push((frame, event));
+        }
+
+        return Ok(Self { events });
+    }
+}
+
+/// Plays an `InputRecording` back into an `InputState`, one frame at a time.
+///
+/// Replaying into a fresh `InputState`, and updating the `InputManager` with the same delta times
+/// as when recording, produces identical frames.
+#[derive(Debug, Clone)]
+pub struct InputReplay {
+    recording: InputRecording,
+    cursor: usize,
+    frame: u64,
+}
+
+impl InputReplay {
+    pub fn new(recording: InputRecording) -> Self {
+        return Self {
+            recording,
+            cursor: 0,
+            frame: 0,
+        };
+    }
+
+    pub fn frame(&self) -> u64 {
+        return self.frame;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.cursor >= self.recording.events.len();
+    }
+
+    /// Applies every event recorded on the current frame, and moves on to the next frame.
+    pub fn step(&mut self, state: &mut InputState) {
+        if self.frame > 0 {
+            state.next_frame();
+        }
+
+        while let Some((frame, event)) = self.recording.events.get(self.cursor) {
+            if *frame > self.frame {
+                break;
+            }
+
+            event.apply(state);
+            self.cursor += 1;
+        }
+
+        self.frame += 1;
+    }
+}
+
+pub(crate) fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// A varint length, then the UTF-8 bytes.
+fn write_string(bytes: &mut Vec<u8>, value: &str) {
+    write_varint(bytes, value.len() as u64);
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+pub(crate) struct ByteReader<'a> {
+    pub(crate) bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn byte(&mut self) -> Result<u8, RecordingError> {
+        let Some((&byte, rest)) = self.bytes.split_first() else {
+            return Err(RecordingError::Malformed);
+        };
+
+        self.bytes = rest;
+
+        return Ok(byte);
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], RecordingError> {
+        let Some((array, rest)) = self.bytes.split_first_chunk::<N>() else {
+            return Err(RecordingError::Malformed);
+        };
+
+        self.bytes = rest;
+
+        return Ok(*array);
+    }
+
+    pub(crate) fn varint(&mut self) -> Result<u64, RecordingError> {
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+
+            value |= ((byte & 0x7F) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        return Err(RecordingError::Malformed);
+    }
+
+    pub(crate) fn float(&mut self) -> Result<f32, RecordingError> {
+        return Ok(f32::from_bits(u32::from_le_bytes(self.array()?)));
+    }
+
+    pub(crate) fn string(&mut self) -> Result<InternString, RecordingError> {
+        let len = usize::try_from(self.varint()?).map_err(|_| RecordingError::Malformed)?;
+
+        let Some((bytes, rest)) = self.bytes.split_at_checked(len) else {
+            return Err(RecordingError::Malformed);
+        };
+
+        self.bytes = rest;
+
+        let value = str::from_utf8(bytes).map_err(|_| RecordingError::Malformed)?;
+
+        return Ok(InternString::new(value));
+    }
+
+    pub(crate) fn primitive<T: TryFromPrimitive<Primitive = u8>>(
+        &mut self,
+    ) -> Result<T, RecordingError> {
+        return T::try_from_primitive(self.byte()?).map_err(|_| RecordingError::Malformed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{BindingList, BoundAxis, BoundButton, DigitalBinding, VectorBinding},
+        binding::{VectorBindingKind, map::BindingMap},
+        manager::{InputFrame, InputManager},
+    };
+    use pawkit_interner::InternString;
+
+    fn bindings() -> BindingMap {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("jump"),
+            BindingList::Digital(vec![
+                DigitalBinding::Keyboard(BoundButton::Digital(KeyboardButton::Space)),
+                DigitalBinding::Gamepad(BoundButton::Digital(GamepadButton::South)),
+            ]),
+        );
+        bindings.register_binding(
+            InternString::new("move"),
+            BindingList::Vector(vec![VectorBinding {
+                axes: VectorBindingKind::Gamepad {
+                    x: BoundAxis::Analog(GamepadAxis::LeftX),
+                    y: BoundAxis::Analog(GamepadAxis::LeftY),
+                },
+                deadzone: 0.1,
+                scale: (1.0, 1.0),
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+                ratchet: None,
+            }]),
+        );
+
+        return bindings;
+    }
+
+    fn frames(manager: &InputManager) -> [Option<InputFrame>; 2] {
+        return [
+            manager.get_binding(&InternString::new("jump")),
+            manager.get_binding(&InternString::new("move")),
+        ];
+    }
+
+    #[test]
+    fn replay_matches_recording() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        state.start_recording();
+
+        let gamepad = state.connect_gamepad();
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(keyboard);
+        manager.connect_device(gamepad);
+
+        let mut expected = vec![];
+
+        for i in 0..8 {
+            state.set_keyboard_button(&keyboard, KeyboardButton::Space, i % 3 == 0);
+            state.set_gamepad_button(&gamepad, GamepadButton::South, i % 2 == 0);
+            state.set_gamepad_axis(&gamepad, GamepadAxis::LeftX, i as f32 * 0.13);
+            state.set_gamepad_axis(&gamepad, GamepadAxis::LeftY, -0.7);
+
+            manager.update(&state, 1.0 / 60.0);
+            expected.push(frames(&manager));
+
+            state.next_frame();
+        }
+
+        let recording = state.stop_recording().unwrap();
+        let recording = InputRecording::read(&mut recording.to_buffer()).unwrap();
+
+        let mut state = InputState::new();
+        let mut replay = InputReplay::new(recording);
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(keyboard);
+        manager.connect_device(gamepad);
+
+        for expected in expected {
+            replay.step(&mut state);
+            manager.update(&state, 1.0 / 60.0);
+
+            assert_eq!(frames(&manager), expected);
+        }
+
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(matches!(
+            InputRecording::load(b"nope"),
+            Err(RecordingError::InvalidHeader)
+        ));
+        assert!(matches!(
+            InputRecording::load(b"PKIR\x01\x00\x04"),
+            Err(RecordingError::Malformed)
+        ));
+    }
+}
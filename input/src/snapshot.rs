@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+
+use bitvec::{order::Lsb0, vec::BitVec};
+use pawkit_interner::InternString;
+use thiserror::Error;
+
+use crate::{
+    binding::{BindingKind, map::BindingMap},
+    manager::{
+        AnalogInputFrame, DigitalInputFrame, InputFrame, InputManager, RawInputFrame,
+        VectorInputFrame,
+    },
+    recording::{ByteReader, RecordingError, write_varint},
+};
+
+/// The number of bits each digital action takes in a snapshot.
+const DIGITAL_BITS: usize = 9;
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("Snapshot has {found} actions, but the layout has {expected}")]
+    LayoutMismatch { expected: usize, found: usize },
+    #[error("Snapshot was encoded with a layout whose actions have different names or kinds")]
+    ChecksumMismatch,
+    #[error("Malformed snapshot")]
+    Malformed,
+}
+
+impl From<RecordingError> for SnapshotError {
+    fn from(_: RecordingError) -> Self {
+        return Self::Malformed;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapshotOptions {
+    /// The smallest change in an analog value that's sent. Values are rounded to a multiple of this.
+    pub analog_precision: f32,
+    /// The smallest change in each component of a vector value that's sent.
+    pub vector_precision: f32,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        return Self {
+            analog_precision: 1f32 / 1024f32,
+            vector_precision: 1f32 / 1024f32,
+        };
+    }
+}
+
+/// The order actions are sent in, which both sides of a connection need to agree on.
+/// Actions are sorted by name, so the same bindings always give the same layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionLayout {
+    actions: Vec<(InternString, BindingKind)>,
+}
+
+impl ActionLayout {
+    pub fn new(bindings: &BindingMap) -> Self {
+        return Self::from_actions(
+            bindings
+                .into_iter()
+                .filter_map(|(name, _)| Some((name.clone(), bindings.get_binding_kind(name)?)))
+                .collect(),
+        );
+    }
+
+    /// Every action in the manager, including the ones in its contexts.
+    pub fn from_manager(manager: &InputManager) -> Self {
+        return Self::from_actions(manager.actions());
+    }
+
+    /// When several actions share a name, the first one's kind is kept.
+    fn from_actions(mut actions: Vec<(InternString, BindingKind)>) -> Self {
+        actions.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        actions.dedup_by(|a, b| a.0 == b.0);
+
+        return Self { actions };
+    }
+
+    /// A hash of every action's name and kind, so snapshots from a different layout
+    /// with the same number of actions aren't decoded into the wrong actions.
+    pub fn checksum(&self) -> u32 {
+        // FNV-1a, which is the same on every platform and build.
+        let mut hash = 0x811c9dc5u32;
+
+        for (name, kind) in &self.actions {
+            for byte in name.as_str().bytes().chain([0, *kind as u8]) {
+                hash ^= byte as u32;
+                hash = hash.wrapping_mul(0x01000193);
+            }
+        }
+
+        return hash;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.actions.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.actions.is_empty();
+    }
+
+    /// The stable index of an action, which is its position in the snapshot.
+    pub fn index_of(&self, name: &InternString) -> Option<usize> {
+        return self.actions.iter().position(|(it, _)| it == name);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&InternString, BindingKind)> {
+        return self.actions.iter().map(|(name, kind)| (name, *kind));
+    }
+
+    /// Encodes the current frame of every action in the layout.
+    /// Actions the manager doesn't have, or that have a different kind, are sent as released.
+    pub fn encode(&self, manager: &InputManager, options: &SnapshotOptions) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut digital = BitVec::<u8, Lsb0>::new();
+        let mut analog = vec![];
+
+        write_varint(&mut bytes, self.actions.len() as u64);
+        bytes.extend_from_slice(&self.checksum().to_le_bytes());
+
+        for (name, kind) in &self.actions {
+            let frame = manager
+                .get_binding(name)
+                .filter(|it| it.kind() == *kind)
+                .unwrap_or_else(|| InputFrame::released(*kind));
+
+            match frame {
+                InputFrame::Digital(frame) => digital.extend(frame.flags()),
+
+                InputFrame::Analog(frame) => {
+                    for value in [frame.value, frame.delta] {
+                        write_quantized(&mut analog, value, options.analog_precision);
+                    }
+                }
+
+                InputFrame::Vector(frame) => {
+                    for value in frame.value.into_iter().chain(frame.delta) {
+                        write_quantized(&mut analog, value, options.vector_precision);
+                    }
+                }
+            }
+        }
+
+        bytes.extend_from_slice(digital.as_raw_slice());
+        bytes.extend_from_slice(&analog);
+
+        return bytes;
+    }
+}
+
+fn write_quantized(bytes: &mut Vec<u8>, value: f32, precision: f32) {
+    let value = (value / precision).round() as i64;
+
+    // Zigzag encoding keeps small negative values small.
+    write_varint(bytes, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+fn read_quantized(reader: &mut ByteReader, precision: f32) -> Result<f32, SnapshotError> {
+    let value = reader.varint()?;
+    let value = (value >> 1) as i64 ^ -((value & 1) as i64);
+
+    return Ok(value as f32 * precision);
+}
+
+/// The actions of a player on another machine, decoded from the snapshots they send.
+pub struct RemoteInputManager {
+    layout: ActionLayout,
+    options: SnapshotOptions,
+    frames: Vec<InputFrame>,
+    indices: HashMap<InternString, usize>,
+}
+
+impl RemoteInputManager {
+    pub fn new(layout: ActionLayout, options: SnapshotOptions) -> Self {
+        return Self {
+            frames: layout
+                .iter()
+                .map(|(_, it)| InputFrame::released(it))
+                .collect(),
+            indices: layout
+                .iter()
+                .enumerate()
+                .map(|(i, (name, _))| (name.clone(), i))
+                .collect(),
+            layout,
+            options,
+        };
+    }
+
+    pub fn layout(&self) -> &ActionLayout {
+        return &self.layout;
+    }
+
+    /// Replaces every frame with the ones in a snapshot.
+    /// The frames are left alone if the snapshot can't be decoded.
+    pub fn apply(&mut self, bytes: &[u8]) -> Result<(), SnapshotError> {
+        let mut reader = ByteReader { bytes };
+
+        let count = reader.varint()? as usize;
+
+        if count != self.layout.len() {
+            return Err(SnapshotError::LayoutMismatch {
+                expected: self.layout.len(),
+                found: count,
+            });
+        }
+
+        if u32::from_le_bytes(reader.array()?) != self.layout.checksum() {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+
+        let digital_count = self
+            .layout
+            .iter()
+            .filter(|(_, it)| *it == BindingKind::Digital)
+            .count();
+
+        let digital_bytes = (digital_count * DIGITAL_BITS).div_ceil(8);
+
+        let Some((digital, rest)) = reader.bytes.split_at_checked(digital_bytes) else {
+            return Err(SnapshotError::Malformed);
+        };
+
+        let digital = BitVec::<u8, Lsb0>::from_slice(digital);
+        let mut digital = digital.chunks(DIGITAL_BITS);
+
+        reader.bytes = rest;
+
+        let mut frames = Vec::with_capacity(self.frames.len());
+
+        for (_, kind) in self.layout.iter() {
+            let frame = match kind {
+                BindingKind::Digital => {
+                    let flags = digital.next().ok_or(SnapshotError::Malformed)?;
+
+                    InputFrame::Digital(DigitalInputFrame::from_flags(flags.iter().map(|it| *it)))
+                }
+
+                BindingKind::Analog => {
+                    let precision = self.options.analog_precision;
+
+                    InputFrame::Analog(AnalogInputFrame {
+                        value: read_quantized(&mut reader, precision)?,
+                        delta: read_quantized(&mut reader, precision)?,
+                    })
+                }
+
+                BindingKind::Vector => {
+                    let precision = self.options.vector_precision;
+
+                    InputFrame::Vector(VectorInputFrame {
+                        value: [
+                            read_quantized(&mut reader, precision)?,
+                            read_quantized(&mut reader, precision)?,
+                        ],
+                        delta: [
+                            read_quantized(&mut reader, precision)?,
+                            read_quantized(&mut reader, precision)?,
+                        ],
+                    })
+                }
+            };
+
+            frames.push(frame);
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(SnapshotError::Malformed);
+        }
+
+        self.frames = frames;
+
+        return Ok(());
+    }
+
+    pub fn get_binding(&self, name: &InternString) -> Option<InputFrame> {
+        let index = self.indices.get(name)?;
+
+        return Some(self.frames[*index]);
+    }
+
+    pub fn get_binding_raw(&self, name: &InternString) -> Option<RawInputFrame> {
+        return Some(match self.get_binding(name)? {
+            InputFrame::Digital(digital) => RawInputFrame { digital },
+            InputFrame::Analog(analog) => RawInputFrame { analog },
+            InputFrame::Vector(vector) => RawInputFrame { vector },
+        });
+    }
+}
+
+impl DigitalInputFrame {
+    fn flags(&self) -> [bool; DIGITAL_BITS] {
+        return [
+            self.pressed,
+            self.just_pressed,
+            self.just_released,
+            self.held,
+            self.long_pressed,
+            self.tapped,
+            self.double_tapped,
+            self.repeated,
+            self.buffered,
+        ];
+    }
+
+    fn from_flags(mut flags: impl Iterator<Item = bool>) -> Self {
+        let mut next = || flags.next().unwrap_or(false);
+
+        return Self {
+            pressed: next(),
+            just_pressed: next(),
+            just_released: next(),
+            held: next(),
+            long_pressed: next(),
+            tapped: next(),
+            double_tapped: next(),
+            repeated: next(),
+            buffered: next(),
+        };
+    }
+}
+
+impl InputFrame {
+    fn kind(&self) -> BindingKind {
+        return match self {
+            Self::Digital(_) => BindingKind::Digital,
+            Self::Analog(_) => BindingKind::Analog,
+            Self::Vector(_) => BindingKind::Vector,
+        };
+    }
+
+    fn released(kind: BindingKind) -> Self {
+        return match kind {
+            BindingKind::Digital => Self::Digital(DigitalInputFrame::from_flags([].into_iter())),
+            BindingKind::Analog => Self::Analog(AnalogInputFrame {
+                value: 0f32,
+                delta: 0f32,
+            }),
+            BindingKind::Vector => Self::Vector(VectorInputFrame {
+                value: [0f32; 2],
+                delta: [0f32; 2],
+            }),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{
+            BindingList, BoundAxis, BoundButton, DigitalBinding, VectorBinding, VectorBindingKind,
+            button::KeyboardButton,
+        },
+        state::InputState,
+    };
+
+    fn bindings() -> BindingMap {
+        let mut bindings = BindingMap::new();
+
+        for (name, key) in [
+            ("jump", KeyboardButton::Space),
+            ("crouch", KeyboardButton::C),
+            ("fire", KeyboardButton::F),
+        ] {
+            bindings.register_binding(
+                InternString::new(name),
+                BindingList::Digital(vec![DigitalBinding::Keyboard(BoundButton::Digital(key))]),
+            );
+        }
+
+        bindings.register_binding(
+            InternString::new("move"),
+            BindingList::Vector(vec![VectorBinding {
+                axes: VectorBindingKind::Keyboard {
+                    x: BoundAxis::MultiDigital {
+                        negative: KeyboardButton::A,
+                        positive: KeyboardButton::D,
+                    },
+                    y: BoundAxis::MultiDigital {
+                        negative: KeyboardButton::S,
+                        positive: KeyboardButton::W,
+                    },
+                },
+                deadzone: 0f32,
+                scale: (1f32, 1f32),
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+                ratchet: None,
+            }]),
+        );
+
+        return bindings;
+    }
+
+    #[test]
+    fn layout_is_sorted_by_name() {
+        let layout = ActionLayout::new(&bindings());
+
+        let names = layout.iter().map(|(it, _)| it.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(names, ["crouch", "fire", "jump", "move"]);
+        assert_eq!(layout.index_of(&InternString::new("jump")), Some(2));
+    }
+
+    #[test]
+    fn remote_matches_local() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(keyboard);
+
+        let layout = ActionLayout::from_manager(&manager);
+        let options = SnapshotOptions::default();
+
+        let mut remote = RemoteInputManager::new(layout.clone(), options);
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        state.set_keyboard_button(&keyboard, KeyboardButton::D, true);
+        state.set_keyboard_button(&keyboard, KeyboardButton::W, true);
+        manager.update(&state, 0.1);
+
+        let snapshot = layout.encode(&manager, &options);
+
+        // The three digital actions fit in 4 bytes, next to the count, the checksum and the 4 vector components.
+        assert!(snapshot.len() <= 1 + 4 + 4 + 4 * 3);
+        assert_eq!(snapshot, layout.encode(&manager, &options));
+
+        remote.apply(&snapshot).unwrap();
+
+        for (name, _) in layout.iter() {
+            let local = manager.get_binding(name).unwrap();
+            let remote = remote.get_binding(name).unwrap();
+
+            match (local, remote) {
+                (InputFrame::Vector(local), InputFrame::Vector(remote)) => {
+                    for (local, remote) in local.value.iter().zip(remote.value) {
+                        assert!((local - remote).abs() <= options.vector_precision);
+                    }
+                }
+                (local, remote) => assert_eq!(local, remote),
+            }
+        }
+
+        let mut other = BindingMap::new();
+        other.register_binding(InternString::new("jump"), BindingList::Digital(vec![]));
+
+        let mut mismatched = RemoteInputManager::new(ActionLayout::new(&other), options);
+
+        assert!(matches!(
+            mismatched.apply(&snapshot),
+            Err(SnapshotError::LayoutMismatch {
+                expected: 1,
+                found: 4
+            })
+        ));
+        assert!(remote.apply(&snapshot[..snapshot.len() - 1]).is_err());
+
+        // Same number of actions, but one of them is named differently.
+        let mut renamed = bindings();
+        renamed.unregister_binding(&InternString::new("fire"));
+        renamed.register_binding(InternString::new("shoot"), BindingList::Digital(vec![]));
+
+        let mut renamed = RemoteInputManager::new(ActionLayout::new(&renamed), options);
+
+        assert!(matches!(
+            renamed.apply(&snapshot),
+            Err(SnapshotError::ChecksumMismatch)
+        ));
+    }
+
+    #[test]
+    fn base_bindings_win_over_contexts() {
+        let mut manager = InputManager::new(bindings());
+
+        // Contexts sort after the base, and by name, whichever order they're added in.
+        for (context, kind) in [
+            ("vehicle", BindingKind::Vector),
+            ("menu", BindingKind::Analog),
+        ] {
+            let mut bindings = BindingMap::new();
+
+            for name in ["jump", "steer"] {
+                let list = match kind {
+                    BindingKind::Vector => BindingList::Vector(vec![]),
+                    _ => BindingList::Analog(vec![]),
+                };
+
+                bindings.register_binding(InternString::new(name), list);
+            }
+
+            manager.add_context(InternString::new(context), bindings, Default::default());
+        }
+
+        let layout = ActionLayout::from_manager(&manager);
+        let kind = |name| layout.iter().find(|(it, _)| it.as_str() == name).unwrap().1;
+
+        assert_eq!(kind("jump"), BindingKind::Digital);
+        assert_eq!(kind("steer"), BindingKind::Analog);
+    }
+}
@@ -1,206 +1,1245 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{borrow::Cow, collections::HashMap, fmt::Debug, slice};
 
 use bitvec::{array::BitArray, slice::BitSlice};
+use num_enum::TryFromPrimitive;
 use pawkit_crockford::Ulid;
+use pawkit_interner::InternString;
 use serde::Serialize;
 
 use crate::{
     DeviceId,
     binding::{
-        AnalogBinding, AnalogBindingKind, BoundAxis, BoundButton, DigitalBinding, VectorBinding,
-        VectorBindingKind,
-        axis::{GamepadAxis, MouseAxis},
-        button::{GamepadButton, KeyboardButton, MouseButton},
+        AnalogBinding, AnalogBindingKind, BoundAxis, BoundButton, ChordModifiers, DigitalBinding,
+        PhysicalInput, VectorBinding, VectorBindingKind,
+        axis::{GamepadAxis, MouseAxis, VirtualAxis},
+        button::{GamepadButton, KeyboardButton, MouseButton, VirtualButton},
     },
+    capture::{CaptureOptions, InputCapture},
+    glyph::ControllerStyle,
+    haptics::{HapticEffect, HapticOutput, HapticQueue},
+    history::ActionValue,
+    layout::KeyboardLayout,
     length_squared,
+    motion::{GyroCalibration, MotionSample, MotionTracker},
+    recording::{InputEvent, InputRecording},
+    text::{TextComposition, TextEvent, TextInput},
+    touch::{TouchContact, TouchSettings, TouchTracker},
+    virtual_device::{VirtualDevice, VirtualLayout},
 };
 
 enum DeviceState {
-    Keyboard(BitArray<[u8; 15]>),
-    Mouse(BitArray<[u8; 1]>, [f32; 4]),
-    Gamepad(BitArray<[u8; 4]>, [f32; 6]),
+    Keyboard(BitArray<[u8; 15]>, Box<TextInput>, Box<KeyboardLayout>),
+    Mouse(BitArray<[u8; 1]>, [f32; 8], MouseCursor),
+    Gamepad(BitArray<[u8; 4]>, [f32; 12], Box<MotionTracker>),
+    Touch(BitArray<[u8; 1]>, [f32; 6], Box<TouchTracker>),
+    /// Room for every button and axis index, so bindings never read past the end.
+    Virtual(BitArray<[u8; 32]>, Box<[f32; 256]>, Box<VirtualDevice>),
+}
+
+/// The viewport and lock state of a mouse cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct MouseCursor {
+    viewport: [f32; 2],
+    locked: bool,
+}
+
+/// The mouse axes that add up the movement set within a frame, rather than holding the last value.
+const MOUSE_MOTION: [MouseAxis; 4] = [
+    MouseAxis::DeltaX,
+    MouseAxis::DeltaY,
+    MouseAxis::WheelX,
+    MouseAxis::WheelY,
+];
+
+/// How much of each mouse's movement an `InputManager` has already read,
+/// so every manager sees the movement since its own last update.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MouseReads {
+    frame: u64,
+    read: HashMap<Ulid, [f32; 4]>,
+}
+
+impl MouseReads {
+    /// Nothing has been read yet if `next_frame` started the movement over since the last update.
+    fn get(&self, frame: u64, device: &DeviceId) -> [f32; 4] {
+        if self.frame != frame {
+            return [0f32; 4];
+        }
+
+        return self.read.get(&device.0).copied().unwrap_or_default();
+    }
+
+    /// Marks all the movement so far as read, after an update.
+    pub(crate) fn advance(&mut self, state: &InputState) {
+        self.frame = state.frame;
+        self.read.clear();
+
+        for (id, device) in &state.devices {
+            let DeviceState::Mouse(_, axes, _) = device else {
+                continue;
+            };
+
+            self.read
+                .insert(*id, MOUSE_MOTION.map(|axis| axes[axis as usize]));
+        }
+    }
+}
+
+impl MouseCursor {
+    fn normalize(&self, axes: &mut [f32]) {
+        for (position, normalized, size) in [
+            (MouseAxis::X, MouseAxis::NormalizedX, self.viewport[0]),
+            (MouseAxis::Y, MouseAxis::NormalizedY, self.viewport[1]),
+        ] {
+            axes[normalized as usize] = if size > 0f32 {
+                axes[position as usize] / size
+            } else {
+                0f32
+            };
+        }
+    }
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum InputFamily {
     Keyboard,
     Mouse,
     Gamepad,
+    Touch,
+    Virtual,
 }
 
 pub struct InputState {
     devices: HashMap<Ulid, DeviceState>,
+    frame: u64,
+    recording: Option<(u64, InputRecording)>,
+    haptics: HashMap<Ulid, HapticQueue>,
+    styles: HashMap<Ulid, ControllerStyle>,
+}
+
+/// A chord that is currently held on a device, used to suppress bindings that it overlaps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ActiveChord {
+    button: usize,
+    modifiers: Box<[usize]>,
+}
+
+impl ActiveChord {
+    /// Whether a binding to `button` with `modifiers` is overlapped by a held chord with more modifiers.
+    fn suppresses<TButton>(
+        chords: &[ActiveChord],
+        button: usize,
+        modifiers: &ChordModifiers<TButton>,
+    ) -> bool
+    where
+        TButton: Copy + Into<usize>,
+    {
+        return chords.iter().any(|chord| {
+            chord.button == button
+                && chord.modifiers.len() > modifiers.len()
+                && modifiers
+                    .iter()
+                    .all(|it| chord.modifiers.contains(&(*it).into()))
+        });
+    }
+}
+
+impl DeviceState {
+    const EMPTY: [f32; 0] = [];
+
+    fn family(&self) -> InputFamily {
+        return match self {
+            Self::Keyboard(_, _, _) => InputFamily::Keyboard,
+            Self::Mouse(_, _, _) => InputFamily::Mouse,
+            Self::Gamepad(_, _, _) => InputFamily::Gamepad,
+            Self::Touch(_, _, _) => InputFamily::Touch,
+            Self::Virtual(_, _, _) => InputFamily::Virtual,
+        };
+    }
+
+    fn digital(&self) -> &BitSlice<u8> {
+        return match self {
+            Self::Keyboard(b, _, _) => b,
+            Self::Mouse(b, _, _) => b,
+            Self::Gamepad(b, _, _) => b,
+            Self::Touch(b, _, _) => b,
+            Self::Virtual(b, _, _) => b,
+        };
+    }
+
+    /// The keys bindings are allowed to see while text input is active.
+    fn text_mask(&self) -> Option<BitArray<[u8; 15]>> {
+        let Self::Keyboard(keys, text, _) = self else {
+            return None;
+        };
+
+        return text.mask(keys);
+    }
+
+    /// The buttons and axes bindings are allowed to see,
+    /// without the keys text input hides or the inputs a higher priority context consumed.
+    /// `read` is the mouse movement the reader has already seen, which is left out.
+    fn visible(
+        &self,
+        consumed: &[PhysicalInput],
+        read: [f32; 4],
+    ) -> (Cow<'_, BitSlice<u8>>, Cow<'_, [f32]>) {
+        let family = self.family();
+
+        let mut digital = match self.text_mask() {
+            Some(masked) => Cow::Owned(masked.to_bitvec()),
+            None => Cow::Borrowed(self.digital()),
+        };
+
+        let mut analog = Cow::Borrowed(self.analog());
+
+        if let Self::Mouse(_, _, _) = self
+            && read != [0f32; 4]
+        {
+            for (axis, read) in MOUSE_MOTION.iter().zip(read) {
+                analog.to_mut()[*axis as usize] -= read;
+            }
+        }
+
+        for input in consumed {
+            match *input {
+                PhysicalInput::Button(it, button) if it == family => {
+                    digital.to_mut().set(button, false);
+                }
+                PhysicalInput::Axis(it, axis) if it == family => analog.to_mut()[axis] = 0f32,
+                PhysicalInput::LogicalKey(key) => {
+                    let Self::Keyboard(_, _, layout) = self else {
+                        continue;
+                    };
+
+                    let held = digital.iter_ones().collect::<Vec<_>>();
+
+                    for physical in held {
+                        if layout.prints(physical, key) {
+                            digital.to_mut().set(physical, false);
+                        }
+                    }
+                }
+                _ => continue,
+            }
+        }
+
+        return (digital, analog);
+    }
+
+    /// The held keys by what's printed on them, for logical keyboard bindings.
+    fn logical(&self, digital: &BitSlice<u8>) -> BitArray<[u8; 15]> {
+        let Self::Keyboard(_, _, layout) = self else {
+            return BitArray::new([0; 15]);
+        };
+
+        return layout.logical_keys(digital);
+    }
+
+    fn analog(&self) -> &[f32] {
+        return match self {
+            Self::Keyboard(_, _, _) => &Self::EMPTY,
+            Self::Mouse(_, a, _) => a,
+            Self::Gamepad(_, a, _) => a,
+            Self::Touch(_, a, _) => a,
+            Self::Virtual(_, a, _) => &a[..],
+        };
+    }
+
+    fn connect_event(&self, id: DeviceId) -> InputEvent {
+        return match self {
+            Self::Keyboard(_, _, _) => InputEvent::ConnectKeyboard(id),
+            Self::Mouse(_, _, _) => InputEvent::ConnectMouse(id),
+            Self::Gamepad(_, _, _) => InputEvent::ConnectGamepad(id),
+            Self::Touch(_, _, _) => InputEvent::ConnectTouch(id),
+            Self::Virtual(_, _, device) => InputEvent::ConnectVirtual(id, device.layout),
+        };
+    }
+
+    /// Records the events needed to recreate this device from scratch.
+    fn record_snapshot(&self, id: DeviceId, recording: &mut InputRecording) {
+        recording.push(0, self.connect_event(id));
+
+        // Touch buttons and axes come from the contacts, so only those need to be recorded.
+        if let Self::Touch(_, _, tracker) = self {
+            recording.push(0, InputEvent::TouchSettings(id, tracker.settings));
+
+            for contact in tracker.contacts() {
+                recording.push(0, InputEvent::TouchContact(id, *contact));
+            }
+
+            return;
+        }
+
+        if let Self::Keyboard(_, text, _) = self
+            && text.active
+        {
+            recording.push(0, InputEvent::TextInputActive(id, true));
+        }
+
+        if let Self::Keyboard(_, _, layout) = self {
+            for (physical, logical) in layout.iter() {
+                recording.push(0, InputEvent::KeyboardLayoutKey(id, physical, logical));
+            }
+        }
+
+        // The viewport has to come before the position, so the normalized axes get derived from it.
+        if let Self::Mouse(_, _, cursor) = self
+            && cursor.viewport != [0f32; 2]
+        {
+            recording.push(0, InputEvent::MouseViewport(id, cursor.viewport));
+        }
+
+        // Recording the last sample restores the time the next sample is measured from,
+        // while the axes below restore the values themselves.
+        if let Self::Gamepad(_, _, tracker) = self {
+            recording.push(0, InputEvent::GyroCalibration(id, tracker.calibration));
+
+            if let Some(sample) = tracker.last_sample() {
+                recording.push(0, InputEvent::GamepadMotion(id, sample));
+            }
+
+            if tracker.is_calibrating() {
+                recording.push(0, InputEvent::BeginGyroCalibration(id));
+            }
+        }
+
+        for button in self.digital().iter_ones() {
+            let event = match self {
+                Self::Keyboard(_, _, _) => KeyboardButton::try_from_primitive(button as u8)
+                    .ok()
+                    .map(|it| InputEvent::KeyboardButton(id, it, true)),
+                Self::Mouse(_, _, _) => MouseButton::try_from_primitive(button as u8)
+                    .ok()
+                    .map(|it| InputEvent::MouseButton(id, it, true)),
+                Self::Gamepad(_, _, _) => GamepadButton::try_from_primitive(button as u8)
+                    .ok()
+                    .map(|it| InputEvent::GamepadButton(id, it, true)),
+                Self::Touch(_, _, _) => None,
+                Self::Virtual(_, _, _) => Some(InputEvent::VirtualButton(
+                    id,
+                    VirtualButton(button as u8),
+                    true,
+                )),
+            };
+
+            if let Some(event) = event {
+                recording.push(0, event);
+            }
+        }
+
+        for (axis, value) in self.analog().iter().enumerate() {
+            if *value == 0f32 {
+                continue;
+            }
+
+            let event = match self {
+                Self::Keyboard(_, _, _) | Self::Touch(_, _, _) => continue,
+                // The normalized axes are derived from the position, so they can't be set.
+                Self::Mouse(_, _, _) => MouseAxis::try_from_primitive(axis as u8)
+                    .ok()
+                    .filter(|it| !matches!(it, MouseAxis::NormalizedX | MouseAxis::NormalizedY))
+                    .map(|it| InputEvent::MouseAxis(id, it, *value)),
+                Self::Gamepad(_, _, _) => GamepadAxis::try_from_primitive(axis as u8)
+                    .ok()
+                    .map(|it| InputEvent::GamepadAxis(id, it, *value)),
+                Self::Virtual(_, _, _) => {
+                    Some(InputEvent::VirtualAxis(id, VirtualAxis(axis as u8), *value))
+                }
+            };
+
+            if let Some(event) = event {
+                recording.push(0, event);
+            }
+        }
+
+        // Sorted by name, so the same state always records the same way.
+        if let Self::Virtual(_, _, device) = self {
+            let mut actions = device.actions.iter().collect::<Vec<_>>();
+            actions.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+            for (name, value) in actions {
+                recording.push(0, InputEvent::SetVirtualAction(id, name.clone(), *value));
+            }
+        }
+    }
 }
 
-impl DeviceState {
-    const EMPTY: [f32; 0] = [];
+impl InputState {
+    pub fn new() -> Self {
+        return Self {
+            devices: HashMap::new(),
+            frame: 0,
+            recording: None,
+            haptics: HashMap::new(),
+            styles: HashMap::new(),
+        };
+    }
+
+    /// The number of times `next_frame` has been called.
+    pub fn frame(&self) -> u64 {
+        return self.frame;
+    }
+
+    /// Marks the end of a frame. Changes made after this are recorded on the next frame,
+    /// and gestures that only last a single frame are cleared.
+    /// Mouse movement and scrolling start adding up from zero again, which only keeps the totals small,
+    /// since each `InputManager` already only sees the movement since its own last update.
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+
+        for device in self.devices.values_mut() {
+            match device {
+                DeviceState::Mouse(_, axes, _) => {
+                    for axis in MOUSE_MOTION {
+                        axes[axis as usize] = 0f32;
+                    }
+                }
+                DeviceState::Gamepad(_, axes, tracker) => tracker.end_frame(axes),
+                DeviceState::Touch(buttons, axes, tracker) => tracker.end_frame(buttons, axes),
+                _ => {}
+            }
+        }
+    }
+
+    /// Starts recording every change made to the state.
+    /// The devices that are already connected are recorded as connecting on the first frame.
+    pub fn start_recording(&mut self) {
+        let mut recording = InputRecording::new();
+
+        let mut devices = self.devices.iter().collect::<Vec<_>>();
+        devices.sort_by_key(|(id, _)| **id);
+
+        for (id, device) in devices {
+            device.record_snapshot(DeviceId(*id), &mut recording);
+        }
+
+        self.recording = Some((self.frame, recording));
+    }
+
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        return self.recording.take().map(|(_, recording)| recording);
+    }
+
+    pub fn is_recording(&self) -> bool {
+        return self.recording.is_some();
+    }
+
+    fn record(&mut self, event: InputEvent) {
+        let Some((start, recording)) = &mut self.recording else {
+            return;
+        };
+
+        recording.push(self.frame - *start, event);
+    }
+
+    #[inline(always)]
+    fn connect_device(&mut self, state: DeviceState) -> DeviceId {
+        let id = DeviceId(Ulid::new());
+
+        self.insert_device(id, state);
+
+        return id;
+    }
+
+    fn insert_device(&mut self, id: DeviceId, state: DeviceState) {
+        self.record(state.connect_event(id));
+
+        self.devices.insert(id.0, state);
+    }
+
+    pub fn device_family(&self, device: &DeviceId) -> Option<InputFamily> {
+        let device = self.devices.get(&device.0)?;
+
+        return Some(device.family());
+    }
+
+    /// Sets which labels a gamepad's buttons have, for showing glyphs.
+    /// It isn't recorded, since it doesn't change the input.
+    pub fn set_controller_style(&mut self, device: &DeviceId, style: ControllerStyle) -> bool {
+        let Some(DeviceState::Gamepad(_, _, _)) = self.devices.get(&device.0) else {
+            return false;
+        };
+
+        self.styles.insert(device.0, style);
+
+        return true;
+    }
+
+    /// The labels a device's buttons have, which is generic for anything but a gamepad with a style set.
+    pub fn controller_style(&self, device: &DeviceId) -> Option<ControllerStyle> {
+        if !self.devices.contains_key(&device.0) {
+            return None;
+        }
+
+        return Some(self.styles.get(&device.0).copied().unwrap_or_default());
+    }
+
+    pub(crate) fn raw_devices(
+        &self,
+    ) -> impl Iterator<Item = (DeviceId, InputFamily, &BitSlice<u8>, &[f32])> {
+        return self.devices.iter().map(|(id, device)| {
+            (
+                DeviceId(*id),
+                device.family(),
+                device.digital(),
+                device.analog(),
+            )
+        });
+    }
+
+    /// Starts waiting for the next input on any connected device, for rebinding.
+    pub fn begin_capture(&self, options: CaptureOptions) -> InputCapture {
+        return InputCapture::new(self, options);
+    }
+
+    pub fn connect_keyboard(&mut self) -> DeviceId {
+        return self.connect_device(Self::keyboard_device());
+    }
+
+    pub fn connect_mouse(&mut self) -> DeviceId {
+        return self.connect_device(Self::mouse_device());
+    }
+
+    pub fn connect_gamepad(&mut self) -> DeviceId {
+        return self.connect_device(Self::gamepad_device());
+    }
+
+    pub fn connect_touch(&mut self) -> DeviceId {
+        return self.connect_device(Self::touch_device());
+    }
+
+    fn keyboard_device() -> DeviceState {
+        return DeviceState::Keyboard(BitArray::new([0; 15]), Box::default(), Box::default());
+    }
+
+    fn mouse_device() -> DeviceState {
+        return DeviceState::Mouse(BitArray::new([0]), [0f32; 8], MouseCursor::default());
+    }
+
+    fn gamepad_device() -> DeviceState {
+        return DeviceState::Gamepad(
+            BitArray::new([0; 4]),
+            [0f32; 12],
+            Box::new(MotionTracker::new()),
+        );
+    }
+
+    /// Connects a device with any number of buttons and axes, driven from code,
+    /// such as an on-screen joystick, a bot or a test.
+    pub fn connect_virtual(&mut self, layout: VirtualLayout) -> DeviceId {
+        return self.connect_device(Self::virtual_device(layout));
+    }
+
+    fn virtual_device(layout: VirtualLayout) -> DeviceState {
+        return DeviceState::Virtual(
+            BitArray::new([0; 32]),
+            Box::new([0f32; 256]),
+            Box::new(VirtualDevice::new(layout)),
+        );
+    }
+
+    fn touch_device() -> DeviceState {
+        return DeviceState::Touch(BitArray::new([0]), [0f32; 6], Box::new(TouchTracker::new()));
+    }
+
+    pub(crate) fn connect_keyboard_with_id(&mut self, id: DeviceId) {
+        self.insert_device(id, Self::keyboard_device());
+    }
+
+    pub(crate) fn connect_mouse_with_id(&mut self, id: DeviceId) {
+        self.insert_device(id, Self::mouse_device());
+    }
+
+    pub(crate) fn connect_gamepad_with_id(&mut self, id: DeviceId) {
+        self.insert_device(id, Self::gamepad_device());
+    }
+
+    pub(crate) fn connect_touch_with_id(&mut self, id: DeviceId) {
+        self.insert_device(id, Self::touch_device());
+    }
+
+    pub(crate) fn connect_virtual_with_id(&mut self, id: DeviceId, layout: VirtualLayout) {
+        self.insert_device(id, Self::virtual_device(layout));
+    }
+
+    pub fn disconnect_device(&mut self, device: &DeviceId) -> bool {
+        if self.devices.remove(&device.0).is_none() {
+            return false;
+        }
+
+        self.haptics.remove(&device.0);
+        self.styles.remove(&device.0);
+
+        self.record(InputEvent::Disconnect(*device));
+
+        return true;
+    }
+
+    /// Queues a haptic effect on a gamepad. Effects play alongside any that are already playing.
+    /// Haptics are output, so they aren't recorded.
+    pub fn play_haptic(&mut self, device: &DeviceId, effect: HapticEffect) -> bool {
+        let Some(DeviceState::Gamepad(_, _, _)) = self.devices.get(&device.0) else {
+            return false;
+        };
+
+        self.haptics.entry(device.0).or_default().push(effect);
+
+        return true;
+    }
+
+    pub fn stop_haptics(&mut self, device: &DeviceId) -> bool {
+        let Some(queue) = self.haptics.get_mut(&device.0) else {
+            return false;
+        };
+
+        queue.stop();
+
+        return true;
+    }
+
+    /// Called by the platform layer once per frame for each gamepad.
+    /// Returns the motor strengths to apply, or `None` if the motors should be left alone.
+    /// Once every effect finishes, this returns zero strengths once, to turn the motors off.
+    pub fn drain_haptics(&mut self, device: &DeviceId, delta_time: f32) -> Option<HapticOutput> {
+        let queue = self.haptics.get_mut(&device.0)?;
+
+        let output = queue.drain(delta_time);
+
+        if queue.is_idle() {
+            self.haptics.remove(&device.0);
+        }
+
+        return output;
+    }
+
+    pub fn set_keyboard_button(
+        &mut self,
+        device: &DeviceId,
+        button: KeyboardButton,
+        value: bool,
+    ) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Keyboard(buttons, text, _) = state else {
+            return false;
+        };
+
+        buttons.set(button as usize, value);
+        text.key_changed(button, value);
+
+        self.record(InputEvent::KeyboardButton(*device, button, value));
+
+        return true;
+    }
+
+    pub fn set_mouse_button(
+        &mut self,
+        device: &DeviceId,
+        button: MouseButton,
+        value: bool,
+    ) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Mouse(buttons, _, _) = state else {
+            return false;
+        };
+
+        buttons.set(button as usize, value);
+
+        self.record(InputEvent::MouseButton(*device, button, value));
+
+        return true;
+    }
+
+    pub fn set_gamepad_button(
+        &mut self,
+        device: &DeviceId,
+        button: GamepadButton,
+        value: bool,
+    ) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Gamepad(buttons, _, _) = state else {
+            return false;
+        };
+
+        buttons.set(button as usize, value);
+
+        self.record(InputEvent::GamepadButton(*device, button, value));
+
+        return true;
+    }
+
+    /// While text input is active, keyboard bindings only see Escape and Enter,
+    /// and text events are queued for the text box to drain.
+    /// Turning it off clears any queued events and composition,
+    /// and keys held while it was active stay hidden from bindings until they're released.
+    pub fn set_text_input_active(&mut self, device: &DeviceId, active: bool) -> bool {
+        let Some(DeviceState::Keyboard(keys, text, _)) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        text.set_active(active, keys);
+
+        self.record(InputEvent::TextInputActive(*device, active));
+
+        return true;
+    }
+
+    pub fn is_text_input_active(&self, device: &DeviceId) -> Option<bool> {
+        let DeviceState::Keyboard(_, text, _) = self.devices.get(&device.0)? else {
+            return None;
+        };
+
+        return Some(text.active);
+    }
+
+    /// Queues text typed on a keyboard, or an IME composition change.
+    /// Events are ignored while text input isn't active, and aren't recorded.
+    pub fn push_text_event(&mut self, device: &DeviceId, event: TextEvent) -> bool {
+        let Some(DeviceState::Keyboard(_, text, _)) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        return text.push(event);
+    }
+
+    /// Takes every text event queued since the last drain.
+    pub fn drain_text_events(&mut self, device: &DeviceId) -> Vec<TextEvent> {
+        let Some(DeviceState::Keyboard(_, text, _)) = self.devices.get_mut(&device.0) else {
+            return vec![];
+        };
+
+        return text.drain();
+    }
+
+    /// Takes the oldest queued text event.
+    pub fn pop_text_event(&mut self, device: &DeviceId) -> Option<TextEvent> {
+        let DeviceState::Keyboard(_, text, _) = self.devices.get_mut(&device.0)? else {
+            return None;
+        };
+
+        return text.pop();
+    }
+
+    /// The text an IME is currently composing, for drawing it in the text box.
+    pub fn text_composition(&self, device: &DeviceId) -> Option<&TextComposition> {
+        let DeviceState::Keyboard(_, text, _) = self.devices.get(&device.0)? else {
+            return None;
+        };
+
+        return text.composition();
+    }
+
+    /// Sets what's printed on each key of a keyboard, for logical bindings.
+    /// Platform layers should call this on connect, and whenever the OS layout changes.
+    pub fn set_keyboard_layout(&mut self, device: &DeviceId, layout: KeyboardLayout) -> bool {
+        let Some(DeviceState::Keyboard(_, _, current)) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        // Only the keys that changed are recorded, as keys that print themselves aren't stored.
+        let mut changed = current
+            .iter()
+            .chain(layout.iter())
+            .map(|(physical, _)| physical)
+            .filter(|it| current.logical(*it) != layout.logical(*it))
+            .collect::<Vec<_>>();
+
+        changed.sort();
+        changed.dedup();
+
+        for physical in changed {
+            let logical = layout.logical(physical);
+
+            self.record(InputEvent::KeyboardLayoutKey(*device, physical, logical));
+        }
+
+        let Some(DeviceState::Keyboard(_, _, current)) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        **current = layout;
+
+        return true;
+    }
+
+    /// Sets what's printed on a single key, leaving the rest of the layout alone.
+    pub fn set_keyboard_key(
+        &mut self,
+        device: &DeviceId,
+        physical: KeyboardButton,
+        logical: KeyboardButton,
+    ) -> bool {
+        let Some(DeviceState::Keyboard(_, _, layout)) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        layout.set(physical, logical);
+
+        self.record(InputEvent::KeyboardLayoutKey(*device, physical, logical));
+
+        return true;
+    }
+
+    pub fn keyboard_layout(&self, device: &DeviceId) -> Option<&KeyboardLayout> {
+        let DeviceState::Keyboard(_, _, layout) = self.devices.get(&device.0)? else {
+            return None;
+        };
+
+        return Some(layout);
+    }
+
+    pub fn set_mouse_axis(&mut self, device: &DeviceId, axis: MouseAxis, value: f32) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Mouse(_, axes, cursor) = state else {
+            return false;
+        };
+
+        match axis {
+            MouseAxis::DeltaX | MouseAxis::DeltaY | MouseAxis::WheelX | MouseAxis::WheelY => {
+                axes[axis as usize] += value;
+            }
+
+            MouseAxis::X | MouseAxis::Y => {
+                axes[axis as usize] = value;
+                cursor.normalize(axes);
+            }
+
+            MouseAxis::NormalizedX | MouseAxis::NormalizedY => return false,
+        }
+
+        self.record(InputEvent::MouseAxis(*device, axis, value));
+
+        return true;
+    }
+
+    /// Sets the size of the viewport the normalized position axes are relative to, in window pixels.
+    pub fn set_mouse_viewport(&mut self, device: &DeviceId, size: [f32; 2]) -> bool {
+        let Some(DeviceState::Mouse(_, axes, cursor)) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        cursor.viewport = size;
+        cursor.normalize(axes);
+
+        self.record(InputEvent::MouseViewport(*device, size));
+
+        return true;
+    }
+
+    /// Asks the platform layer to lock the cursor, such as for mouse look.
+    /// This is only a request for the platform to read, so it isn't recorded.
+    pub fn set_cursor_locked(&mut self, device: &DeviceId, locked: bool) -> bool {
+        let Some(DeviceState::Mouse(_, _, cursor)) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        cursor.locked = locked;
+
+        return true;
+    }
+
+    pub fn is_cursor_locked(&self, device: &DeviceId) -> Option<bool> {
+        let DeviceState::Mouse(_, _, cursor) = self.devices.get(&device.0)? else {
+            return None;
+        };
+
+        return Some(cursor.locked);
+    }
+
+    pub fn set_gamepad_axis(&mut self, device: &DeviceId, axis: GamepadAxis, value: f32) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Gamepad(_, axes, _) = state else {
+            return false;
+        };
+
+        axes[axis as usize] = value;
+
+        self.record(InputEvent::GamepadAxis(*device, axis, value));
+
+        return true;
+    }
+
+    /// Feeds a motion sensor sample to a gamepad. Samples should be sent in order, as they arrive,
+    /// and the gyro axes accumulate the rotation between them until the next frame.
+    pub fn set_gamepad_motion(&mut self, device: &DeviceId, sample: MotionSample) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Gamepad(_, axes, tracker) = state else {
+            return false;
+        };
+
+        tracker.push_sample(sample, axes);
+
+        self.record(InputEvent::GamepadMotion(*device, sample));
+
+        return true;
+    }
+
+    pub fn gyro_calibration(&self, device: &DeviceId) -> Option<GyroCalibration> {
+        let DeviceState::Gamepad(_, _, tracker) = self.devices.get(&device.0)? else {
+            return None;
+        };
+
+        return Some(tracker.calibration);
+    }
 
-    fn family(&self) -> InputFamily {
-        return match self {
-            Self::Keyboard(_) => InputFamily::Keyboard,
-            Self::Mouse(_, _) => InputFamily::Mouse,
-            Self::Gamepad(_, _) => InputFamily::Gamepad,
+    /// Restores a calibration, such as one saved from an earlier session.
+    pub fn set_gyro_calibration(
+        &mut self,
+        device: &DeviceId,
+        calibration: GyroCalibration,
+    ) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Gamepad(_, _, tracker) = state else {
+            return false;
         };
+
+        tracker.calibration = calibration;
+
+        self.record(InputEvent::GyroCalibration(*device, calibration));
+
+        return true;
     }
 
-    fn digital(&self) -> &BitSlice<u8> {
-        return match self {
-            Self::Keyboard(b) => b,
-            Self::Mouse(b, _) => b,
-            Self::Gamepad(b, _) => b,
+    /// Starts averaging the gyro samples of a gamepad, which should be left resting until the calibration ends.
+    pub fn begin_gyro_calibration(&mut self, device: &DeviceId) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
         };
+
+        let DeviceState::Gamepad(_, _, tracker) = state else {
+            return false;
+        };
+
+        tracker.begin_calibration();
+
+        self.record(InputEvent::BeginGyroCalibration(*device));
+
+        return true;
     }
 
-    fn analog(&self) -> &[f32] {
-        return match self {
-            Self::Keyboard(_) => &Self::EMPTY,
-            Self::Mouse(_, a) => a,
-            Self::Gamepad(_, a) => a,
+    /// Uses the average gyro reading since the calibration began as the offset.
+    /// Returns `None` if no calibration was running, or no samples arrived during it.
+    pub fn end_gyro_calibration(&mut self, device: &DeviceId) -> Option<GyroCalibration> {
+        let DeviceState::Gamepad(_, _, tracker) = self.devices.get_mut(&device.0)? else {
+            return None;
         };
+
+        let calibration = tracker.end_calibration();
+
+        self.record(InputEvent::EndGyroCalibration(*device));
+
+        return calibration;
     }
-}
 
-impl InputState {
-    pub fn new() -> Self {
-        return Self {
-            devices: HashMap::new(),
+    pub fn set_touch_settings(&mut self, device: &DeviceId, settings: TouchSettings) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Touch(_, _, tracker) = state else {
+            return false;
         };
+
+        tracker.settings = settings;
+
+        self.record(InputEvent::TouchSettings(*device, settings));
+
+        return true;
     }
 
-    #[inline(always)]
-    fn connect_device(&mut self, state: DeviceState) -> DeviceId {
-        let id = Ulid::new();
+    /// Adds a finger to the screen, or moves one that's already on it.
+    pub fn set_touch_contact(&mut self, device: &DeviceId, contact: TouchContact) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Touch(buttons, axes, tracker) = state else {
+            return false;
+        };
 
-        self.devices.insert(id, state);
+        tracker.set_contact(contact, buttons, axes);
 
-        return DeviceId(id);
+        self.record(InputEvent::TouchContact(*device, contact));
+
+        return true;
     }
 
-    pub fn device_family(&self, device: &DeviceId) -> Option<InputFamily> {
-        let device = self.devices.get(&device.0)?;
+    /// Lifts a finger off the screen.
+    pub fn remove_touch_contact(&mut self, device: &DeviceId, id: u64) -> bool {
+        let Some(state) = self.devices.get_mut(&device.0) else {
+            return false;
+        };
+
+        let DeviceState::Touch(buttons, axes, tracker) = state else {
+            return false;
+        };
 
-        match device {
-            DeviceState::Keyboard(_) => return Some(InputFamily::Keyboard),
-            DeviceState::Mouse(_, _) => return Some(InputFamily::Mouse),
-            DeviceState::Gamepad(_, _) => return Some(InputFamily::Gamepad),
+        if !tracker.remove_contact(id, buttons, axes) {
+            return false;
         }
-    }
 
-    pub fn connect_keyboard(&mut self) -> DeviceId {
-        return self.connect_device(DeviceState::Keyboard(BitArray::new([0; 15])));
-    }
+        self.record(InputEvent::TouchRelease(*device, id));
 
-    pub fn connect_mouse(&mut self) -> DeviceId {
-        return self.connect_device(DeviceState::Mouse(BitArray::new([0]), [0f32; 4]));
+        return true;
     }
 
-    pub fn connect_gamepad(&mut self) -> DeviceId {
-        return self.connect_device(DeviceState::Gamepad(BitArray::new([0; 4]), [0f32; 6]));
+    pub fn touch_contacts(&self, device: &DeviceId) -> Option<&[TouchContact]> {
+        let DeviceState::Touch(_, _, tracker) = self.devices.get(&device.0)? else {
+            return None;
+        };
+
+        return Some(tracker.contacts());
     }
 
-    pub fn disconnect_device(&mut self, device: &DeviceId) -> bool {
-        return self.devices.remove(&device.0).is_some();
+    pub fn virtual_layout(&self, device: &DeviceId) -> Option<VirtualLayout> {
+        let DeviceState::Virtual(_, _, state) = self.devices.get(&device.0)? else {
+            return None;
+        };
+
+        return Some(state.layout);
     }
 
-    pub fn set_keyboard_button(
+    /// Returns false if the device isn't virtual, or the button is outside its layout.
+    pub fn set_virtual_button(
         &mut self,
         device: &DeviceId,
-        button: KeyboardButton,
+        button: VirtualButton,
         value: bool,
     ) -> bool {
-        let Some(device) = self.devices.get_mut(&device.0) else {
+        let Some(DeviceState::Virtual(buttons, _, state)) = self.devices.get_mut(&device.0) else {
             return false;
         };
 
-        let DeviceState::Keyboard(buttons) = device else {
+        if button.0 >= state.layout.buttons {
             return false;
-        };
+        }
 
-        buttons.set(button as usize, value);
+        buttons.set(button.into(), value);
+
+        self.record(InputEvent::VirtualButton(*device, button, value));
 
         return true;
     }
 
-    pub fn set_mouse_button(
-        &mut self,
-        device: &DeviceId,
-        button: MouseButton,
-        value: bool,
-    ) -> bool {
-        let Some(device) = self.devices.get_mut(&device.0) else {
+    /// Returns false if the device isn't virtual, or the axis is outside its layout.
+    pub fn set_virtual_axis(&mut self, device: &DeviceId, axis: VirtualAxis, value: f32) -> bool {
+        let Some(DeviceState::Virtual(_, axes, state)) = self.devices.get_mut(&device.0) else {
             return false;
         };
 
-        let DeviceState::Mouse(buttons, _) = device else {
+        if axis.0 >= state.layout.axes {
             return false;
-        };
+        }
 
-        buttons.set(button as usize, value);
+        axes[usize::from(axis)] = value;
+
+        self.record(InputEvent::VirtualAxis(*device, axis, value));
 
         return true;
     }
 
-    pub fn set_gamepad_button(
+    /// Drives an action directly from a virtual device, in place of whatever its bindings read on that device,
+    /// in every context that has the action. The value has to match the action's kind to have any effect.
+    /// Action values are keyed by name, so they aren't recorded.
+    pub fn set_virtual_action(
         &mut self,
         device: &DeviceId,
-        button: GamepadButton,
-        value: bool,
+        name: InternString,
+        value: ActionValue,
     ) -> bool {
-        let Some(device) = self.devices.get_mut(&device.0) else {
+        let Some(DeviceState::Virtual(_, _, state)) = self.devices.get_mut(&device.0) else {
             return false;
         };
 
-        let DeviceState::Gamepad(buttons, _) = device else {
-            return false;
-        };
+        state.actions.insert(name.clone(), value);
 
-        buttons.set(button as usize, value);
+        self.record(InputEvent::SetVirtualAction(*device, name, value));
 
         return true;
     }
 
-    pub fn set_mouse_axis(&mut self, device: &DeviceId, axis: MouseAxis, value: f32) -> bool {
-        let Some(device) = self.devices.get_mut(&device.0) else {
+    /// Hands an action back to the device's bindings. Returns false if the action wasn't set.
+    pub fn clear_virtual_action(&mut self, device: &DeviceId, name: &InternString) -> bool {
+        let Some(DeviceState::Virtual(_, _, state)) = self.devices.get_mut(&device.0) else {
             return false;
         };
 
-        let DeviceState::Mouse(_, axes) = device else {
+        if state.actions.remove(name).is_none() {
             return false;
-        };
+        }
 
-        axes[axis as usize] = value;
+        self.record(InputEvent::ClearVirtualAction(*device, name.clone()));
 
         return true;
     }
 
-    pub fn set_gamepad_axis(&mut self, device: &DeviceId, axis: GamepadAxis, value: f32) -> bool {
-        let Some(device) = self.devices.get_mut(&device.0) else {
-            return false;
+    pub(crate) fn virtual_action(
+        &self,
+        device: &DeviceId,
+        name: &InternString,
+    ) -> Option<ActionValue> {
+        let DeviceState::Virtual(_, _, state) = self.devices.get(&device.0)? else {
+            return None;
         };
 
-        let DeviceState::Gamepad(_, axes) = device else {
-            return false;
+        return state.actions.get(name).copied();
+    }
+
+    fn get_chord_single<TButton, TAxis>(
+        &self,
+        digital: &BitSlice<u8>,
+        button: &BoundButton<TButton, TAxis>,
+    ) -> Option<ActiveChord>
+    where
+        TButton: Debug + Copy + PartialEq + Serialize + Into<usize>,
+        TAxis: Debug + Copy + PartialEq + Serialize + Into<usize>,
+    {
+        let BoundButton::Chord { modifiers, button } = button else {
+            return None;
         };
 
-        axes[axis as usize] = value;
+        let button = (*button).into();
 
-        return true;
+        if !digital[button] {
+            return None;
+        }
+
+        let modifiers = modifiers
+            .iter()
+            .map(|it| (*it).into())
+            .collect::<Box<[usize]>>();
+
+        if !modifiers.iter().all(|it| digital[*it]) {
+            return None;
+        }
+
+        return Some(ActiveChord { button, modifiers });
+    }
+
+    /// Collects every chord in `bindings` that is currently held on the device.
+    pub(crate) fn get_active_chords(
+        &self,
+        device: &DeviceId,
+        bindings: &[DigitalBinding],
+        consumed: &[PhysicalInput],
+        reads: &MouseReads,
+        chords: &mut Vec<ActiveChord>,
+    ) {
+        let read = reads.get(self.frame, device);
+
+        let Some(device) = self.devices.get(&device.0) else {
+            return;
+        };
+
+        let family = device.family();
+        let (digital, _) = device.visible(consumed, read);
+        let logical = device.logical(&digital);
+
+        for binding in bindings {
+            let chord = match binding {
+                DigitalBinding::Keyboard(button) if family == InputFamily::Keyboard => {
+                    self.get_chord_single(&digital, button)
+                }
+
+                DigitalBinding::LogicalKeyboard(button) if family == InputFamily::Keyboard => {
+                    self.get_chord_single(&logical, button)
+                }
+
+                DigitalBinding::Mouse(button) if family == InputFamily::Mouse => {
+                    self.get_chord_single(&digital, button)
+                }
+
+                DigitalBinding::Gamepad(button) if family == InputFamily::Gamepad => {
+                    self.get_chord_single(&digital, button)
+                }
+
+                DigitalBinding::Touch(button) if family == InputFamily::Touch => {
+                    self.get_chord_single(&digital, button)
+                }
+
+                DigitalBinding::Virtual(button) if family == InputFamily::Virtual => {
+                    self.get_chord_single(&digital, button)
+                }
+
+                _ => continue,
+            };
+
+            let Some(chord) = chord else {
+                continue;
+            };
+
+            if chords.contains(&chord) {
+                continue;
+            }
+
+            chords.push(chord);
+        }
     }
 
     fn get_digital_single<TButton, TAxis>(
         &self,
         digital: &BitSlice<u8>,
         analog: &[f32],
-        button: BoundButton<TButton, TAxis>,
+        button: &BoundButton<TButton, TAxis>,
+        chords: &[ActiveChord],
     ) -> bool
     where
         TButton: Debug + Copy + PartialEq + Serialize + Into<usize>,
         TAxis: Debug + Copy + PartialEq + Serialize + Into<usize>,
     {
         match button {
-            BoundButton::Digital(button) => return digital[button.into()],
-            BoundButton::Analog { axis, threshold } => return analog[axis.into()] > threshold,
+            BoundButton::Digital(button) => {
+                let button = (*button).into();
+
+                return digital[button]
+                    && !ActiveChord::suppresses::<usize>(chords, button, &Default::default());
+            }
+
+            BoundButton::Analog { axis, threshold } => {
+                let value = analog[(*axis).into()];
+
+                if *threshold < 0f32 {
+                    return value < *threshold;
+                }
+
+                return value > *threshold;
+            }
+
+            BoundButton::Chord { modifiers, button } => {
+                let button = (*button).into();
+
+                if !digital[button] {
+                    return false;
+                }
+
+                if !modifiers.iter().all(|it| digital[(*it).into()]) {
+                    return false;
+                }
+
+                return !ActiveChord::suppresses(chords, button, modifiers);
+            }
         }
     }
 
@@ -208,28 +1247,50 @@ impl InputState {
         &self,
         device: &DeviceId,
         bindings: &[DigitalBinding],
+        chords: &[ActiveChord],
+        consumed: &[PhysicalInput],
+        reads: &MouseReads,
     ) -> Option<bool> {
+        let read = reads.get(self.frame, device);
         let device = self.devices.get(&device.0)?;
         let family = device.family();
-        let digital = device.digital();
-        let analog = device.analog();
+        let (digital, analog) = device.visible(consumed, read);
+        let logical = device.logical(&digital);
 
         for binding in bindings {
             match binding {
                 DigitalBinding::Keyboard(button) if family == InputFamily::Keyboard => {
-                    if self.get_digital_single(digital, analog, *button) {
+                    if self.get_digital_single(&digital, &analog, button, chords) {
+                        return Some(true);
+                    }
+                }
+
+                DigitalBinding::LogicalKeyboard(button) if family == InputFamily::Keyboard => {
+                    if self.get_digital_single(&logical, &analog, button, chords) {
                         return Some(true);
                     }
                 }
 
                 DigitalBinding::Mouse(button) if family == InputFamily::Mouse => {
-                    if self.get_digital_single(digital, analog, *button) {
+                    if self.get_digital_single(&digital, &analog, button, chords) {
                         return Some(true);
                     }
                 }
 
                 DigitalBinding::Gamepad(button) if family == InputFamily::Gamepad => {
-                    if self.get_digital_single(digital, analog, *button) {
+                    if self.get_digital_single(&digital, &analog, button, chords) {
+                        return Some(true);
+                    }
+                }
+
+                DigitalBinding::Touch(button) if family == InputFamily::Touch => {
+                    if self.get_digital_single(&digital, &analog, button, chords) {
+                        return Some(true);
+                    }
+                }
+
+                DigitalBinding::Virtual(button) if family == InputFamily::Virtual => {
+                    if self.get_digital_single(&digital, &analog, button, chords) {
                         return Some(true);
                     }
                 }
@@ -270,26 +1331,51 @@ impl InputState {
         }
     }
 
-    pub(crate) fn get_analog(&self, device: &DeviceId, bindings: &[AnalogBinding]) -> Option<f32> {
+    pub(crate) fn get_analog(
+        &self,
+        device: &DeviceId,
+        bindings: &[AnalogBinding],
+        consumed: &[PhysicalInput],
+        reads: &MouseReads,
+    ) -> Option<f32> {
+        let read = reads.get(self.frame, device);
         let device = self.devices.get(&device.0)?;
         let family = device.family();
-        let digital = device.digital();
-        let analog = device.analog();
+        let (digital, analog) = device.visible(consumed, read);
+        let logical = device.logical(&digital);
 
         let mut value = 0f32;
 
         for binding in bindings {
             match binding.axis {
                 AnalogBindingKind::Keyboard(axis) if family == InputFamily::Keyboard => {
-                    value = value.max(self.get_analog_single(digital, analog, axis));
+                    let current = self.get_analog_single(&digital, &analog, axis);
+                    value = strongest(value, binding.apply(current));
+                }
+
+                AnalogBindingKind::LogicalKeyboard(axis) if family == InputFamily::Keyboard => {
+                    let current = self.get_analog_single(&logical, &analog, axis);
+                    value = strongest(value, binding.apply(current));
                 }
 
                 AnalogBindingKind::Mouse(axis) if family == InputFamily::Mouse => {
-                    value = value.max(self.get_analog_single(digital, analog, axis));
+                    let current = self.get_analog_single(&digital, &analog, axis);
+                    value = strongest(value, binding.apply(current));
                 }
 
                 AnalogBindingKind::Gamepad(axis) if family == InputFamily::Gamepad => {
-                    value = value.max(self.get_analog_single(digital, analog, axis));
+                    let current = self.get_analog_single(&digital, &analog, axis);
+                    value = strongest(value, binding.apply(current));
+                }
+
+                AnalogBindingKind::Touch(axis) if family == InputFamily::Touch => {
+                    let current = self.get_analog_single(&digital, &analog, axis);
+                    value = strongest(value, binding.apply(current));
+                }
+
+                AnalogBindingKind::Virtual(axis) if family == InputFamily::Virtual => {
+                    let current = self.get_analog_single(&digital, &analog, axis);
+                    value = strongest(value, binding.apply(current));
                 }
 
                 _ => continue,
@@ -303,22 +1389,47 @@ impl InputState {
         &self,
         device: &DeviceId,
         bindings: &[VectorBinding],
+        consumed: &[PhysicalInput],
+        reads: &MouseReads,
     ) -> Option<[f32; 2]> {
+        let id = device;
+        let read = reads.get(self.frame, device);
         let device = self.devices.get(&device.0)?;
         let family = device.family();
-        let digital = device.digital();
-        let analog = device.analog();
+        let (digital, analog) = device.visible(consumed, read);
+        let logical = device.logical(&digital);
 
         let mut value = [0f32; 2];
         let mut value_len_sqr = 0f32;
 
         for binding in bindings {
+            if let Some(ratchet) = &binding.ratchet
+                && self.get_digital(id, slice::from_ref(ratchet), &[], consumed, reads)
+                    != Some(true)
+            {
+                continue;
+            }
+
             match binding.axes {
                 VectorBindingKind::Keyboard { x, y } if family == InputFamily::Keyboard => {
-                    let current = [
-                        self.get_analog_single(digital, analog, x),
-                        self.get_analog_single(digital, analog, y),
-                    ];
+                    let current = binding.apply([
+                        self.get_analog_single(&digital, &analog, x),
+                        self.get_analog_single(&digital, &analog, y),
+                    ]);
+
+                    let current_len_sqr = length_squared(current);
+
+                    if current_len_sqr > value_len_sqr {
+                        value = current;
+                        value_len_sqr = current_len_sqr;
+                    }
+                }
+
+                VectorBindingKind::LogicalKeyboard { x, y } if family == InputFamily::Keyboard => {
+                    let current = binding.apply([
+                        self.get_analog_single(&logical, &analog, x),
+                        self.get_analog_single(&logical, &analog, y),
+                    ]);
 
                     let current_len_sqr = length_squared(current);
 
@@ -329,10 +1440,10 @@ impl InputState {
                 }
 
                 VectorBindingKind::Mouse { x, y } if family == InputFamily::Mouse => {
-                    let current = [
-                        self.get_analog_single(digital, analog, x),
-                        self.get_analog_single(digital, analog, y),
-                    ];
+                    let current = binding.apply([
+                        self.get_analog_single(&digital, &analog, x),
+                        self.get_analog_single(&digital, &analog, y),
+                    ]);
 
                     let current_len_sqr = length_squared(current);
 
@@ -343,10 +1454,38 @@ impl InputState {
                 }
 
                 VectorBindingKind::Gamepad { x, y } if family == InputFamily::Gamepad => {
-                    let current = [
-                        self.get_analog_single(digital, analog, x),
-                        self.get_analog_single(digital, analog, y),
-                    ];
+                    let current = binding.apply([
+                        self.get_analog_single(&digital, &analog, x),
+                        self.get_analog_single(&digital, &analog, y),
+                    ]);
+
+                    let current_len_sqr = length_squared(current);
+
+                    if current_len_sqr > value_len_sqr {
+                        value = current;
+                        value_len_sqr = current_len_sqr;
+                    }
+                }
+
+                VectorBindingKind::Touch { x, y } if family == InputFamily::Touch => {
+                    let current = binding.apply([
+                        self.get_analog_single(&digital, &analog, x),
+                        self.get_analog_single(&digital, &analog, y),
+                    ]);
+
+                    let current_len_sqr = length_squared(current);
+
+                    if current_len_sqr > value_len_sqr {
+                        value = current;
+                        value_len_sqr = current_len_sqr;
+                    }
+                }
+
+                VectorBindingKind::Virtual { x, y } if family == InputFamily::Virtual => {
+                    let current = binding.apply([
+                        self.get_analog_single(&digital, &analog, x),
+                        self.get_analog_single(&digital, &analog, y),
+                    ]);
 
                     let current_len_sqr = length_squared(current);
 
@@ -363,3 +1502,130 @@ impl InputState {
         return Some(value);
     }
 }
+
+/// Whichever value is further from zero, so negative axes aren't lost.
+fn strongest(a: f32, b: f32) -> f32 {
+    return if b.abs() > a.abs() { b } else { a };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mouse_axes(state: &InputState) -> Vec<f32> {
+        return state.raw_devices().next().unwrap().3.to_vec();
+    }
+
+    #[test]
+    fn mouse_deltas_accumulate_until_next_frame() {
+        let mut state = InputState::new();
+        let mouse = state.connect_mouse();
+
+        state.set_mouse_axis(&mouse, MouseAxis::DeltaX, 2.0);
+        state.set_mouse_axis(&mouse, MouseAxis::DeltaX, 3.0);
+        state.set_mouse_axis(&mouse, MouseAxis::WheelY, -1.0);
+
+        let axes = mouse_axes(&state);
+
+        assert_eq!(axes[MouseAxis::DeltaX as usize], 5.0);
+        assert_eq!(axes[MouseAxis::WheelY as usize], -1.0);
+
+        state.next_frame();
+
+        let axes = mouse_axes(&state);
+
+        assert_eq!(axes[MouseAxis::DeltaX as usize], 0.0);
+        assert_eq!(axes[MouseAxis::WheelY as usize], 0.0);
+    }
+
+    #[test]
+    fn every_manager_sees_the_movement_since_its_last_update() {
+        use crate::{
+            binding::{BindingList, map::BindingMap},
+            manager::{InputFrame, InputManager},
+        };
+
+        let name = InternString::new("look");
+
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            name.clone(),
+            BindingList::Analog(vec![AnalogBinding {
+                axis: AnalogBindingKind::Mouse(BoundAxis::Analog(MouseAxis::DeltaX)),
+                deadzone: 0f32,
+                scale: 1f32,
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+            }]),
+        );
+
+        let look = |manager: &InputManager| {
+            let Some(InputFrame::Analog(frame)) = manager.get_binding(&name) else {
+                panic!();
+            };
+
+            return frame.value;
+        };
+
+        let mut state = InputState::new();
+        let mouse = state.connect_mouse();
+
+        let mut first = InputManager::new(bindings.clone());
+        let mut second = InputManager::new(bindings);
+        first.connect_device(mouse);
+        second.connect_device(mouse);
+
+        state.set_mouse_axis(&mouse, MouseAxis::DeltaX, 0.25);
+        state.set_mouse_axis(&mouse, MouseAxis::DeltaX, 0.5);
+        first.update(&state, 0.1);
+        second.update(&state, 0.1);
+
+        assert_eq!(look(&first), 0.75);
+        assert_eq!(look(&second), 0.75);
+
+        // Without calling `next_frame`, the movement still only counts once.
+        first.update(&state, 0.1);
+
+        assert_eq!(look(&first), 0f32);
+
+        state.set_mouse_axis(&mouse, MouseAxis::DeltaX, -0.5);
+        first.update(&state, 0.1);
+
+        assert_eq!(look(&first), -0.5);
+
+        state.next_frame();
+        state.set_mouse_axis(&mouse, MouseAxis::DeltaX, 0.25);
+        first.update(&state, 0.1);
+        second.update(&state, 0.1);
+
+        assert_eq!(look(&first), 0.25);
+        assert_eq!(look(&second), 0.25);
+    }
+
+    #[test]
+    fn normalized_position_follows_viewport() {
+        let mut state = InputState::new();
+        let mouse = state.connect_mouse();
+
+        state.set_mouse_axis(&mouse, MouseAxis::X, 320.0);
+        state.set_mouse_axis(&mouse, MouseAxis::Y, 120.0);
+
+        // Without a viewport there's nothing to normalize against.
+        assert_eq!(mouse_axes(&state)[MouseAxis::NormalizedX as usize], 0.0);
+
+        state.set_mouse_viewport(&mouse, [640.0, 480.0]);
+
+        let axes = mouse_axes(&state);
+
+        assert_eq!(axes[MouseAxis::NormalizedX as usize], 0.5);
+        assert_eq!(axes[MouseAxis::NormalizedY as usize], 0.25);
+
+        assert!(!state.set_mouse_axis(&mouse, MouseAxis::NormalizedX, 1.0));
+
+        state.next_frame();
+
+        assert_eq!(mouse_axes(&state)[MouseAxis::X as usize], 320.0);
+    }
+}
@@ -0,0 +1,259 @@
+use std::{collections::VecDeque, ops::Range};
+
+use bitvec::{array::BitArray, slice::BitSlice};
+use serde::{Deserialize, Serialize};
+
+use crate::binding::button::KeyboardButton;
+
+/// Keys that still reach bindings while text input is active, so menus can submit or close the text box.
+const PASSTHROUGH: [KeyboardButton; 3] = [
+    KeyboardButton::Escape,
+    KeyboardButton::Enter,
+    KeyboardButton::NumpadEnter,
+];
+
+/// Text that an IME is still composing, which hasn't been committed yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextComposition {
+    pub text: String,
+    /// The cursor, or the selected part of the text, as a byte range into `text`.
+    pub cursor: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TextEvent {
+    /// Text that was typed, or committed by an IME.
+    Commit {
+        text: String,
+    },
+    CompositionStart,
+    CompositionUpdate(TextComposition),
+    /// The composition finished, either because it was committed or cancelled.
+    CompositionEnd,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TextInput {
+    pub(crate) active: bool,
+    events: VecDeque<TextEvent>,
+    composition: Option<TextComposition>,
+    /// Keys held while text input was active, which stay hidden from bindings until they're released,
+    /// so closing the text box doesn't press whatever was still held.
+    hidden: BitArray<[u8; 15]>,
+}
+
+impl TextInput {
+    pub(crate) fn set_active(&mut self, active: bool, keys: &BitSlice<u8>) {
+        self.active = active;
+
+        if active {
+            self.hidden.copy_from_bitslice(keys);
+        } else {
+            self.events.clear();
+            self.composition = None;
+        }
+    }
+
+    pub(crate) fn key_changed(&mut self, key: KeyboardButton, pressed: bool) {
+        if !pressed || self.active {
+            self.hidden.set(key as usize, pressed);
+        }
+    }
+
+    pub(crate) fn composition(&self) -> Option<&TextComposition> {
+        return self.composition.as_ref();
+    }
+
+    pub(crate) fn push(&mut self, event: TextEvent) -> bool {
+        if !self.active {
+            return false;
+        }
+
+        match &event {
+            TextEvent::Commit { .. } => {}
+            TextEvent::CompositionStart => {
+                self.composition = Some(TextComposition {
+                    text: String::new(),
+                    cursor: 0..0,
+                });
+            }
+            TextEvent::CompositionUpdate(composition) => {
+                if !composition.text.is_char_boundary(composition.cursor.start)
+                    || !composition.text.is_char_boundary(composition.cursor.end)
+                    || composition.cursor.start > composition.cursor.end
+                {
+                    return false;
+                }
+
+                self.composition = Some(composition.clone());
+            }
+            TextEvent::CompositionEnd => self.composition = None,
+        }
+
+        self.events.push_back(event);
+
+        return true;
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<TextEvent> {
+        return self.events.pop_front();
+    }
+
+    pub(crate) fn drain(&mut self) -> Vec<TextEvent> {
+        return self.events.drain(..).collect();
+    }
+
+    /// The keys bindings are allowed to see, or `None` if they can see every key.
+    pub(crate) fn mask(&self, keys: &BitSlice<u8>) -> Option<BitArray<[u8; 15]>> {
+        if !self.active {
+            if self.hidden.not_any() {
+                return None;
+            }
+
+            let mut masked = BitArray::new([0; 15]);
+
+            masked.copy_from_bitslice(keys);
+
+            for key in self.hidden.iter_ones() {
+                masked.set(key, false);
+            }
+
+            // Bindings could already see these while text input was active.
+            for key in PASSTHROUGH {
+                masked.set(key as usize, keys[key as usize]);
+            }
+
+            return Some(masked);
+        }
+
+        let mut masked = BitArray::new([0; 15]);
+
+        for key in PASSTHROUGH {
+            masked.set(key as usize, keys[key as usize]);
+        }
+
+        return Some(masked);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{BindingList, BoundButton, DigitalBinding, map::BindingMap},
+        manager::{InputFrame, InputManager},
+        state::InputState,
+    };
+    use pawkit_interner::InternString;
+
+    #[test]
+    fn text_input_suppresses_bindings() {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("jump"),
+            BindingList::Digital(vec![DigitalBinding::Keyboard(BoundButton::Digital(
+                KeyboardButton::Space,
+            ))]),
+        );
+        bindings.register_binding(
+            InternString::new("submit"),
+            BindingList::Digital(vec![DigitalBinding::Keyboard(BoundButton::Digital(
+                KeyboardButton::Enter,
+            ))]),
+        );
+
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        let mut manager = InputManager::new(bindings);
+        manager.connect_device(keyboard);
+
+        let pressed = |manager: &InputManager, name: &str| {
+            let Some(InputFrame::Digital(frame)) = manager.get_binding(&InternString::new(name))
+            else {
+                panic!("expected a digital frame");
+            };
+
+            return frame.pressed;
+        };
+
+        state.set_text_input_active(&keyboard, true);
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        state.set_keyboard_button(&keyboard, KeyboardButton::Enter, true);
+        manager.update(&state, 0.1);
+
+        assert!(!pressed(&manager, "jump"));
+        assert!(pressed(&manager, "submit"));
+
+        // Space was pressed while typing, so closing the text box doesn't jump until it's pressed again.
+        state.set_text_input_active(&keyboard, false);
+        manager.update(&state, 0.1);
+
+        assert!(!pressed(&manager, "jump"));
+        assert!(pressed(&manager, "submit"));
+
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, false);
+        manager.update(&state, 0.1);
+        state.set_keyboard_button(&keyboard, KeyboardButton::Space, true);
+        manager.update(&state, 0.1);
+
+        assert!(pressed(&manager, "jump"));
+    }
+
+    #[test]
+    fn composition_events() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+
+        assert!(!state.push_text_event(
+            &keyboard,
+            TextEvent::Commit {
+                text: "ignored".into()
+            }
+        ));
+
+        state.set_text_input_active(&keyboard, true);
+        state.push_text_event(&keyboard, TextEvent::CompositionStart);
+
+        let update = TextComposition {
+            text: "にほ".into(),
+            cursor: 6..6,
+        };
+
+        assert!(state.push_text_event(&keyboard, TextEvent::CompositionUpdate(update.clone())));
+        assert_eq!(state.text_composition(&keyboard), Some(&update));
+
+        // Cursors have to land on character boundaries.
+        assert!(!state.push_text_event(
+            &keyboard,
+            TextEvent::CompositionUpdate(TextComposition {
+                text: "にほ".into(),
+                cursor: 1..1,
+            })
+        ));
+
+        state.push_text_event(
+            &keyboard,
+            TextEvent::Commit {
+                text: "日本".into(),
+            },
+        );
+        state.push_text_event(&keyboard, TextEvent::CompositionEnd);
+
+        assert_eq!(state.text_composition(&keyboard), None);
+        assert_eq!(
+            state.drain_text_events(&keyboard),
+            [
+                TextEvent::CompositionStart,
+                TextEvent::CompositionUpdate(update),
+                TextEvent::Commit {
+                    text: "日本".into()
+                },
+                TextEvent::CompositionEnd,
+            ]
+        );
+        assert!(state.drain_text_events(&keyboard).is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+use bitvec::slice::BitSlice;
+
+use crate::{
+    binding::{axis::TouchAxis, button::TouchButton},
+    length_squared,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchContact {
+    /// Identifies the finger for as long as it's on the screen.
+    pub id: u64,
+    /// The position normalized to the screen, from 0 to 1, with y pointing down.
+    pub position: [f32; 2],
+    pub pressure: f32,
+}
+
+/// Distances are normalized to the screen, like the contact positions.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchSettings {
+    /// A finger released within this distance of where it started is a tap.
+    pub tap_distance: f32,
+    /// A finger released at least this far from where it started is a swipe.
+    pub swipe_distance: f32,
+    /// A finger held down for more frames than this isn't a tap, even if it didn't move.
+    pub max_tap_frames: u32,
+}
+
+impl Default for TouchSettings {
+    fn default() -> Self {
+        return Self {
+            tap_distance: 0.02,
+            swipe_distance: 0.1,
+            max_tap_frames: 15,
+        };
+    }
+}
+
+/// Tracks the fingers on a touch device, and turns them into gesture buttons and axes.
+///
+/// Taps and swipes are only recognized when a single finger was used,
+/// so lifting a finger after a pinch doesn't also count as a tap.
+pub(crate) struct TouchTracker {
+    pub(crate) settings: TouchSettings,
+    contacts: Vec<TouchContact>,
+    origins: Vec<[f32; 2]>,
+    multi_touched: bool,
+    /// How many frames have ended since the first finger touched down.
+    held_frames: u32,
+}
+
+impl TouchTracker {
+    pub(crate) fn new() -> Self {
+        return Self {
+            settings: TouchSettings::default(),
+            contacts: vec![],
+            origins: vec![],
+            multi_touched: false,
+            held_frames: 0,
+        };
+    }
+
+    pub(crate) fn contacts(&self) -> &[TouchContact] {
+        return &self.contacts;
+    }
+
+    /// The distance between, and midpoint of, the first two fingers.
+    fn pair(&self) -> Option<(u64, u64, f32, [f32; 2])> {
+        let [a, b, ..] = self.contacts.as_slice() else {
+            return None;
+        };
+
+        let distance =
+            length_squared([a.position[0] - b.position[0], a.position[1] - b.position[1]]).sqrt();
+
+        let midpoint = [
+            (a.position[0] + b.position[0]) / 2f32,
+            (a.position[1] + b.position[1]) / 2f32,
+        ];
+
+        return Some((a.id, b.id, distance, midpoint));
+    }
+
+    pub(crate) fn set_contact(
+        &mut self,
+        contact: TouchContact,
+        buttons: &mut BitSlice<u8>,
+        axes: &mut [f32],
+    ) {
+        let before = self.pair();
+
+        match self.contacts.iter().position(|it| it.id == contact.id) {
+            Some(index) => self.contacts[index] = contact,
+            None => {
+                if self.contacts.is_empty() {
+                    self.held_frames = 0;
+                }
+
+                self.contacts.push(contact);
+                self.origins.push(contact.position);
+            }
+        }
+
+        let after = self.pair();
+
+        if let (Some(before), Some(after)) = (before, after)
+            && before.0 == after.0
+            && before.1 == after.1
+        {
+            axes[TouchAxis::Pinch as usize] += after.2 - before.2;
+            axes[TouchAxis::PanX as usize] += after.3[0] - before.3[0];
+            axes[TouchAxis::PanY as usize] += after.3[1] - before.3[1];
+        }
+
+        if self.contacts.len() >= 2 {
+            self.multi_touched = true;
+        }
+
+        self.refresh(buttons, axes);
+    }
+
+    pub(crate) fn remove_contact(
+        &mut self,
+        id: u64,
+        buttons: &mut BitSlice<u8>,
+        axes: &mut [f32],
+    ) -> bool {
+        let Some(index) = self.contacts.iter().position(|it| it.id == id) else {
+            return false;
+        };
+
+        let contact = self.contacts.remove(index);
+        let origin = self.origins.remove(index);
+
+        if self.contacts.is_empty() {
+            if !self.multi_touched {
+                self.recognize(contact.position, origin, buttons);
+            }
+
+            self.multi_touched = false;
+        }
+
+        self.refresh(buttons, axes);
+
+        return true;
+    }
+
+    fn recognize(&self, position: [f32; 2], origin: [f32; 2], buttons: &mut BitSlice<u8>) {
+        let [x, y] = [position[0] - origin[0], position[1] - origin[1]];
+        let distance = length_squared([x, y]).sqrt();
+
+        if distance <= self.settings.tap_distance {
+            if self.held_frames > self.settings.max_tap_frames {
+                return;
+            }
+
+            buttons.set(TouchButton::Tap as usize, true);
+            return;
+        }
+
+        if distance < self.settings.swipe_distance {
+            return;
+        }
+
+        let button = if x.abs() >= y.abs() {
+            if x < 0f32 {
+                TouchButton::SwipeLeft
+            } else {
+                TouchButton::SwipeRight
+            }
+        } else if y < 0f32 {
+            TouchButton::SwipeUp
+        } else {
+            TouchButton::SwipeDown
+        };
+
+        buttons.set(button as usize, true);
+    }
+
+    fn refresh(&self, buttons: &mut BitSlice<u8>, axes: &mut [f32]) {
+        buttons.set(TouchButton::Touch as usize, !self.contacts.is_empty());
+        buttons.set(TouchButton::MultiTouch as usize, self.contacts.len() >= 2);
+
+        let (position, pressure) = match self.contacts.first() {
+            Some(contact) => (contact.position, contact.pressure),
+            None => ([0f32; 2], 0f32),
+        };
+
+        axes[TouchAxis::X as usize] = position[0];
+        axes[TouchAxis::Y as usize] = position[1];
+        axes[TouchAxis::Pressure as usize] = pressure;
+    }
+
+    /// Clears the gestures that only last for a single frame.
+    pub(crate) fn end_frame(&mut self, buttons: &mut BitSlice<u8>, axes: &mut [f32]) {
+        if !self.contacts.is_empty() {
+            self.held_frames = self.held_frames.saturating_add(1);
+        }
+
+        for button in [
+            TouchButton::Tap,
+            TouchButton::SwipeLeft,
+            TouchButton::SwipeRight,
+            TouchButton::SwipeUp,
+            TouchButton::SwipeDown,
+        ] {
+            buttons.set(button as usize, false);
+        }
+
+        for axis in [TouchAxis::Pinch, TouchAxis::PanX, TouchAxis::PanY] {
+            axes[axis as usize] = 0f32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::InputState;
+
+    fn contact(id: u64, x: f32, y: f32) -> TouchContact {
+        return TouchContact {
+            id,
+            position: [x, y],
+            pressure: 1.0,
+        };
+    }
+
+    fn button(state: &InputState, touch: &crate::DeviceId, button: TouchButton) -> bool {
+        use crate::binding::{BoundButton, DigitalBinding};
+
+        return state.get_digital(
+            touch,
+            &[DigitalBinding::Touch(BoundButton::Digital(button))],
+            &[],
+            &[],
+            &Default::default(),
+        ) == Some(true);
+    }
+
+    #[test]
+    fn tap_lasts_one_frame() {
+        let mut state = InputState::new();
+        let touch = state.connect_touch();
+
+        state.set_touch_contact(&touch, contact(1, 0.5, 0.5));
+        assert!(button(&state, &touch, TouchButton::Touch));
+
+        state.set_touch_contact(&touch, contact(1, 0.51, 0.5));
+        state.remove_touch_contact(&touch, 1);
+
+        assert!(button(&state, &touch, TouchButton::Tap));
+        assert!(!button(&state, &touch, TouchButton::Touch));
+
+        state.next_frame();
+        assert!(!button(&state, &touch, TouchButton::Tap));
+    }
+
+    #[test]
+    fn long_press_is_not_a_tap() {
+        let mut state = InputState::new();
+        let touch = state.connect_touch();
+
+        state.set_touch_contact(&touch, contact(1, 0.5, 0.5));
+
+        for _ in 0..60 {
+            state.next_frame();
+        }
+
+        state.remove_touch_contact(&touch, 1);
+
+        assert!(!button(&state, &touch, TouchButton::Tap));
+    }
+
+    #[test]
+    fn swipe_direction() {
+        let mut state = InputState::new();
+        let touch = state.connect_touch();
+
+        state.set_touch_contact(&touch, contact(1, 0.5, 0.5));
+        state.set_touch_contact(&touch, contact(1, 0.45, 0.8));
+        state.remove_touch_contact(&touch, 1);
+
+        assert!(button(&state, &touch, TouchButton::SwipeDown));
+        assert!(!button(&state, &touch, TouchButton::Tap));
+    }
+
+    #[test]
+    fn pinch_and_pan() {
+        let mut state = InputState::new();
+        let touch = state.connect_touch();
+
+        state.set_touch_contact(&touch, contact(1, 0.4, 0.5));
+        state.set_touch_contact(&touch, contact(2, 0.6, 0.5));
+        state.next_frame();
+
+        state.set_touch_contact(&touch, contact(1, 0.3, 0.6));
+        state.set_touch_contact(&touch, contact(2, 0.7, 0.6));
+
+        let axes = axes(&state, &touch);
+
+        assert!((axes[TouchAxis::Pinch as usize] - 0.2).abs() < 1e-5);
+        assert!(axes[TouchAxis::PanX as usize].abs() < 1e-5);
+        assert!((axes[TouchAxis::PanY as usize] - 0.1).abs() < 1e-5);
+
+        // Lifting the fingers after a pinch isn't a tap.
+        state.remove_touch_contact(&touch, 1);
+        state.remove_touch_contact(&touch, 2);
+
+        assert!(!button(&state, &touch, TouchButton::Tap));
+    }
+
+    fn axes(state: &InputState, touch: &crate::DeviceId) -> Vec<f32> {
+        let (_, _, _, axes) = state.raw_devices().find(|it| it.0 == *touch).unwrap();
+
+        return axes.to_vec();
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use pawkit_interner::InternString;
+use serde::{Deserialize, Serialize};
+
+use crate::history::ActionValue;
+
+/// How many buttons and axes a virtual device has.
+/// What each one means is up to the code driving it, such as an on-screen joystick or a bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct VirtualLayout {
+    pub buttons: u8,
+    pub axes: u8,
+}
+
+/// The parts of a virtual device that aren't buttons or axes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VirtualDevice {
+    pub(crate) layout: VirtualLayout,
+    /// Values set on actions by name, which take the place of whatever the action's bindings read.
+    pub(crate) actions: HashMap<InternString, ActionValue>,
+}
+
+impl VirtualDevice {
+    pub(crate) fn new(layout: VirtualLayout) -> Self {
+        return Self {
+            layout,
+            actions: HashMap::new(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        binding::{
+            AnalogBinding, AnalogBindingKind, BindingList, BoundAxis, BoundButton, DigitalBinding,
+            axis::VirtualAxis, button::VirtualButton, map::BindingMap,
+        },
+        manager::{InputFrame, InputManager},
+        recording::{InputEvent, InputRecording, InputReplay},
+        state::{InputFamily, InputState},
+    };
+
+    fn bindings() -> BindingMap {
+        let mut bindings = BindingMap::new();
+
+        bindings.register_binding(
+            InternString::new("jump"),
+            BindingList::Digital(vec![DigitalBinding::Virtual(BoundButton::Digital(
+                VirtualButton(2),
+            ))]),
+        );
+
+        bindings.register_binding(
+            InternString::new("throttle"),
+            BindingList::Analog(vec![AnalogBinding {
+                axis: AnalogBindingKind::Virtual(BoundAxis::Analog(VirtualAxis(0))),
+                deadzone: 0f32,
+                scale: 1f32,
+                deadzone_shape: Default::default(),
+                outer_deadzone: None,
+                curve: Default::default(),
+            }]),
+        );
+
+        return bindings;
+    }
+
+    fn frame(manager: &InputManager, name: &str) -> InputFrame {
+        return manager.get_binding(&InternString::new(name)).unwrap();
+    }
+
+    #[test]
+    fn virtual_buttons_and_axes_drive_bindings() {
+        let mut state = InputState::new();
+        state.start_recording();
+
+        let device = state.connect_virtual(VirtualLayout {
+            buttons: 4,
+            axes: 1,
+        });
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(device);
+
+        assert_eq!(state.device_family(&device), Some(InputFamily::Virtual));
+        assert!(state.set_virtual_button(&device, VirtualButton(2), true));
+        assert!(state.set_virtual_axis(&device, VirtualAxis(0), 0.5));
+        assert!(!state.set_virtual_button(&device, VirtualButton(4), true));
+        assert!(!state.set_virtual_axis(&device, VirtualAxis(1), 1f32));
+
+        manager.update(&state, 0.1);
+
+        let InputFrame::Digital(jump) = frame(&manager, "jump") else {
+            panic!();
+        };
+        let InputFrame::Analog(throttle) = frame(&manager, "throttle") else {
+            panic!();
+        };
+
+        assert!(jump.just_pressed);
+        assert_eq!(throttle.value, 0.5);
+
+        // Replaying the recording recreates the device with the same layout and inputs.
+        let recording = state.stop_recording().unwrap();
+
+        let mut replayed = InputState::new();
+        InputReplay::new(recording).step(&mut replayed);
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(device);
+        manager.update(&replayed, 0.1);
+
+        assert_eq!(
+            replayed.virtual_layout(&device),
+            state.virtual_layout(&device)
+        );
+        assert!(matches!(frame(&manager, "jump"), InputFrame::Digital(it) if it.pressed));
+    }
+
+    #[test]
+    fn virtual_actions_bypass_bindings() {
+        let mut state = InputState::new();
+        let keyboard = state.connect_keyboard();
+        let bot = state.connect_virtual(VirtualLayout::default());
+
+        let jump = InternString::new("jump");
+        let throttle = InternString::new("throttle");
+
+        let mut manager = InputManager::new(bindings());
+        manager.connect_device(keyboard);
+        manager.connect_device(bot);
+
+        assert!(state.set_virtual_action(&bot, jump.clone(), ActionValue::Digital(true)));
+        assert!(state.set_virtual_action(&bot, throttle.clone(), ActionValue::Analog(0.75)));
+        assert!(!state.set_virtual_action(&keyboard, jump.clone(), ActionValue::Digital(true)));
+
+        manager.update(&state, 0.1);
+
+        let InputFrame::Digital(jumped) = frame(&manager, "jump") else {
+            panic!();
+        };
+        let InputFrame::Analog(pushed) = frame(&manager, "throttle") else {
+            panic!();
+        };
+
+        assert!(jumped.just_pressed);
+        assert_eq!(pushed.value, 0.75);
+
+        assert!(state.clear_virtual_action(&bot, &jump));
+        assert!(!state.clear_virtual_action(&bot, &jump));
+
+        manager.update(&state, 0.1);
+
+        let InputFrame::Digital(jumped) = frame(&manager, "jump") else {
+            panic!();
+        };
+
+        assert!(jumped.just_released);
+
+        // Actions set before recording are in the snapshot, and changes after it are recorded.
+        assert!(state.set_virtual_action(&bot, jump.clone(), ActionValue::Digital(true)));
+
+        state.start_recording();
+
+        assert!(state.clear_virtual_action(&bot, &jump));
+        assert!(state.set_virtual_action(&bot, throttle.clone(), ActionValue::Vector([0.5, 1f32])));
+
+        let recording = state.stop_recording().unwrap();
+        let snapshot = InputEvent::SetVirtualAction(bot, jump.clone(), ActionValue::Digital(true));
+
+        assert!(recording.events().iter().any(|(_, it)| *it == snapshot));
+
+        let recording = InputRecording::load(&recording.save()).unwrap();
+
+        let mut replayed = InputState::new();
+        InputReplay::new(recording).step(&mut replayed);
+
+        assert_eq!(replayed.virtual_action(&bot, &jump), None);
+        assert_eq!(
+            replayed.virtual_action(&bot, &throttle),
+            Some(ActionValue::Vector([0.5, 1f32]))
+        );
+    }
+}